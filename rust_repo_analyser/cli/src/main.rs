@@ -1,4 +1,21 @@
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Args, CommandFactory, FromArgMatches, Parser};
+use repo_analyser::clone_cache::CloneCache;
+use repo_analyser::commit_class::CommitClassifier;
+use repo_analyser::commit_filter::CommitFilter;
+use repo_analyser::config::PipelineConfig;
+use repo_analyser::credentials::CloneCredentials;
+use repo_analyser::entrypoint::{CloneRunOptions, FileSource};
+use repo_analyser::manifest::Manifest;
+use repo_analyser::timeline::TimelineWindow;
+use repo_analyser::{
+    open_store, AnalysisWindow, GitAnalyzer, GraphStore, IngestMode, MergeStrategy, PathFilter,
+};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+const DEFAULT_CONFIG: &str = "pipeline.toml";
 
 #[derive(Parser)]
 #[command(name = "scraper")]
@@ -7,11 +24,25 @@ struct Cli {
     #[command(subcommand)]
     command: Commands,
 
-    #[arg(short, long, default_value = "bolt://localhost:7687")]
+    #[arg(long, global = true, default_value = "bolt://localhost:7687")]
     neo4j_uri: String,
 
     #[arg(long, default_value = "", help = "Neo4j database name (optional)")]
     neo4j_database: String,
+
+    #[arg(
+        long,
+        default_value = "neo4j",
+        help = "Graph store backend: 'neo4j', 'memory' (in-process, nothing persisted) or 'sqlite:<path.db>'"
+    )]
+    store: String,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Pipeline config (TOML) whose settings apply where no flag is given; defaults to pipeline.toml if present"
+    )]
+    config: Option<String>,
 }
 
 #[derive(Parser)]
@@ -27,6 +58,9 @@ enum Commands {
     CodeSceneAnalyze(CodeSceneAnalyzeArgs),
     ExportHubScores(ExportHubScoresArgs),
     RecomputeHubScores(RecomputeHubScoresArgs),
+    Timeline(TimelineArgs),
+    Szz(SzzArgs),
+    Status(StatusArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -52,6 +86,158 @@ struct GithubArgs {
     token: String,
 }
 
+/// Which files and commits are analysed and how, shared by every command that walks a
+/// repository's history.
+#[derive(Args, Debug)]
+struct AnalysisArgs {
+    #[arg(
+        long,
+        default_value = ".cpp",
+        help = "Comma-separated file extensions to filter (e.g., '.cpp,.h,.hpp')"
+    )]
+    extension: String,
+
+    #[arg(long, default_value = "200")]
+    max_files_per_commit: usize,
+
+    #[arg(long, default_value = "300")]
+    max_renames_per_commit: usize,
+
+    #[arg(
+        long,
+        help = "Only analyse commits made on or after this date (YYYY-MM-DD)"
+    )]
+    since: Option<String>,
+
+    #[arg(long, help = "Only analyse commits made before this date (YYYY-MM-DD)")]
+    until: Option<String>,
+
+    #[arg(
+        long,
+        help = "Only analyse commits in this revision range (e.g., 'v1.0..v2.0')"
+    )]
+    range: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "all-parents",
+        value_parser = ["skip", "first-parent", "all-parents", "combined-diff"],
+        help = "Which changes merge commits contribute"
+    )]
+    merge_strategy: String,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Only follow the first parent of merge commits when walking history"
+    )]
+    first_parent: bool,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Only analyse paths matching these gitignore-style globs (e.g., 'src/**,include/')"
+    )]
+    include: Vec<String>,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Skip paths matching these gitignore-style globs (e.g., 'tests/,*_test.cpp')"
+    )]
+    exclude: Vec<String>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Analyse vendored, third-party and generated code instead of excluding it"
+    )]
+    keep_vendored: bool,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Skip commits whose author name or email matches these wildcard patterns (e.g., '*[bot]*,ci@*')"
+    )]
+    skip_authors: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Skip commits whose message matches this regular expression (repeatable)"
+    )]
+    skip_message: Vec<String>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Skip commits by dependabot, renovate, GitHub Actions and other bots"
+    )]
+    skip_bots: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Skip commits that only change whitespace in analysed files"
+    )]
+    skip_whitespace_only: bool,
+
+    #[arg(
+        long,
+        help = "Count consecutive commits by the same author within this many minutes as one change set"
+    )]
+    change_set_gap_minutes: Option<u32>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Also record churn with whitespace changes and formatting-only hunks left out, and score hubs on it as normalized_hub_score"
+    )]
+    normalize_churn: bool,
+
+    #[arg(
+        long,
+        help = "JSON file with rules for classifying commit messages (defaults to built-in keywords)"
+    )]
+    commit_rules: Option<String>,
+}
+
+/// How `analyse-local` and `clone` write the analysis and what else they derive from it.
+#[derive(Args, Debug)]
+struct IngestArgs {
+    #[arg(
+        long,
+        default_value = "500",
+        help = "Number of commits buffered per batched write"
+    )]
+    batch_size: usize,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Build the whole graph in memory and write it in one bulk load"
+    )]
+    bulk_load: bool,
+
+    #[arg(
+        long,
+        help = "Half-life in days for time-decayed coupling weights and hub scores"
+    )]
+    half_life_days: Option<f64>,
+
+    #[arg(
+        long,
+        help = "Link authors who change the same file within this many days into a collaboration network"
+    )]
+    collaboration_window_days: Option<u32>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Also map changed lines to functions and build a function-level co-change graph"
+    )]
+    functions: bool,
+}
+
 #[derive(Parser, Debug)]
 #[command(about = "Analyze a local Git repository and save to Neo4j", long_about = None)]
 struct AnalyseLocalArgs {
@@ -67,43 +253,105 @@ struct AnalyseLocalArgs {
     #[arg(long, default_value = "5")]
     threshold: i64,
 
-    #[arg(long, default_value = "200")]
-    max_files_per_commit: usize,
-
-    #[arg(long, default_value = "300")]
-    max_renames_per_commit: usize,
-
     #[arg(
         long,
         help = "Hub score threshold: files >= threshold go to high/, < threshold go to low/"
     )]
-    hub_threshold: f64,
+    hub_threshold: Option<f64>,
 
     #[arg(
         long,
-        default_value = ".cpp",
-        help = "Comma-separated file extensions to filter (e.g., '.cpp,.h,.hpp')"
+        default_value_t = false,
+        help = "Only analyse commits after the last analysed one and update the stored graph"
     )]
-    extension: String,
+    incremental: bool,
 
-    #[arg(long, default_value = "bolt://localhost:7687")]
-    neo4j_uri: String,
+    #[command(flatten)]
+    ingest: IngestArgs,
+
+    #[command(flatten)]
+    analysis: AnalysisArgs,
 }
 
 #[derive(Parser, Debug)]
 #[command(about = "Clone and analyze GitHub repositories from a JSON file", long_about = None)]
 struct CloneArgs {
     #[arg(short, long)]
-    input: String,
+    input: Option<String>,
 
-    #[arg(long, default_value = "./repo_cache", hide = true)]
+    #[arg(
+        long,
+        default_value = "./repo_cache",
+        help = "Directory the repositories are cloned into and reused from"
+    )]
     path: String,
 
-    #[arg(long, default_value = ".cpp")]
-    extension: String,
+    #[arg(
+        long,
+        default_value = "anonymous",
+        help = "How to authenticate clones: 'anonymous', 'token' (GITHUB_TOKEN), 'token:<ENV_VAR>', 'ssh-agent' or 'ssh-key:<path>'"
+    )]
+    credentials: String,
 
-    #[arg(long, default_value = "bolt://localhost:7687")]
-    neo4j_uri: String,
+    #[arg(
+        long,
+        help = "Shallow-clone only the last N commits of each repository"
+    )]
+    depth: Option<u32>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Partial clones that only fetch the file contents of analysed files (needs git on PATH)"
+    )]
+    blobless: bool,
+
+    #[arg(
+        long,
+        help = "Directory of local mirrors (<repo>.git or <repo>) to clone from instead of the network"
+    )]
+    mirror_dir: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "4",
+        help = "Number of repositories cloned at once"
+    )]
+    clone_jobs: usize,
+
+    #[arg(
+        long,
+        default_value = "2",
+        help = "Number of repositories analysed at once"
+    )]
+    jobs: usize,
+
+    #[arg(
+        long,
+        default_value = "clone_summary.json",
+        help = "JSON file for the per-repository success and failure summary"
+    )]
+    summary: String,
+
+    #[arg(
+        long,
+        default_value = "corpus_manifest.json",
+        help = "JSON file recording the pipeline stage of every repository, used to resume interrupted runs"
+    )]
+    manifest: String,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Ignore the stages recorded in the manifest and process every repository again"
+    )]
+    restart: bool,
+
+    #[command(flatten)]
+    ingest: IngestArgs,
+
+    #[command(flatten)]
+    analysis: AnalysisArgs,
 }
 
 #[derive(Parser)]
@@ -114,9 +362,20 @@ struct VerifyArgs {
 
     #[arg(short, long, default_value = "graph_output.json")]
     output: String,
+}
 
-    #[arg(long, default_value = "bolt://localhost:7687")]
-    neo4j_uri: String,
+#[derive(Parser, Debug)]
+#[command(about = "Print the pipeline stage of every repository in a corpus manifest", long_about = None)]
+struct StatusArgs {
+    #[arg(long, default_value = "corpus_manifest.json")]
+    manifest: String,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Only list repositories whose last attempt failed"
+    )]
+    failed: bool,
 }
 
 #[derive(Parser, Debug)]
@@ -127,26 +386,43 @@ struct CopyTopFilesArgs {
         long,
         help = "Hub score threshold: files >= threshold go to high/, < threshold go to low/"
     )]
-    score_hub_threshold: f64,
+    score_hub_threshold: Option<f64>,
 
     #[arg(short, long, default_value = "data/files")]
     output: String,
 
-    #[arg(short, long, default_value = "cpp")]
+    #[arg(short, long, default_value = ".cpp")]
     extension: String,
 
     #[arg(short, long, num_args = 0..)]
     ignore: Vec<String>,
 
-    #[arg(long, default_value = "bolt://localhost:7687")]
-    neo4j_uri: String,
+    #[arg(
+        long,
+        default_value = "./repo_cache",
+        help = "Directory the repositories are cloned into and reused from"
+    )]
+    path: String,
+
+    #[arg(
+        long,
+        default_value = "anonymous",
+        help = "How to authenticate clones: 'anonymous', 'token' (GITHUB_TOKEN), 'token:<ENV_VAR>', 'ssh-agent' or 'ssh-key:<path>'"
+    )]
+    credentials: String,
+
+    #[arg(
+        long,
+        help = "Directory of local mirrors (<repo>.git or <repo>) to clone from instead of the network"
+    )]
+    mirror_dir: Option<String>,
 }
 
 #[derive(Parser, Debug)]
 #[command(about = "Analyze file metrics from a folder", long_about = None)]
 struct MetricsArgs {
     #[arg(short, long)]
-    folder: String,
+    folder: Option<String>,
 
     #[arg(short, long, default_value = "../results/metrics.csv")]
     output: String,
@@ -187,24 +463,691 @@ struct ExportHubScoresArgs {
     #[arg(long, default_value = "../results/hub_scores.json")]
     output: String,
 
-    #[arg(long, default_value = "bolt://localhost:7687")]
-    neo4j_uri: String,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Export function-level hub scores instead of file-level ones"
+    )]
+    functions: bool,
+
+    #[arg(
+        long,
+        help = "Corpus manifest in which to mark the exported repositories as exported"
+    )]
+    manifest: Option<String>,
 }
 
 #[derive(Parser, Debug)]
 #[command(about = "Recompute hub scores for all repos with minimum coupling threshold", long_about = None)]
 struct RecomputeHubScoresArgs {
     #[arg(long, help = "Minimum coupling threshold (e.g., 0.3)")]
+    min_coupling: Option<f64>,
+}
+
+#[derive(Parser, Debug)]
+#[command(about = "Compute per-file hub score time series over sliding windows", long_about = None)]
+struct TimelineArgs {
+    #[arg(short, long)]
+    repo: String,
+
+    #[arg(
+        long,
+        help = "Window size in commits",
+        conflicts_with = "window_months"
+    )]
+    window_commits: Option<usize>,
+
+    #[arg(long, help = "Window size in calendar months")]
+    window_months: Option<u32>,
+
+    #[arg(
+        long,
+        help = "How far each window moves, in the window's unit (defaults to the window size)"
+    )]
+    step: Option<usize>,
+
+    #[arg(long, default_value = "0.0", help = "Minimum coupling threshold")]
     min_coupling: f64,
 
-    #[arg(long, default_value = "bolt://localhost:7687")]
-    neo4j_uri: String,
+    #[arg(short, long, default_value = "timeline.csv")]
+    output: String,
+
+    #[arg(long, default_value = "csv", value_parser = ["csv", "json"])]
+    format: String,
+
+    #[command(flatten)]
+    analysis: AnalysisArgs,
+}
+
+#[derive(Parser, Debug)]
+#[command(about = "Label files with the bug-introducing commits found by SZZ", long_about = None)]
+struct SzzArgs {
+    #[arg(short, long)]
+    repo: String,
+
+    #[arg(short, long, default_value = "szz.csv")]
+    output: String,
+
+    #[command(flatten)]
+    analysis: AnalysisArgs,
+}
+
+impl AnalysisArgs {
+    fn configure(&mut self, matches: &ArgMatches, config: &mut PipelineConfig) {
+        let PipelineConfig {
+            filters,
+            analysis,
+            thresholds,
+            ..
+        } = config;
+        bind(
+            matches,
+            "extension",
+            &mut self.extension,
+            &mut filters.extension,
+        );
+        bind(
+            matches,
+            "max_files_per_commit",
+            &mut self.max_files_per_commit,
+            &mut thresholds.max_files_per_commit,
+        );
+        bind(
+            matches,
+            "max_renames_per_commit",
+            &mut self.max_renames_per_commit,
+            &mut thresholds.max_renames_per_commit,
+        );
+        bind_option(matches, "since", &mut self.since, &mut filters.since);
+        bind_option(matches, "until", &mut self.until, &mut filters.until);
+        bind_option(matches, "range", &mut self.range, &mut filters.range);
+        bind(
+            matches,
+            "merge_strategy",
+            &mut self.merge_strategy,
+            &mut analysis.merge_strategy,
+        );
+        bind(
+            matches,
+            "first_parent",
+            &mut self.first_parent,
+            &mut analysis.first_parent,
+        );
+        bind(matches, "include", &mut self.include, &mut filters.include);
+        bind(matches, "exclude", &mut self.exclude, &mut filters.exclude);
+        bind(
+            matches,
+            "keep_vendored",
+            &mut self.keep_vendored,
+            &mut filters.keep_vendored,
+        );
+        bind(
+            matches,
+            "skip_authors",
+            &mut self.skip_authors,
+            &mut filters.skip_authors,
+        );
+        bind(
+            matches,
+            "skip_message",
+            &mut self.skip_message,
+            &mut filters.skip_message,
+        );
+        bind(
+            matches,
+            "skip_bots",
+            &mut self.skip_bots,
+            &mut filters.skip_bots,
+        );
+        bind(
+            matches,
+            "skip_whitespace_only",
+            &mut self.skip_whitespace_only,
+            &mut filters.skip_whitespace_only,
+        );
+        bind_option(
+            matches,
+            "change_set_gap_minutes",
+            &mut self.change_set_gap_minutes,
+            &mut analysis.change_set_gap_minutes,
+        );
+        bind(
+            matches,
+            "normalize_churn",
+            &mut self.normalize_churn,
+            &mut analysis.normalize_churn,
+        );
+        bind_option(
+            matches,
+            "commit_rules",
+            &mut self.commit_rules,
+            &mut analysis.commit_rules,
+        );
+    }
+
+    /// An analyser of the repository at `repo_path` with these settings, printing the ones
+    /// in effect.
+    fn to_analyzer(&self, repo_path: String) -> Result<GitAnalyzer, String> {
+        println!("Extension: {}", self.extension);
+        println!(
+            "Max files per commit: {}, max renames per commit: {}",
+            self.max_files_per_commit, self.max_renames_per_commit
+        );
+        let window = AnalysisWindow::parse(
+            self.since.as_deref(),
+            self.until.as_deref(),
+            self.range.as_deref(),
+        )?;
+        if let Some(window) = window.describe() {
+            println!("Window: {}", window);
+        }
+        if let Some(minutes) = self.change_set_gap_minutes {
+            println!("Change-set gap: {} minutes", minutes);
+        }
+        if self.normalize_churn {
+            println!("Churn normalization: on");
+        }
+        let merge_strategy = merge_strategy(&self.merge_strategy, self.first_parent)?;
+        let path_filter = path_filter(&self.include, &self.exclude, self.keep_vendored);
+        let commit_filter = commit_filter(
+            &self.skip_authors,
+            &self.skip_message,
+            self.skip_bots,
+            self.skip_whitespace_only,
+        )?;
+        let classifier = commit_classifier(self.commit_rules.as_deref())?;
+
+        Ok(
+            GitAnalyzer::new(repo_path, "null".to_string(), self.extension.clone())
+                .with_window(window)
+                .with_classifier(classifier)
+                .with_change_set_gap(self.change_set_gap_minutes)
+                .with_merge_strategy(merge_strategy)
+                .with_first_parent(self.first_parent)
+                .with_path_filter(path_filter)
+                .with_commit_filter(commit_filter)
+                .with_normalized_churn(self.normalize_churn)
+                .with_commit_limits(self.max_files_per_commit, self.max_renames_per_commit),
+        )
+    }
+}
+
+impl IngestArgs {
+    fn configure(&mut self, matches: &ArgMatches, config: &mut PipelineConfig) {
+        let PipelineConfig {
+            analysis, scoring, ..
+        } = config;
+        bind(
+            matches,
+            "batch_size",
+            &mut self.batch_size,
+            &mut analysis.batch_size,
+        );
+        bind(
+            matches,
+            "bulk_load",
+            &mut self.bulk_load,
+            &mut analysis.bulk_load,
+        );
+        bind_option(
+            matches,
+            "half_life_days",
+            &mut self.half_life_days,
+            &mut scoring.half_life_days,
+        );
+        bind_option(
+            matches,
+            "collaboration_window_days",
+            &mut self.collaboration_window_days,
+            &mut analysis.collaboration_window_days,
+        );
+        bind(
+            matches,
+            "functions",
+            &mut self.functions,
+            &mut analysis.functions,
+        );
+    }
+
+    /// `analyser` with these settings, printing the ones in effect.
+    fn apply(&self, analyser: GitAnalyzer) -> GitAnalyzer {
+        println!(
+            "Batch size: {}, bulk load: {}",
+            self.batch_size, self.bulk_load
+        );
+        if let Some(half_life) = self.half_life_days {
+            println!("Half-life: {} days", half_life);
+        }
+        if let Some(days) = self.collaboration_window_days {
+            println!("Collaboration window: {} days", days);
+        }
+        if self.functions {
+            println!("Function-level co-change: on");
+        }
+        analyser
+            .with_batch_size(self.batch_size)
+            .with_ingest_mode(ingest_mode(self.bulk_load))
+            .with_half_life_days(self.half_life_days)
+            .with_collaboration_window(self.collaboration_window_days)
+            .with_function_level(self.functions)
+    }
+}
+
+impl AnalyseLocalArgs {
+    fn configure(&mut self, matches: &ArgMatches, config: &mut PipelineConfig) {
+        self.ingest.configure(matches, config);
+        self.analysis.configure(matches, config);
+        let PipelineConfig {
+            analysis,
+            thresholds,
+            ..
+        } = config;
+        bind(matches, "prune", &mut self.prune, &mut analysis.prune);
+        bind(
+            matches,
+            "threshold",
+            &mut self.threshold,
+            &mut thresholds.prune_threshold,
+        );
+        bind_option(
+            matches,
+            "hub_threshold",
+            &mut self.hub_threshold,
+            &mut thresholds.hub_threshold,
+        );
+        bind(
+            matches,
+            "incremental",
+            &mut self.incremental,
+            &mut analysis.incremental,
+        );
+    }
+}
+
+impl CloneArgs {
+    fn configure(&mut self, matches: &ArgMatches, config: &mut PipelineConfig) {
+        self.ingest.configure(matches, config);
+        self.analysis.configure(matches, config);
+        let PipelineConfig {
+            sources, outputs, ..
+        } = config;
+        bind_option(matches, "input", &mut self.input, &mut sources.input);
+        bind(matches, "path", &mut self.path, &mut sources.cache_dir);
+        bind(
+            matches,
+            "credentials",
+            &mut self.credentials,
+            &mut sources.credentials,
+        );
+        bind_option(matches, "depth", &mut self.depth, &mut sources.depth);
+        bind(
+            matches,
+            "blobless",
+            &mut self.blobless,
+            &mut sources.blobless,
+        );
+        bind_option(
+            matches,
+            "mirror_dir",
+            &mut self.mirror_dir,
+            &mut sources.mirror_dir,
+        );
+        bind(
+            matches,
+            "clone_jobs",
+            &mut self.clone_jobs,
+            &mut sources.clone_jobs,
+        );
+        bind(matches, "jobs", &mut self.jobs, &mut sources.jobs);
+        bind(matches, "summary", &mut self.summary, &mut outputs.summary);
+        bind(
+            matches,
+            "manifest",
+            &mut self.manifest,
+            &mut sources.manifest,
+        );
+    }
+}
+
+impl VerifyArgs {
+    fn configure(&mut self, matches: &ArgMatches, config: &mut PipelineConfig) {
+        bind(
+            matches,
+            "output",
+            &mut self.output,
+            &mut config.outputs.graph,
+        );
+    }
+}
+
+impl StatusArgs {
+    fn configure(&mut self, matches: &ArgMatches, config: &mut PipelineConfig) {
+        bind(
+            matches,
+            "manifest",
+            &mut self.manifest,
+            &mut config.sources.manifest,
+        );
+    }
+}
+
+impl CopyTopFilesArgs {
+    fn configure(&mut self, matches: &ArgMatches, config: &mut PipelineConfig) {
+        let PipelineConfig {
+            sources,
+            filters,
+            thresholds,
+            outputs,
+            ..
+        } = config;
+        bind_option(
+            matches,
+            "score_hub_threshold",
+            &mut self.score_hub_threshold,
+            &mut thresholds.hub_threshold,
+        );
+        bind(matches, "output", &mut self.output, &mut outputs.files);
+        bind(
+            matches,
+            "extension",
+            &mut self.extension,
+            &mut filters.extension,
+        );
+        bind(
+            matches,
+            "ignore",
+            &mut self.ignore,
+            &mut filters.ignore_repos,
+        );
+        bind(matches, "path", &mut self.path, &mut sources.cache_dir);
+        bind(
+            matches,
+            "credentials",
+            &mut self.credentials,
+            &mut sources.credentials,
+        );
+        bind_option(
+            matches,
+            "mirror_dir",
+            &mut self.mirror_dir,
+            &mut sources.mirror_dir,
+        );
+    }
+}
+
+impl MetricsArgs {
+    fn configure(&mut self, matches: &ArgMatches, config: &mut PipelineConfig) {
+        bind_option(
+            matches,
+            "folder",
+            &mut self.folder,
+            &mut config.outputs.files,
+        );
+        bind(
+            matches,
+            "output",
+            &mut self.output,
+            &mut config.outputs.metrics_csv,
+        );
+    }
+}
+
+impl CodeSceneAnalyzeArgs {
+    fn configure(&mut self, matches: &ArgMatches, config: &mut PipelineConfig) {
+        bind(
+            matches,
+            "threshold",
+            &mut self.threshold,
+            &mut config.thresholds.code_health,
+        );
+        bind(
+            matches,
+            "extensions",
+            &mut self.extensions,
+            &mut config.filters.extension,
+        );
+        bind(
+            matches,
+            "output_csv",
+            &mut self.output_csv,
+            &mut config.outputs.codescene_csv,
+        );
+    }
+}
+
+impl ExportHubScoresArgs {
+    fn configure(&mut self, matches: &ArgMatches, config: &mut PipelineConfig) {
+        bind(
+            matches,
+            "extension",
+            &mut self.extension,
+            &mut config.filters.extension,
+        );
+        bind(
+            matches,
+            "output",
+            &mut self.output,
+            &mut config.outputs.hub_scores,
+        );
+        bind_option(
+            matches,
+            "manifest",
+            &mut self.manifest,
+            &mut config.sources.manifest,
+        );
+    }
+}
+
+impl RecomputeHubScoresArgs {
+    fn configure(&mut self, matches: &ArgMatches, config: &mut PipelineConfig) {
+        bind_option(
+            matches,
+            "min_coupling",
+            &mut self.min_coupling,
+            &mut config.scoring.min_coupling,
+        );
+    }
+}
+
+impl TimelineArgs {
+    fn configure(&mut self, matches: &ArgMatches, config: &mut PipelineConfig) {
+        self.analysis.configure(matches, config);
+        let PipelineConfig {
+            scoring, outputs, ..
+        } = config;
+        // A window size given on the command line replaces the configured one of either unit
+        if on_command_line(matches, "window_commits") {
+            scoring.window_months = None;
+        }
+        if on_command_line(matches, "window_months") {
+            scoring.window_commits = None;
+        }
+        bind_option(
+            matches,
+            "window_commits",
+            &mut self.window_commits,
+            &mut scoring.window_commits,
+        );
+        bind_option(
+            matches,
+            "window_months",
+            &mut self.window_months,
+            &mut scoring.window_months,
+        );
+        bind_option(matches, "step", &mut self.step, &mut scoring.step);
+        bind(
+            matches,
+            "min_coupling",
+            &mut self.min_coupling,
+            &mut scoring.min_coupling,
+        );
+        bind(matches, "output", &mut self.output, &mut outputs.timeline);
+        bind(
+            matches,
+            "format",
+            &mut self.format,
+            &mut outputs.timeline_format,
+        );
+    }
+}
+
+impl SzzArgs {
+    fn configure(&mut self, matches: &ArgMatches, config: &mut PipelineConfig) {
+        self.analysis.configure(matches, config);
+        bind(matches, "output", &mut self.output, &mut config.outputs.szz);
+    }
+}
+
+fn on_command_line(matches: &ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+/// Takes `value` from the config setting unless its flag was given on the command line,
+/// then records the value in effect in the setting.
+fn bind<T: Clone>(matches: &ArgMatches, id: &str, value: &mut T, setting: &mut Option<T>) {
+    if !on_command_line(matches, id) {
+        if let Some(configured) = setting {
+            *value = configured.clone();
+        }
+    }
+    *setting = Some(value.clone());
+}
+
+/// `bind` for a flag without a default.
+fn bind_option<T: Clone>(
+    matches: &ArgMatches,
+    id: &str,
+    value: &mut Option<T>,
+    setting: &mut Option<T>,
+) {
+    if !on_command_line(matches, id) && setting.is_some() {
+        *value = setting.clone();
+    }
+    *setting = value.clone();
+}
+
+fn required<T>(value: Option<T>, flag: &str, setting: &str) -> Result<T, String> {
+    value.ok_or_else(|| format!("Pass --{} or set {} in the config", flag, setting))
+}
+
+fn load_config(path: Option<&str>) -> Result<PipelineConfig, String> {
+    let path = match path {
+        Some(path) => path,
+        None if Path::new(DEFAULT_CONFIG).exists() => DEFAULT_CONFIG,
+        None => return Ok(PipelineConfig::default()),
+    };
+    println!("Config: {}", path);
+    PipelineConfig::load(path)
+}
+
+fn ingest_mode(bulk_load: bool) -> IngestMode {
+    if bulk_load {
+        IngestMode::Bulk
+    } else {
+        IngestMode::Stream
+    }
+}
+
+fn commit_classifier(rules: Option<&str>) -> Result<CommitClassifier, String> {
+    match rules {
+        Some(path) => {
+            println!("Commit rules: {}", path);
+            CommitClassifier::from_file(path)
+        }
+        None => Ok(CommitClassifier::default()),
+    }
+}
+
+fn path_filter(include: &[String], exclude: &[String], keep_vendored: bool) -> PathFilter {
+    if !include.is_empty() {
+        println!("Include: {}", include.join(", "));
+    }
+    if !exclude.is_empty() {
+        println!("Exclude: {}", exclude.join(", "));
+    }
+    if keep_vendored {
+        println!("Vendored code detection: off");
+    }
+    PathFilter::new(include, exclude, !keep_vendored)
+}
+
+fn commit_filter(
+    authors: &[String],
+    messages: &[String],
+    bots: bool,
+    whitespace_only: bool,
+) -> Result<CommitFilter, String> {
+    if !authors.is_empty() {
+        println!("Skipping commits by: {}", authors.join(", "));
+    }
+    for message in messages {
+        println!("Skipping commit messages matching: {}", message);
+    }
+    if bots {
+        println!("Skipping bot commits");
+    }
+    if whitespace_only {
+        println!("Skipping whitespace-only commits");
+    }
+    let filter = CommitFilter::new(authors, messages, whitespace_only)?;
+    Ok(if bots { filter.with_bots() } else { filter })
+}
+
+fn clone_cache(
+    path: &str,
+    credentials: &str,
+    depth: Option<u32>,
+    blobless: bool,
+    mirror_dir: Option<String>,
+) -> Result<CloneCache, String> {
+    let cache = CloneCache::new(path, CloneCredentials::parse(credentials)?)
+        .with_depth(depth)
+        .with_blobless(blobless)
+        .with_mirrors(mirror_dir);
+    println!("Cloning with: {}", cache.describe());
+    Ok(cache)
+}
+
+fn merge_strategy(value: &str, first_parent: bool) -> Result<MergeStrategy, String> {
+    println!(
+        "Merge strategy: {}, first parent only: {}",
+        value, first_parent
+    );
+    MergeStrategy::parse(value)
+}
+
+fn mark_exported(manifest: Option<&str>, repos: &HashSet<String>) -> Result<(), String> {
+    if let Some(path) = manifest {
+        if !Path::new(path).exists() {
+            return Err(format!("No manifest at {}", path));
+        }
+        let marked = Manifest::load(path)?.mark_exported(repos)?;
+        println!("Marked {} repositories as exported in {}", marked, path);
+    }
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches)?;
+
+    let mut config = load_config(cli.config.as_deref())?;
+    bind(&matches, "store", &mut cli.store, &mut config.store.backend);
+    bind(
+        &matches,
+        "neo4j_uri",
+        &mut cli.neo4j_uri,
+        &mut config.store.neo4j_uri,
+    );
+    bind(
+        &matches,
+        "neo4j_database",
+        &mut cli.neo4j_database,
+        &mut config.store.neo4j_database,
+    );
+    let (_, sub_matches) = matches.subcommand().ok_or("No command given")?;
 
     match cli.command {
         Commands::Joss(args) => {
@@ -222,55 +1165,88 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             )
             .await?;
         }
-        Commands::AnalyseLocal(args) => {
+        Commands::AnalyseLocal(mut args) => {
+            args.configure(sub_matches, &mut config);
+            let hub_threshold = required(
+                args.hub_threshold,
+                "hub-threshold",
+                "thresholds.hub_threshold",
+            )?;
+
             println!("Analyzing repository: {}", args.repo);
-            println!("Neo4j URI: {}", args.neo4j_uri);
+            println!("Store: {}", cli.store);
+            println!("Neo4j URI: {}", cli.neo4j_uri);
             println!("Prune: {}, threshold: {}", args.prune, args.threshold);
-            println!(
-                "Max files per commit: {}, max renames per commit: {}",
-                args.max_files_per_commit, args.max_renames_per_commit
-            );
-            println!("Hub threshold: {}", args.hub_threshold);
-            println!("Extension: {}", args.extension);
+            println!("Hub threshold: {}", hub_threshold);
+            println!("Incremental: {}", args.incremental);
 
             let output_csv = format!("../results/{}_metrics.csv", args.name);
+            let run_config = config.to_toml()?;
+            let analyser = args
+                .ingest
+                .apply(args.analysis.to_analyzer(args.repo)?)
+                .with_run_config(Some(run_config));
+            let store = open_store(&cli.store, &cli.neo4j_uri, &cli.neo4j_database).await?;
 
             repo_analyser::entrypoint::analyze_local_repo(
-                args.repo,
+                analyser,
                 args.name,
-                args.neo4j_uri,
-                "".to_string(),
-                args.prune,
-                args.threshold,
-                args.max_files_per_commit,
-                args.max_renames_per_commit,
-                args.hub_threshold,
-                args.extension,
+                store.as_ref(),
+                args.incremental,
+                args.prune.then_some(args.threshold),
+                hub_threshold,
                 output_csv,
             )
             .await?;
-            println!("Successfully saved graph to Neo4j");
+            println!("Successfully saved graph");
         }
-        Commands::Clone(args) => {
-            println!("Cloning and analyzing repositories from: {}", args.input);
+        Commands::Clone(mut args) => {
+            args.configure(sub_matches, &mut config);
+            let input = required(args.input, "input", "sources.input")?;
+
+            println!("Cloning and analyzing repositories from: {}", input);
             println!("Clone path: {}", args.path);
-            println!("Extension: {}", args.extension);
-            println!("Neo4j URI: {}", args.neo4j_uri);
+            println!("Neo4j URI: {}", cli.neo4j_uri);
 
-            repo_analyser::entrypoint::analyse_github_repos(
-                args.input,
-                args.neo4j_uri,
-                args.path,
-                args.extension,
-            )
-            .await?;
-            println!("Successfully analyzed all repositories");
+            let cache = clone_cache(
+                &args.path,
+                &args.credentials,
+                args.depth,
+                args.blobless,
+                args.mirror_dir,
+            )?;
+
+            println!(
+                "Concurrency: {} clones, {} analyses",
+                args.clone_jobs, args.jobs
+            );
+
+            let run_config = config.to_toml()?;
+            // Each repository gets a copy of this pointed at its clone
+            let analyser = args
+                .ingest
+                .apply(args.analysis.to_analyzer(String::new())?)
+                .with_run_config(Some(run_config));
+            let options = CloneRunOptions {
+                clone_jobs: args.clone_jobs,
+                analysis_jobs: args.jobs,
+                summary_path: args.summary,
+                manifest_path: args.manifest,
+                restart: args.restart,
+            };
+
+            let store: Arc<dyn GraphStore> =
+                Arc::from(open_store(&cli.store, &cli.neo4j_uri, &cli.neo4j_database).await?);
+            repo_analyser::entrypoint::analyse_github_repos(input, store, cache, analyser, options)
+                .await?;
+            println!("Finished cloning and analysing repositories");
         }
-        Commands::Verify(args) => {
+        Commands::Verify(mut args) => {
+            args.configure(sub_matches, &mut config);
             println!("Verifying graph for repository: {}", args.repo);
             println!("Neo4j URI: {}", cli.neo4j_uri);
 
-            let client = repo_analyser::Neo4jClient::new(&cli.neo4j_uri).await?;
+            let client = open_store(&cli.store, &cli.neo4j_uri, &cli.neo4j_database).await?;
             let graph = client.get_graph(&args.repo).await?;
 
             let json = serde_json::to_string_pretty(&graph)?;
@@ -282,36 +1258,43 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 graph.edges.len()
             );
         }
-        Commands::Copy(args) => {
-            println!(
-                "Copying files (hub_threshold: {})",
-                args.score_hub_threshold
-            );
+        Commands::Copy(mut args) => {
+            args.configure(sub_matches, &mut config);
+            let hub_threshold = required(
+                args.score_hub_threshold,
+                "score-hub-threshold",
+                "thresholds.hub_threshold",
+            )?;
+
+            println!("Copying files (hub_threshold: {})", hub_threshold);
             println!("Output: {}", args.output);
             println!("Extension: {}", args.extension);
+            println!("Clone path: {}", args.path);
             println!("Neo4j URI: {}", cli.neo4j_uri);
+            let cache = clone_cache(&args.path, &args.credentials, None, false, args.mirror_dir)?;
 
+            let store = open_store(&cli.store, &cli.neo4j_uri, &cli.neo4j_database).await?;
             repo_analyser::entrypoint::copy_files_by_hub_threshold(
-                cli.neo4j_uri,
-                cli.neo4j_database,
-                args.score_hub_threshold,
+                store.as_ref(),
+                hub_threshold,
                 args.output,
                 args.extension,
                 args.ignore.clone(),
-                None,
+                FileSource::Cache(cache),
             )
             .await?;
             println!("Successfully copied all files");
         }
-        Commands::Metrics(args) => {
-            println!("Analyzing file metrics from: {}", args.folder);
-            repo_analyser::file_metrics_analyser::convert_balanced_metrics(
-                args.folder,
-                args.output,
-            )?;
+        Commands::Metrics(mut args) => {
+            args.configure(sub_matches, &mut config);
+            let folder = required(args.folder, "folder", "outputs.files")?;
+
+            println!("Analyzing file metrics from: {}", folder);
+            repo_analyser::file_metrics_analyser::convert_balanced_metrics(folder, args.output)?;
             println!("Successfully analyzed file metrics");
         }
-        Commands::CodeSceneAnalyze(args) => {
+        Commands::CodeSceneAnalyze(mut args) => {
+            args.configure(sub_matches, &mut config);
             println!("Analyzing repository with CodeScene: {}", args.repo);
             println!("Code health threshold: {}", args.threshold);
 
@@ -326,39 +1309,147 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .await?;
             println!("Successfully analyzed with CodeScene");
         }
-        Commands::ExportHubScores(args) => {
+        Commands::ExportHubScores(mut args) => {
+            args.configure(sub_matches, &mut config);
             println!("Exporting hub scores for extension: {}", args.extension);
             println!("Output: {}", args.output);
             println!("Neo4j URI: {}", cli.neo4j_uri);
 
-            let client = repo_analyser::Neo4jClient::new(&cli.neo4j_uri).await?;
-            let hub_scores = client.get_all_hub_scores(&args.extension).await?;
+            let client = open_store(&cli.store, &cli.neo4j_uri, &cli.neo4j_database).await?;
+            if args.functions {
+                let hub_scores = client.get_all_function_hub_scores(&args.extension).await?;
 
-            let json = serde_json::to_string_pretty(&hub_scores)?;
-            std::fs::write(&args.output, json)?;
-            println!("Saved {} hub scores to {}", hub_scores.len(), args.output);
+                let json = serde_json::to_string_pretty(&hub_scores)?;
+                std::fs::write(&args.output, json)?;
+                println!(
+                    "Saved {} function hub scores to {}",
+                    hub_scores.len(),
+                    args.output
+                );
+                let repos = hub_scores.iter().map(|s| s.repo.clone()).collect();
+                mark_exported(args.manifest.as_deref(), &repos)?;
+            } else {
+                let hub_scores = client.get_all_hub_scores(&args.extension).await?;
+
+                let json = serde_json::to_string_pretty(&hub_scores)?;
+                std::fs::write(&args.output, json)?;
+                println!("Saved {} hub scores to {}", hub_scores.len(), args.output);
+                let repos = hub_scores.iter().map(|s| s.repo.clone()).collect();
+                mark_exported(args.manifest.as_deref(), &repos)?;
+            }
         }
-        Commands::RecomputeHubScores(args) => {
+        Commands::RecomputeHubScores(mut args) => {
+            args.configure(sub_matches, &mut config);
+            let min_coupling = required(args.min_coupling, "min-coupling", "scoring.min_coupling")?;
+
             println!(
                 "Recomputing hub scores for all repos (min_coupling: {})",
-                args.min_coupling
+                min_coupling
             );
-            println!("Neo4j URI: {}", args.neo4j_uri);
+            println!("Neo4j URI: {}", cli.neo4j_uri);
 
-            let client = repo_analyser::Neo4jClient::new(&args.neo4j_uri).await?;
+            let client = open_store(&cli.store, &cli.neo4j_uri, &cli.neo4j_database).await?;
             let repos = client.get_all_repo_names().await?;
 
             println!("Found {} repos to process", repos.len());
 
             for (i, repo) in repos.iter().enumerate() {
                 print!("[{}/{}] Processing {}...", i + 1, repos.len(), repo);
-                client.compute_hub_scores(repo, args.min_coupling).await?;
+                client.compute_hub_scores(repo, min_coupling).await?;
                 println!(" done");
             }
 
             println!("Successfully recomputed hub scores for all repos");
         }
+        Commands::Timeline(mut args) => {
+            args.configure(sub_matches, &mut config);
+            let (window, size) = match (args.window_commits, args.window_months) {
+                (Some(commits), _) => (TimelineWindow::Commits(commits), commits),
+                (None, Some(months)) => (TimelineWindow::Months(months), months as usize),
+                (None, None) => (TimelineWindow::Commits(500), 500),
+            };
+            let step = args.step.unwrap_or(size);
+
+            println!("Computing timeline for: {}", args.repo);
+            println!("Window: {:?}, step: {}", window, step);
+            println!("Output: {} ({})", args.output, args.format);
+            let analyser = args.analysis.to_analyzer(args.repo)?;
+
+            let points = repo_analyser::entrypoint::export_timeline(
+                analyser,
+                window,
+                step,
+                args.min_coupling,
+                args.output.clone(),
+                args.format,
+            )?;
+            println!("Saved {} timeline points to {}", points, args.output);
+        }
+        Commands::Szz(mut args) => {
+            args.configure(sub_matches, &mut config);
+            println!("Finding bug-introducing commits in: {}", args.repo);
+            println!("Output: {}", args.output);
+            let analyser = args.analysis.to_analyzer(args.repo)?;
+
+            let files = repo_analyser::entrypoint::export_szz(analyser, args.output.clone())?;
+            println!("Saved SZZ labels for {} files to {}", files, args.output);
+        }
+        Commands::Status(mut args) => {
+            args.configure(sub_matches, &mut config);
+            repo_analyser::entrypoint::print_manifest_status(args.manifest, args.failed)?;
+        }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configured_szz(args: &[&str], config: &mut PipelineConfig) -> SzzArgs {
+        let matches = Cli::command()
+            .try_get_matches_from(["scraper", "szz", "--repo", "r"].iter().chain(args))
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        let mut args = SzzArgs::from_arg_matches(sub_matches).unwrap();
+        args.analysis.configure(sub_matches, config);
+        args
+    }
+
+    #[test]
+    fn flags_on_the_command_line_override_the_config() {
+        let mut config = PipelineConfig::default();
+        config.thresholds.max_files_per_commit = Some(1);
+        config.thresholds.max_renames_per_commit = Some(7);
+
+        let args = configured_szz(&["--max-files-per-commit", "5"], &mut config);
+        assert_eq!(args.analysis.max_files_per_commit, 5);
+        assert_eq!(args.analysis.max_renames_per_commit, 7);
+        assert_eq!(config.thresholds.max_files_per_commit, Some(5));
+        assert_eq!(config.thresholds.max_renames_per_commit, Some(7));
+    }
+
+    #[test]
+    fn flag_defaults_apply_when_the_config_has_no_setting() {
+        let mut config = PipelineConfig::default();
+        config.filters.since = Some("2024-01-01".to_string());
+
+        let args = configured_szz(&[], &mut config);
+        assert_eq!(args.analysis.max_files_per_commit, 200);
+        assert_eq!(args.analysis.since.as_deref(), Some("2024-01-01"));
+        assert_eq!(args.analysis.until, None);
+        assert_eq!(config.thresholds.max_files_per_commit, Some(200));
+        assert_eq!(config.filters.until, None);
+    }
+
+    #[test]
+    fn options_given_on_the_command_line_replace_the_config() {
+        let mut config = PipelineConfig::default();
+        config.filters.since = Some("2024-01-01".to_string());
+
+        let args = configured_szz(&["--since", "2023-06-01"], &mut config);
+        assert_eq!(args.analysis.since.as_deref(), Some("2023-06-01"));
+        assert_eq!(config.filters.since.as_deref(), Some("2023-06-01"));
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::BTreeSet;
+
+use crate::file_graph::CommitChanges;
+
+/// Groups consecutive commits by the same author into logical change sets while commits are
+/// walked in time order. A commit joins the current set when it follows the previous one
+/// within `gap` seconds; its co-change pairs then also link its files to the files the set
+/// already touched, so every pair is counted once per change set instead of once per commit.
+pub struct ChangeSetGrouper {
+    gap: i64,
+    author: Option<String>,
+    last_time: i64,
+    files: BTreeSet<String>,
+}
+
+impl ChangeSetGrouper {
+    pub fn new(gap_minutes: u32) -> Self {
+        Self {
+            gap: gap_minutes as i64 * 60,
+            author: None,
+            last_time: 0,
+            files: BTreeSet::new(),
+        }
+    }
+
+    /// Records on the commit which files its change set touched before it, then adds the
+    /// commit's own files to the set.
+    pub fn assign(&mut self, commit: &mut CommitChanges) {
+        let author = commit.author.key();
+        if self.author.as_ref() != Some(&author) || commit.commit_time - self.last_time > self.gap {
+            self.files.clear();
+        }
+
+        for (old_path, new_path) in &commit.renames {
+            self.files.remove(new_path);
+            if self.files.remove(old_path) {
+                self.files.insert(new_path.clone());
+            }
+        }
+
+        commit.change_set_files = self.files.iter().cloned().collect();
+
+        // Large commits have no co-change pairs, so they end the change set
+        if commit.record_cochanges {
+            self.files
+                .extend(commit.files.iter().map(|f| f.path.clone()));
+        } else {
+            self.files.clear();
+        }
+        for file in commit.files.iter().filter(|f| f.is_deleted) {
+            self.files.remove(&file.path);
+        }
+
+        self.author = Some(author);
+        self.last_time = commit.commit_time;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commit_class::CommitClass;
+    use crate::file_graph::{ChangedFile, Identity};
+
+    fn commit(email: &str, time: i64, paths: &[&str]) -> CommitChanges {
+        CommitChanges {
+            commit_hash: format!("{}-{}", email, time),
+            commit_time: time,
+            renames: Vec::new(),
+            files: paths
+                .iter()
+                .map(|path| ChangedFile {
+                    path: path.to_string(),
+                    additions: 1,
+                    deletions: 0,
+                    normalized_additions: 1,
+                    normalized_deletions: 0,
+                    is_deleted: false,
+                    renamed_to: None,
+                })
+                .collect(),
+            record_cochanges: true,
+            decay: None,
+            author: Identity {
+                name: email.to_string(),
+                email: email.to_string(),
+            },
+            committer: Identity::default(),
+            class: CommitClass::Other,
+            issues: Vec::new(),
+            change_set_files: Vec::new(),
+            functions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn commits_exactly_gap_apart_share_a_change_set() {
+        let mut grouper = ChangeSetGrouper::new(30);
+        grouper.assign(&mut commit("a@x.org", 1000, &["a.cpp"]));
+
+        let mut next = commit("a@x.org", 1000 + 30 * 60, &["b.cpp"]);
+        grouper.assign(&mut next);
+        assert_eq!(next.change_set_files, vec!["a.cpp"]);
+    }
+
+    #[test]
+    fn commits_past_the_gap_start_a_new_change_set() {
+        let mut grouper = ChangeSetGrouper::new(30);
+        grouper.assign(&mut commit("a@x.org", 1000, &["a.cpp"]));
+
+        let mut next = commit("a@x.org", 1000 + 30 * 60 + 1, &["b.cpp"]);
+        grouper.assign(&mut next);
+        assert!(next.change_set_files.is_empty());
+    }
+
+    #[test]
+    fn another_author_starts_a_new_change_set() {
+        let mut grouper = ChangeSetGrouper::new(30);
+        grouper.assign(&mut commit("a@x.org", 1000, &["a.cpp"]));
+
+        let mut next = commit("b@x.org", 1001, &["b.cpp"]);
+        grouper.assign(&mut next);
+        assert!(next.change_set_files.is_empty());
+    }
+
+    #[test]
+    fn large_commits_end_the_change_set() {
+        let mut grouper = ChangeSetGrouper::new(30);
+        let mut large = commit("a@x.org", 1000, &["a.cpp"]);
+        large.record_cochanges = false;
+        grouper.assign(&mut large);
+
+        let mut next = commit("a@x.org", 1001, &["b.cpp"]);
+        grouper.assign(&mut next);
+        assert!(next.change_set_files.is_empty());
+    }
+}
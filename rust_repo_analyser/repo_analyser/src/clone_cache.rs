@@ -0,0 +1,237 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::credentials::CloneCredentials;
+
+/// Where repositories are cloned to and reused from, and how they are cloned: with which
+/// credentials, how much history and from which local mirrors.
+#[derive(Clone)]
+pub struct CloneCache {
+    pub dir: PathBuf,
+    credentials: CloneCredentials,
+    depth: Option<u32>,
+    blobless: bool,
+    mirror_dir: Option<PathBuf>,
+}
+
+impl CloneCache {
+    pub fn new(dir: &str, credentials: CloneCredentials) -> Self {
+        Self {
+            dir: PathBuf::from(dir),
+            credentials,
+            depth: None,
+            blobless: false,
+            mirror_dir: None,
+        }
+    }
+
+    /// Shallow clones holding only the last `depth` commits of history.
+    pub fn with_depth(mut self, depth: Option<u32>) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Partial clones that fetch commits and trees, the checked-out files and the blobs of
+    /// analysed files only. libgit2 cannot clone partially, so these go through `git`, as
+    /// do shallow clones of local mirrors, which libgit2's local transport cannot make.
+    pub fn with_blobless(mut self, blobless: bool) -> Self {
+        self.blobless = blobless;
+        self
+    }
+
+    /// A directory of local mirrors, `<repo>.git` or `<repo>`, cloned from instead of the
+    /// repository URL when present.
+    pub fn with_mirrors(mut self, mirror_dir: Option<String>) -> Self {
+        self.mirror_dir = mirror_dir.map(PathBuf::from);
+        self
+    }
+
+    pub fn is_blobless(&self) -> bool {
+        self.blobless
+    }
+
+    pub fn describe(&self) -> String {
+        let mut parts = vec![format!("credentials {}", self.credentials.describe())];
+        if let Some(depth) = self.depth {
+            parts.push(format!("depth {}", depth));
+        }
+        if self.blobless {
+            parts.push("blobless".to_string());
+        }
+        if let Some(mirror_dir) = &self.mirror_dir {
+            parts.push(format!("mirrors in {}", mirror_dir.display()));
+        }
+        parts.join(", ")
+    }
+
+    /// The URL to clone `repo_name` from: its local mirror if there is one, else `url`.
+    pub fn source(&self, repo_name: &str, url: &str) -> String {
+        let Some(mirror_dir) = &self.mirror_dir else {
+            return url.to_string();
+        };
+        [format!("{}.git", repo_name), repo_name.to_string()]
+            .iter()
+            .map(|name| mirror_dir.join(name))
+            .find(|path| path.is_dir())
+            .and_then(|path| fs::canonicalize(path).ok())
+            .map(|path| format!("file://{}", path.display()))
+            .unwrap_or_else(|| url.to_string())
+    }
+
+    /// Clones `url` into `path`. A blobless clone also fetches the blobs of every version
+    /// of the files ending in one of the comma-separated `extensions`, which the analysis
+    /// reads. The clone is made in a sibling directory and moved to `path` once complete,
+    /// so `path` never holds a partial clone.
+    pub fn clone_repo(&self, url: &str, path: &Path, extensions: &str) -> Result<(), String> {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        let partial = path.with_file_name(format!(".{}.partial", name));
+        if partial.exists() {
+            fs::remove_dir_all(&partial)
+                .map_err(|e| format!("Failed to remove {}: {}", partial.display(), e))?;
+        }
+
+        let result = if self.blobless {
+            self.clone_with_git(url, &partial)
+                .and_then(|_| self.fetch_analysed_blobs(&partial, extensions))
+        } else if self.depth.is_some() && url.starts_with("file://") {
+            self.clone_with_git(url, &partial)
+        } else {
+            self.clone_with_libgit2(url, &partial)
+        };
+        let result = result.and_then(|_| {
+            fs::rename(&partial, path)
+                .map_err(|e| format!("Failed to move clone to {}: {}", path.display(), e))
+        });
+
+        if result.is_err() {
+            let _ = fs::remove_dir_all(&partial);
+        }
+        result
+    }
+
+    fn clone_with_libgit2(&self, url: &str, path: &Path) -> Result<(), String> {
+        let mut fo = self.credentials.fetch_options();
+        if let Some(depth) = self.depth {
+            fo.depth(depth as i32);
+        }
+
+        let mut builder = git2::build::RepoBuilder::new();
+        builder.fetch_options(fo);
+        builder
+            .clone(url, path)
+            .map_err(|e| format!("Clone failed: {}", e))?;
+
+        Ok(())
+    }
+
+    fn clone_with_git(&self, url: &str, path: &Path) -> Result<(), String> {
+        let mut command = self.git();
+        command.args(["clone", "--quiet"]);
+        if self.blobless {
+            command.arg("--filter=blob:none");
+        }
+        if let Some(depth) = self.depth {
+            command.arg(format!("--depth={}", depth));
+        }
+        command.arg(url).arg(path);
+        run(&mut command, None).map_err(|e| format!("Clone failed: {}", e))?;
+
+        Ok(())
+    }
+
+    // Lists the blobs on both sides of every change to an analysed file, merges included,
+    // and fetches the missing ones in a single request
+    fn fetch_analysed_blobs(&self, path: &Path, extensions: &str) -> Result<(), String> {
+        let extensions: Vec<&str> = extensions.split(',').filter(|e| !e.is_empty()).collect();
+        let mut log = self.git();
+        log.arg("-C").arg(path).args([
+            "log",
+            "HEAD",
+            "-m",
+            "--format=",
+            "--raw",
+            "--no-abbrev",
+            "--no-renames",
+        ]);
+        let raw = run(&mut log, None)
+            .map_err(|e| format!("Failed to list the blobs of analysed files: {}", e))?;
+
+        let mut blobs = Vec::new();
+        for line in raw.lines() {
+            // :<old mode> <new mode> <old blob> <new blob> <status>\t<path>
+            let Some((meta, file)) = line.split_once('\t') else {
+                continue;
+            };
+            if !extensions.is_empty() && !extensions.iter().any(|ext| file.ends_with(ext)) {
+                continue;
+            }
+            blobs.extend(
+                meta.split(' ')
+                    .skip(2)
+                    .take(2)
+                    .filter(|oid| oid.chars().any(|c| c != '0')),
+            );
+        }
+        blobs.sort_unstable();
+        blobs.dedup();
+        if blobs.is_empty() {
+            return Ok(());
+        }
+
+        let mut fetch = self.git();
+        fetch
+            .arg("-C")
+            .arg(path)
+            .args(["-c", "fetch.negotiationAlgorithm=noop", "fetch"])
+            .args(["--quiet", "--no-tags", "--no-write-fetch-head"])
+            .args(["--recurse-submodules=no", "--filter=blob:none", "--stdin"])
+            .arg("origin");
+        let mut wanted = blobs.join("\n");
+        wanted.push('\n');
+        run(&mut fetch, Some(&wanted))
+            .map_err(|e| format!("Failed to fetch the blobs of analysed files: {}", e))?;
+
+        Ok(())
+    }
+
+    fn git(&self) -> Command {
+        let mut command = Command::new("git");
+        // Fail instead of waiting for a password on the terminal
+        command.env("GIT_TERMINAL_PROMPT", "0");
+        self.credentials.configure_git(&mut command);
+        command
+    }
+}
+
+fn run(command: &mut Command, stdin: Option<&str>) -> Result<String, String> {
+    let mut child = command
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run git: {}", e))?;
+
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input.as_bytes())
+            .map_err(|e| format!("could not write to git: {}", e))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("could not run git: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use crate::file_graph::CommitChanges;
+use crate::storage::Collaboration;
+
+/// Builds the author collaboration network while commits are walked in time order. Two
+/// authors are linked when they change the same file within `window` seconds of each
+/// other; every such file touch adds one to the pair's weight.
+pub struct CollaborationTracker {
+    window: i64,
+    touches: HashMap<String, Vec<(i64, String)>>,
+    weights: HashMap<(String, String), i64>,
+}
+
+impl CollaborationTracker {
+    pub fn new(window_days: u32) -> Self {
+        Self {
+            window: window_days as i64 * 86_400,
+            touches: HashMap::new(),
+            weights: HashMap::new(),
+        }
+    }
+
+    /// Folds a commit into the network, linking its author with everyone who recently
+    /// touched the same files.
+    pub fn record(&mut self, commit: &CommitChanges) {
+        self.apply(commit, true);
+    }
+
+    /// Remembers a commit's file touches without adding links, used to seed the window
+    /// with commits an earlier run already counted.
+    pub fn observe(&mut self, commit: &CommitChanges) {
+        self.apply(commit, false);
+    }
+
+    fn apply(&mut self, commit: &CommitChanges, link: bool) {
+        for (old_path, new_path) in &commit.renames {
+            self.touches.remove(new_path);
+            if let Some(touches) = self.touches.remove(old_path) {
+                self.touches.insert(new_path.clone(), touches);
+            }
+        }
+
+        // Large commits only carry renames and deletions, like their co-change pairs
+        if commit.record_cochanges {
+            let author = commit.author.key();
+            let mut paths: Vec<&str> = commit.files.iter().map(|f| f.path.as_str()).collect();
+            paths.sort();
+            paths.dedup();
+
+            for path in paths {
+                let touches = self.touches.entry(path.to_string()).or_default();
+                touches.retain(|(time, other)| {
+                    commit.commit_time - time <= self.window && *other != author
+                });
+
+                if link {
+                    for (_, other) in touches.iter() {
+                        let pair = if author < *other {
+                            (author.clone(), other.clone())
+                        } else {
+                            (other.clone(), author.clone())
+                        };
+                        *self.weights.entry(pair).or_insert(0) += 1;
+                    }
+                }
+                touches.push((commit.commit_time, author.clone()));
+            }
+        }
+
+        for file in commit.files.iter().filter(|f| f.is_deleted) {
+            self.touches.remove(&file.path);
+        }
+    }
+
+    pub fn collaborations(&self) -> Vec<Collaboration> {
+        let mut collaborations: Vec<Collaboration> = self
+            .weights
+            .iter()
+            .map(|((source, target), weight)| Collaboration {
+                source: source.clone(),
+                target: target.clone(),
+                weight: *weight,
+            })
+            .collect();
+        collaborations.sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+        collaborations
+    }
+}
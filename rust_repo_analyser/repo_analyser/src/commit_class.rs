@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitClass {
+    Bugfix,
+    Feature,
+    Refactor,
+    Docs,
+    Merge,
+    Other,
+}
+
+/// Maps conventional-commit types (`fix(parser): ...`) and message keywords to a class.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassRule {
+    pub class: CommitClass,
+    #[serde(default)]
+    pub prefixes: Vec<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+/// Classifies commit messages. Merge commits are recognised by their parents; otherwise a
+/// conventional-commit type is matched against every rule's prefixes first, then the
+/// message words against the keywords, in rule order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitClassifier {
+    pub rules: Vec<ClassRule>,
+}
+
+fn rule(class: CommitClass, prefixes: &[&str], keywords: &[&str]) -> ClassRule {
+    ClassRule {
+        class,
+        prefixes: prefixes.iter().map(|s| s.to_string()).collect(),
+        keywords: keywords.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+impl Default for CommitClassifier {
+    fn default() -> Self {
+        Self {
+            rules: vec![
+                rule(
+                    CommitClass::Bugfix,
+                    &["fix", "bugfix", "hotfix"],
+                    &[
+                        "fix",
+                        "fixes",
+                        "fixed",
+                        "fixing",
+                        "bug",
+                        "bugs",
+                        "bugfix",
+                        "hotfix",
+                        "crash",
+                        "regression",
+                        "defect",
+                        "fault",
+                    ],
+                ),
+                rule(
+                    CommitClass::Refactor,
+                    &["refactor", "perf", "style"],
+                    &[
+                        "refactor",
+                        "refactoring",
+                        "refactored",
+                        "cleanup",
+                        "restructure",
+                        "simplify",
+                        "tidy",
+                    ],
+                ),
+                rule(
+                    CommitClass::Docs,
+                    &["docs", "doc"],
+                    &["doc", "docs", "documentation", "readme", "typo", "comments"],
+                ),
+                rule(
+                    CommitClass::Feature,
+                    &["feat", "feature"],
+                    &[
+                        "add",
+                        "adds",
+                        "added",
+                        "implement",
+                        "implements",
+                        "implemented",
+                        "introduce",
+                        "feature",
+                        "support",
+                    ],
+                ),
+            ],
+        }
+    }
+}
+
+impl CommitClassifier {
+    /// Loads rules from a JSON file shaped like
+    /// `{"rules": [{"class": "bugfix", "prefixes": ["fix"], "keywords": ["bug"]}]}`.
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let file =
+            File::open(path).map_err(|e| format!("Failed to open commit rules {}: {}", path, e))?;
+        serde_json::from_reader(file)
+            .map_err(|e| format!("Failed to parse commit rules {}: {}", path, e))
+    }
+
+    pub fn classify(&self, message: &str, parent_count: usize) -> CommitClass {
+        if parent_count > 1 {
+            return CommitClass::Merge;
+        }
+
+        let header = message.lines().next().unwrap_or("").trim();
+        if let Some(kind) = conventional_type(header) {
+            if let Some(rule) = self
+                .rules
+                .iter()
+                .find(|rule| rule.prefixes.iter().any(|p| p.eq_ignore_ascii_case(&kind)))
+            {
+                return rule.class;
+            }
+        }
+
+        let lowered = message.to_lowercase();
+        let words: Vec<&str> = lowered
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect();
+        self.rules
+            .iter()
+            .find(|rule| rule.keywords.iter().any(|k| words.contains(&k.as_str())))
+            .map_or(CommitClass::Other, |rule| rule.class)
+    }
+}
+
+// `type(scope)!: subject` -> `type`
+fn conventional_type(header: &str) -> Option<String> {
+    let (head, _) = header.split_once(':')?;
+    let kind = head.split(['(', '!']).next()?.trim();
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some(kind.to_lowercase())
+}
+
+/// Per-file number of commits in each class. Unclassified commits only show up in the
+/// file's total commit count.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClassCounts {
+    pub bugfix_commits: i64,
+    pub feature_commits: i64,
+    pub refactor_commits: i64,
+    pub docs_commits: i64,
+    pub merge_commits: i64,
+}
+
+impl ClassCounts {
+    pub fn add(&mut self, class: CommitClass) {
+        match class {
+            CommitClass::Bugfix => self.bugfix_commits += 1,
+            CommitClass::Feature => self.feature_commits += 1,
+            CommitClass::Refactor => self.refactor_commits += 1,
+            CommitClass::Docs => self.docs_commits += 1,
+            CommitClass::Merge => self.merge_commits += 1,
+            CommitClass::Other => {}
+        }
+    }
+
+    pub fn merge(&mut self, other: &ClassCounts) {
+        self.bugfix_commits += other.bugfix_commits;
+        self.feature_commits += other.feature_commits;
+        self.refactor_commits += other.refactor_commits;
+        self.docs_commits += other.docs_commits;
+        self.merge_commits += other.merge_commits;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_are_classified_by_parents() {
+        let classifier = CommitClassifier::default();
+        assert_eq!(classifier.classify("fix: bug", 2), CommitClass::Merge);
+        assert_eq!(
+            classifier.classify("Merge branch 'x'", 1),
+            CommitClass::Other
+        );
+    }
+
+    #[test]
+    fn conventional_type_wins_over_keywords() {
+        let classifier = CommitClassifier::default();
+        assert_eq!(classifier.classify("docs: fix typo", 1), CommitClass::Docs);
+        assert_eq!(
+            classifier.classify("feat(parser)!: drop bug workaround", 1),
+            CommitClass::Feature
+        );
+        // Unknown types fall back to the keywords
+        assert_eq!(
+            classifier.classify("chore: fix crash", 1),
+            CommitClass::Bugfix
+        );
+        assert_eq!(
+            classifier.classify("chore: bump version", 1),
+            CommitClass::Other
+        );
+    }
+
+    #[test]
+    fn keywords_match_whole_words_in_rule_order() {
+        let classifier = CommitClassifier::default();
+        assert_eq!(
+            classifier.classify("Refactor and fix the loader", 1),
+            CommitClass::Bugfix
+        );
+        assert_eq!(
+            classifier.classify("Add a prefix option", 1),
+            CommitClass::Feature
+        );
+        assert_eq!(
+            classifier.classify("Update\n\nThis closes a BUG.", 1),
+            CommitClass::Bugfix
+        );
+    }
+
+    #[test]
+    fn custom_rules_replace_the_defaults() {
+        let classifier: CommitClassifier = serde_json::from_str(
+            r#"{"rules": [{"class": "refactor", "keywords": ["fix"]}, {"class": "bugfix", "prefixes": ["patch"]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            classifier.classify("fix the build", 1),
+            CommitClass::Refactor
+        );
+        assert_eq!(
+            classifier.classify("patch: wrong index", 1),
+            CommitClass::Bugfix
+        );
+        assert_eq!(classifier.classify("Add feature", 1), CommitClass::Other);
+        assert!(CommitClassifier::from_file("/nonexistent/rules.json").is_err());
+    }
+}
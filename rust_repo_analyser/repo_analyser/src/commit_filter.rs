@@ -0,0 +1,142 @@
+use regex::Regex;
+
+use crate::file_graph::Identity;
+use crate::path_filter::wildcard_match;
+
+// Dependency-update bots and CI accounts, matched against the author name and email
+const BOT_AUTHORS: &[&str] = &[
+    "*[bot]*",
+    "dependabot*",
+    "renovate*",
+    "greenkeeper*",
+    "github-actions*",
+    "pre-commit-ci*",
+    "snyk-bot*",
+];
+
+/// Leaves automated commits out of the co-change analysis: commits whose author name or
+/// email matches a wildcard pattern (case-insensitive), whose message matches a regular
+/// expression, or, when enabled, whose changes to analysed files are whitespace only.
+#[derive(Debug, Clone, Default)]
+pub struct CommitFilter {
+    authors: Vec<String>,
+    messages: Vec<Regex>,
+    whitespace_only: bool,
+}
+
+impl CommitFilter {
+    pub fn new(
+        authors: &[String],
+        messages: &[String],
+        whitespace_only: bool,
+    ) -> Result<Self, String> {
+        let messages = messages
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|e| format!("Invalid message pattern '{}': {}", pattern, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            authors: authors.iter().map(|p| p.to_lowercase()).collect(),
+            messages,
+            whitespace_only,
+        })
+    }
+
+    /// Adds the built-in patterns for dependabot, renovate, GitHub Actions and other bots.
+    pub fn with_bots(mut self) -> Self {
+        self.authors
+            .extend(BOT_AUTHORS.iter().map(|p| p.to_string()));
+        self
+    }
+
+    pub fn skips_whitespace_only(&self) -> bool {
+        self.whitespace_only
+    }
+
+    /// The reason a commit by `author` with `message` is skipped, or `None` to analyse it.
+    pub fn exclusion(&self, author: &Identity, message: &str) -> Option<String> {
+        let name = author.name.to_lowercase();
+        let email = author.email.to_lowercase();
+        if let Some(pattern) = self
+            .authors
+            .iter()
+            .find(|p| wildcard_match(p, &name) || wildcard_match(p, &email))
+        {
+            return Some(format!("author matches {}", pattern));
+        }
+
+        self.messages
+            .iter()
+            .find(|regex| regex.is_match(message))
+            .map(|regex| format!("message matches {}", regex.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn author(name: &str, email: &str) -> Identity {
+        Identity {
+            name: name.to_string(),
+            email: email.to_string(),
+        }
+    }
+
+    #[test]
+    fn bots_are_matched_by_name_or_email() {
+        let filter = CommitFilter::default().with_bots();
+        assert_eq!(
+            filter
+                .exclusion(
+                    &author(
+                        "dependabot[bot]",
+                        "49699333+dependabot[bot]@users.noreply.github.com"
+                    ),
+                    "Bump x"
+                )
+                .as_deref(),
+            Some("author matches *[bot]*")
+        );
+        assert_eq!(
+            filter
+                .exclusion(&author("Renovate Bot", "bot@renovateapp.com"), "Update")
+                .as_deref(),
+            Some("author matches renovate*")
+        );
+        assert_eq!(
+            filter.exclusion(&author("Jane", "jane@example.org"), "Fix"),
+            None
+        );
+    }
+
+    #[test]
+    fn author_patterns_are_case_insensitive_wildcards() {
+        let filter = CommitFilter::new(&["CI@*".to_string()], &[], false).unwrap();
+        assert!(filter
+            .exclusion(&author("Build", "ci@example.org"), "Release")
+            .is_some());
+        assert!(filter
+            .exclusion(&author("Ci", "dev@example.org"), "Release")
+            .is_none());
+        assert!(CommitFilter::default()
+            .exclusion(&author("dependabot[bot]", ""), "Bump")
+            .is_none());
+    }
+
+    #[test]
+    fn message_patterns_are_regular_expressions() {
+        let filter = CommitFilter::new(&[], &["^Bump ".to_string()], true).unwrap();
+        let jane = author("Jane", "jane@example.org");
+        assert_eq!(
+            filter.exclusion(&jane, "Bump serde to 1.0").as_deref(),
+            Some("message matches ^Bump ")
+        );
+        assert_eq!(filter.exclusion(&jane, "Revert \"Bump serde\""), None);
+        assert!(filter.skips_whitespace_only());
+        assert!(CommitFilter::new(&[], &["(".to_string()], false).is_err());
+    }
+}
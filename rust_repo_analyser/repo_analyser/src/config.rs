@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// The settings of the whole pipeline, read from a TOML file that every command loads.
+/// Every setting is optional: a command takes a setting from its flag when given, else
+/// from this file, else from the flag's default. The effective values are stored with
+/// each analysis run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PipelineConfig {
+    #[serde(skip_serializing_if = "is_default")]
+    pub store: StoreConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub sources: SourcesConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub filters: FiltersConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub analysis: AnalysisConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub scoring: ScoringConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub thresholds: ThresholdsConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub outputs: OutputsConfig,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StoreConfig {
+    /// `neo4j`, `memory` or `sqlite:<path.db>`
+    pub backend: Option<String>,
+    pub neo4j_uri: Option<String>,
+    pub neo4j_database: Option<String>,
+}
+
+/// Where the repositories come from and how they are cloned.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SourcesConfig {
+    /// JSON list of repositories, as written by `github`
+    pub input: Option<String>,
+    pub cache_dir: Option<String>,
+    pub credentials: Option<String>,
+    pub depth: Option<u32>,
+    pub blobless: Option<bool>,
+    pub mirror_dir: Option<String>,
+    pub clone_jobs: Option<usize>,
+    pub jobs: Option<usize>,
+    pub manifest: Option<String>,
+}
+
+/// Which files and commits are analysed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FiltersConfig {
+    /// Comma-separated file extensions, e.g. `.cpp,.h`
+    pub extension: Option<String>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    pub keep_vendored: Option<bool>,
+    pub skip_authors: Option<Vec<String>>,
+    pub skip_message: Option<Vec<String>>,
+    pub skip_bots: Option<bool>,
+    pub skip_whitespace_only: Option<bool>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub range: Option<String>,
+    /// Repositories `copy` leaves out
+    pub ignore_repos: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalysisConfig {
+    pub batch_size: Option<usize>,
+    pub bulk_load: Option<bool>,
+    pub incremental: Option<bool>,
+    pub prune: Option<bool>,
+    pub change_set_gap_minutes: Option<u32>,
+    pub collaboration_window_days: Option<u32>,
+    pub functions: Option<bool>,
+    pub merge_strategy: Option<String>,
+    pub first_parent: Option<bool>,
+    pub normalize_churn: Option<bool>,
+    pub commit_rules: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoringConfig {
+    pub half_life_days: Option<f64>,
+    pub min_coupling: Option<f64>,
+    pub window_commits: Option<usize>,
+    pub window_months: Option<u32>,
+    pub step: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThresholdsConfig {
+    pub max_files_per_commit: Option<usize>,
+    pub max_renames_per_commit: Option<usize>,
+    /// Edges lighter than this are pruned
+    pub prune_threshold: Option<i64>,
+    /// Files at or above this hub score count as hubs
+    pub hub_threshold: Option<f64>,
+    /// CodeScene code health below which a file is risky
+    pub code_health: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputsConfig {
+    pub summary: Option<String>,
+    pub hub_scores: Option<String>,
+    /// Folder `copy` writes hub and non-hub files to, and `metrics` reads them from
+    pub files: Option<String>,
+    pub metrics_csv: Option<String>,
+    pub codescene_csv: Option<String>,
+    pub graph: Option<String>,
+    pub timeline: Option<String>,
+    pub timeline_format: Option<String>,
+    pub szz: Option<String>,
+}
+
+impl PipelineConfig {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config {}: {}", path, e))?;
+        toml::from_str(&content).map_err(|e| format!("Failed to parse config {}: {}", path, e))
+    }
+
+    pub fn to_toml(&self) -> Result<String, String> {
+        toml::to_string(self).map_err(|e| format!("Failed to serialise config: {}", e))
+    }
+}
+
+fn is_default<T: Default + PartialEq>(section: &T) -> bool {
+    *section == T::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_keys_and_sections_are_rejected() {
+        let err = toml::from_str::<PipelineConfig>("[filters]\nextensions = \".cpp\"\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unknown field `extensions`"), "{}", err);
+
+        let err = toml::from_str::<PipelineConfig>("[output]\nsummary = \"s.csv\"\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unknown field `output`"), "{}", err);
+    }
+
+    #[test]
+    fn only_sections_with_settings_are_written() {
+        let mut config = PipelineConfig::default();
+        config.thresholds.max_files_per_commit = Some(50);
+
+        let written = config.to_toml().unwrap();
+        assert_eq!(written, "[thresholds]\nmax_files_per_commit = 50\n");
+        assert_eq!(toml::from_str::<PipelineConfig>(&written).unwrap(), config);
+    }
+}
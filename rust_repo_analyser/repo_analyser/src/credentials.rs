@@ -0,0 +1,216 @@
+use git2::{Cred, CredentialType, FetchOptions, RemoteCallbacks};
+use std::{env, path::PathBuf, process::Command};
+
+const DEFAULT_TOKEN_VAR: &str = "GITHUB_TOKEN";
+
+/// How `clone` and `copy` authenticate when a remote asks for credentials. Public HTTPS
+/// repositories never ask, so `Anonymous` is enough for them.
+#[derive(Clone)]
+pub enum CloneCredentials {
+    Anonymous,
+    /// A personal access token sent as the HTTPS password
+    Token(String),
+    SshAgent,
+    SshKey(PathBuf),
+}
+
+impl CloneCredentials {
+    /// Parses `anonymous`, `token` (reads `GITHUB_TOKEN`), `token:<ENV_VAR>`, `ssh-agent`
+    /// or `ssh-key:<path>`. A token is read from the environment right away, so a missing
+    /// variable fails before anything is cloned.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (kind, arg) = match value.split_once(':') {
+            Some((kind, arg)) => (kind, Some(arg)),
+            None => (value, None),
+        };
+
+        match (kind, arg) {
+            ("anonymous", None) => Ok(CloneCredentials::Anonymous),
+            ("token", var) => {
+                let var = var.unwrap_or(DEFAULT_TOKEN_VAR);
+                let token = env::var(var)
+                    .map_err(|_| format!("Environment variable {} with the token is not set", var))?;
+                Ok(CloneCredentials::Token(token))
+            }
+            ("ssh-agent", None) => Ok(CloneCredentials::SshAgent),
+            ("ssh-key", Some(path)) => Ok(CloneCredentials::SshKey(expand_home(path))),
+            _ => Err(format!(
+                "Unknown credentials '{}': expected anonymous, token[:ENV_VAR], ssh-agent or ssh-key:<path>",
+                value
+            )),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            CloneCredentials::Anonymous => "anonymous".to_string(),
+            CloneCredentials::Token(_) => "token".to_string(),
+            CloneCredentials::SshAgent => "ssh-agent".to_string(),
+            CloneCredentials::SshKey(path) => format!("ssh-key {}", path.display()),
+        }
+    }
+
+    /// Fetch options answering credential requests with this strategy. libgit2 asks again
+    /// after a rejected credential, so only the first request is answered.
+    pub fn fetch_options(&self) -> FetchOptions<'_> {
+        let mut asked = false;
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.credentials(move |url, username_from_url, allowed| {
+            if asked {
+                return Err(git2::Error::from_str(&format!(
+                    "Authentication with {} credentials failed for {}",
+                    self.describe(),
+                    url
+                )));
+            }
+            asked = true;
+            self.credential(url, username_from_url, allowed)
+        });
+
+        let mut fo = FetchOptions::new();
+        fo.remote_callbacks(callbacks);
+        fo
+    }
+
+    /// Makes a `git` command authenticate with this strategy. The token is handed over in
+    /// the environment of the command, not on its command line.
+    pub fn configure_git(&self, command: &mut Command) {
+        match self {
+            CloneCredentials::Anonymous | CloneCredentials::SshAgent => {}
+            CloneCredentials::Token(token) => {
+                command
+                    .env("REPO_ANALYSER_TOKEN", token)
+                    .args(["-c", "credential.helper="])
+                    .args([
+                        "-c",
+                        "credential.helper=!f() { echo username=x-access-token; echo \"password=$REPO_ANALYSER_TOKEN\"; }; f",
+                    ]);
+            }
+            CloneCredentials::SshKey(path) => {
+                // git runs the command through the shell, so a quote in the path must not
+                // end the quoted argument
+                let path = path.display().to_string().replace('\'', "'\\''");
+                command.env(
+                    "GIT_SSH_COMMAND",
+                    format!("ssh -i '{}' -o IdentitiesOnly=yes", path),
+                );
+            }
+        }
+    }
+
+    fn credential(
+        &self,
+        url: &str,
+        username_from_url: Option<&str>,
+        allowed: CredentialType,
+    ) -> Result<Cred, git2::Error> {
+        let unsupported = || {
+            git2::Error::from_str(&format!(
+                "{} asks for credentials that {} cannot provide",
+                url,
+                self.describe()
+            ))
+        };
+
+        match self {
+            CloneCredentials::Anonymous => Err(git2::Error::from_str(&format!(
+                "{} requires authentication; pass --credentials token, ssh-agent or ssh-key:<path>",
+                url
+            ))),
+            CloneCredentials::Token(token) => {
+                if !allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+                    return Err(unsupported());
+                }
+                Cred::userpass_plaintext(username_from_url.unwrap_or("x-access-token"), token)
+            }
+            CloneCredentials::SshAgent => {
+                if !allowed.contains(CredentialType::SSH_KEY) {
+                    return Err(unsupported());
+                }
+                Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"))
+            }
+            CloneCredentials::SshKey(path) => {
+                if !allowed.contains(CredentialType::SSH_KEY) {
+                    return Err(unsupported());
+                }
+                Cred::ssh_key(username_from_url.unwrap_or("git"), None, path, None)
+            }
+        }
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var("HOME")) {
+        (Some(rest), Ok(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn parses_each_strategy() {
+        assert!(matches!(
+            CloneCredentials::parse("anonymous"),
+            Ok(CloneCredentials::Anonymous)
+        ));
+        assert!(matches!(
+            CloneCredentials::parse("ssh-agent"),
+            Ok(CloneCredentials::SshAgent)
+        ));
+        match CloneCredentials::parse("ssh-key:/keys/id_ed25519") {
+            Ok(CloneCredentials::SshKey(path)) => {
+                assert_eq!(path, PathBuf::from("/keys/id_ed25519"))
+            }
+            other => panic!("expected an ssh key, got {:?}", other.map(|c| c.describe())),
+        }
+    }
+
+    #[test]
+    fn token_is_read_from_the_named_variable() {
+        env::set_var("CREDENTIALS_TEST_TOKEN", "secret");
+        match CloneCredentials::parse("token:CREDENTIALS_TEST_TOKEN") {
+            Ok(CloneCredentials::Token(token)) => assert_eq!(token, "secret"),
+            other => panic!("expected a token, got {:?}", other.map(|c| c.describe())),
+        }
+
+        let err = CloneCredentials::parse("token:CREDENTIALS_TEST_MISSING")
+            .err()
+            .unwrap();
+        assert_eq!(
+            err,
+            "Environment variable CREDENTIALS_TEST_MISSING with the token is not set"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_strategies_and_misplaced_arguments() {
+        for value in ["password", "ssh-key", "anonymous:x", "ssh-agent:key"] {
+            let err = CloneCredentials::parse(value).err().unwrap();
+            assert!(
+                err.starts_with(&format!("Unknown credentials '{}'", value)),
+                "{}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn ssh_key_paths_are_quoted_for_the_shell() {
+        let mut command = Command::new("git");
+        CloneCredentials::SshKey(PathBuf::from("/keys/it's mine")).configure_git(&mut command);
+
+        let ssh_command = command
+            .get_envs()
+            .find(|(name, _)| *name == OsStr::new("GIT_SSH_COMMAND"))
+            .and_then(|(_, value)| value)
+            .unwrap();
+        assert_eq!(
+            ssh_command,
+            OsStr::new("ssh -i '/keys/it'\\''s mine' -o IdentitiesOnly=yes")
+        );
+    }
+}
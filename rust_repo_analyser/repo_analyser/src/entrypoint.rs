@@ -1,144 +1,435 @@
-use git2::{Cred, RemoteCallbacks};
+use serde::Serialize;
 use std::{
-    env,
+    collections::{BTreeMap, HashSet},
     fs::{self, File},
-    path::Path,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
 };
+use tokio::runtime::Handle;
+use tokio::sync::Semaphore;
+use tokio::task::{self, JoinSet};
 
+use crate::clone_cache::CloneCache;
 use crate::codescene_client::{label_from_code_health, CodeSceneClient};
 use crate::git_analyzer::GitAnalyzer;
-use crate::storage::Neo4jClient;
+use crate::manifest::{Manifest, Stage};
+use crate::storage::GraphStore;
+use crate::szz::find_bug_introducing;
+use crate::timeline::{compute_timeline, series_by_file, TimelineWindow};
 
 fn extract_repo_name(url: &str) -> Option<String> {
     let mut name_url = String::from(url);
     if name_url.chars().last() == Some('/') {
         name_url.pop();
     }
-    name_url
-        .split("github.com/")
-        .nth(1)?
+    let repo_path = match name_url.strip_prefix("file://") {
+        Some(path) => path,
+        None => name_url.split("github.com/").nth(1)?,
+    };
+    repo_path
         .split('/')
         .last()
         .map(|s| s.trim_end_matches(".git").to_string())
 }
 
+/// How one repository of a `clone` run ended, as written to the JSON summary.
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoOutcome {
+    pub repo: String,
+    pub url: String,
+    pub status: RepoStatus,
+    pub stage: Stage,
+    pub cached: bool,
+    pub commits: Option<i64>,
+    pub skipped_commits: BTreeMap<String, i64>,
+    pub elapsed_secs: f64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepoStatus {
+    Analysed,
+    Skipped,
+    Failed,
+}
+
+/// How a `clone` run schedules its repositories and where it records their progress.
+pub struct CloneRunOptions {
+    pub clone_jobs: usize,
+    pub analysis_jobs: usize,
+    pub summary_path: String,
+    pub manifest_path: String,
+    pub restart: bool,
+}
+
+/// Clones every GitHub repository listed in `json_file` into `cache` and analyses it with
+/// a copy of `analyser`, which carries the analysis settings. Up to `clone_jobs`
+/// clones and `analysis_jobs` analyses run at once on blocking threads; their writes share
+/// the store, whose own lock serialises them. One repository failing does not stop the
+/// others, and the outcome of each is written to `summary_path`.
+///
+/// Each completed stage is recorded in the manifest at `manifest_path`, so a rerun skips
+/// repositories that already have hub scores and resumes the others, failed ones included,
+/// at the stage they stopped at. `restart` ignores the recorded stages.
 pub async fn analyse_github_repos(
     json_file: String,
-    neo4j_uri: String,
-    _folder_path: String,
-    extension: String,
+    client: Arc<dyn GraphStore>,
+    cache: CloneCache,
+    analyser: GitAnalyzer,
+    options: CloneRunOptions,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let client = Neo4jClient::new(&neo4j_uri).await?;
+    let CloneRunOptions {
+        clone_jobs,
+        analysis_jobs,
+        summary_path,
+        manifest_path,
+        restart,
+    } = options;
+    // Without extensions every blob in history would be fetched lazily, one at a time
+    if cache.is_blobless() && analyser.extensions().split(',').all(str::is_empty) {
+        return Err(
+            "--blobless needs --extension (or filters.extension in the config) to select the files whose blobs are fetched"
+                .into(),
+        );
+    }
     client.init_schema().await?;
 
     let file = File::open(json_file)?;
     let repos: Vec<serde_json::Value> = serde_json::from_reader(file)?;
-    let mut callbacks = RemoteCallbacks::new();
-    callbacks.credentials(|_url, username_from_url, _allowed_types| {
-        Cred::ssh_key(
-            username_from_url.unwrap(),
-            None,
-            std::path::Path::new(&format!("{}/.ssh/id_ed25519", env::var("HOME").unwrap())),
-            None,
-        )
-    });
-
-    let mut fo = git2::FetchOptions::new();
-    fo.remote_callbacks(callbacks);
-
-    let mut builder = git2::build::RepoBuilder::new();
-    builder.fetch_options(fo);
 
-    let cache_base = Path::new("./repo_cache");
-    fs::create_dir_all(cache_base)?;
+    fs::create_dir_all(&cache.dir)?;
 
+    let mut names = HashSet::new();
+    let mut queued = Vec::new();
     for repo in repos {
         let repo_url = repo["repo_url"].as_str().unwrap_or("");
-        if repo_url.contains("github.com") {
-            let repo_name = extract_repo_name(repo_url).unwrap_or_else(|| "unknown".to_string());
-            println!("{}", repo_name);
+        if !repo_url.contains("github.com") && !repo_url.starts_with("file://") {
+            continue;
+        }
+        let repo_name = extract_repo_name(repo_url).unwrap_or_else(|| "unknown".to_string());
+        // Repositories sharing a name would share a clone directory and a graph
+        if !names.insert(repo_name.clone()) {
+            println!(
+                "Skipping {}: a repository named {} is already queued",
+                repo_url, repo_name
+            );
+            continue;
+        }
+        queued.push((repo_name, repo_url.to_string()));
+    }
 
-            println!("Processing: {}", repo_url);
+    let manifest = Arc::new(if restart {
+        Manifest::fresh(&manifest_path)
+    } else {
+        Manifest::load(&manifest_path)?
+    });
+    manifest.register(&queued)?;
 
-            let repo_clone_path = cache_base.join(&repo_name);
+    let context = Arc::new(WorkerContext {
+        clone_slots: Semaphore::new(clone_jobs.max(1)),
+        analysis_slots: Semaphore::new(analysis_jobs.max(1)),
+        extension: analyser.extensions(),
+        cache,
+    });
+    let mut outcomes = Vec::new();
+    let mut workers = JoinSet::new();
+
+    for (repo_name, repo_url) in queued {
+        let repo_clone_path = context.cache.dir.join(&repo_name);
+        let stage = manifest
+            .state(&repo_name)
+            .map(|state| state.stage)
+            .unwrap_or(Stage::Queued);
+        if stage >= Stage::HubScored {
+            println!("Skipping {}: already {}", repo_name, stage);
+            outcomes.push(RepoOutcome {
+                repo: repo_name,
+                url: repo_url,
+                status: RepoStatus::Skipped,
+                stage,
+                cached: repo_clone_path.exists(),
+                commits: None,
+                skipped_commits: BTreeMap::new(),
+                elapsed_secs: 0.0,
+                error: None,
+            });
+            continue;
+        }
 
-            if repo_clone_path.exists() {
-                println!("Using cached repo: {}", repo_name);
-            } else {
-                println!("Cloning {}...", repo_name);
-                builder
-                    .clone(repo_url, &repo_clone_path)
-                    .map_err(|e| format!("Clone failed: {}", e))?;
-            }
+        let analyser = analyser
+            .for_repo(repo_clone_path.display().to_string(), repo_url.clone())
+            .with_analysed_paths_only(context.cache.is_blobless());
+
+        workers.spawn(process_repo(
+            repo_name,
+            repo_url,
+            repo_clone_path,
+            analyser,
+            client.clone(),
+            manifest.clone(),
+            context.clone(),
+        ));
+    }
 
-            let analyser = GitAnalyzer::new(
-                repo_clone_path.display().to_string(),
-                repo_url.to_string(),
-                extension.clone(),
-            );
+    while let Some(outcome) = workers.join_next().await {
+        outcomes.push(outcome?);
+    }
+    outcomes.sort_by(|a, b| a.repo.cmp(&b.repo));
+
+    let count = |status| {
+        outcomes
+            .iter()
+            .filter(|outcome| outcome.status == status)
+            .count()
+    };
+    let (analysed, skipped, failed) = (
+        count(RepoStatus::Analysed),
+        count(RepoStatus::Skipped),
+        count(RepoStatus::Failed),
+    );
+    fs::write(&summary_path, serde_json::to_string_pretty(&outcomes)?)?;
+    println!(
+        "Analysed {} repositories, {} already done, {} failed; summary saved to {}",
+        analysed, skipped, failed, summary_path
+    );
+    println!("Manifest saved to {}", manifest_path);
+
+    Ok(())
+}
 
-            let max_files_per_commit = 200;
-            let max_renames_per_commit = 300;
+// Shared by the workers of a `clone` run: slots limiting how many clones and analyses
+// are in flight, the extensions a blobless clone fetches and the cache to clone into
+struct WorkerContext {
+    clone_slots: Semaphore,
+    analysis_slots: Semaphore,
+    extension: String,
+    cache: CloneCache,
+}
 
-            match analyser
-                .analyze(
-                    &client,
-                    &repo_name,
-                    max_files_per_commit,
-                    max_renames_per_commit,
-                )
+async fn process_repo(
+    repo_name: String,
+    repo_url: String,
+    repo_clone_path: PathBuf,
+    analyser: GitAnalyzer,
+    client: Arc<dyn GraphStore>,
+    manifest: Arc<Manifest>,
+    context: Arc<WorkerContext>,
+) -> RepoOutcome {
+    let started = Instant::now();
+    let resume_at = manifest
+        .state(&repo_name)
+        .map(|state| state.stage)
+        .unwrap_or(Stage::Queued);
+    // A directory the manifest does not record as cloned, e.g. one cloned before the manifest
+    // existed, is reused if it holds a repository with a commit checked out
+    let cached =
+        repo_clone_path.exists() && (resume_at >= Stage::Cloned || has_head(&repo_clone_path));
+    let mut outcome = RepoOutcome {
+        repo: repo_name.clone(),
+        url: repo_url.clone(),
+        status: RepoStatus::Failed,
+        stage: resume_at,
+        cached,
+        commits: None,
+        skipped_commits: BTreeMap::new(),
+        elapsed_secs: 0.0,
+        error: None,
+    };
+
+    let result = async {
+        if resume_at < Stage::Analysed {
+            if cached {
+                println!("Using cached repo: {}", repo_name);
+            } else {
+                let _slot = context
+                    .clone_slots
+                    .acquire()
+                    .await
+                    .map_err(|e| format!("Clone pool closed: {}", e))?;
+                if repo_clone_path.exists() {
+                    println!("Removing broken clone of {}", repo_name);
+                    fs::remove_dir_all(&repo_clone_path).map_err(|e| {
+                        format!("Failed to remove broken clone of {}: {}", repo_name, e)
+                    })?;
+                }
+                let source = context.cache.source(&repo_name, &repo_url);
+                if source == repo_url {
+                    println!("Cloning {}...", repo_name);
+                } else {
+                    println!("Cloning {} from {}...", repo_name, source);
+                }
+                let path = repo_clone_path.clone();
+                let context = context.clone();
+                task::spawn_blocking(move || {
+                    context.cache.clone_repo(&source, &path, &context.extension)
+                })
                 .await
-            {
-                Ok(commit_count) => {
-                    client.compute_hub_scores(&repo_name, 0.0).await?;
-                    println!(
-                        "Saved {} to Neo4j with {} commits analyzed",
-                        repo_name, commit_count
-                    );
+                .map_err(|e| format!("Clone of {} panicked: {}", repo_name, e))??;
+            }
+            manifest.advance(&repo_name, &repo_url, Stage::Cloned)?;
+        }
+
+        let _slot = context
+            .analysis_slots
+            .acquire()
+            .await
+            .map_err(|e| format!("Analysis pool closed: {}", e))?;
+        // The history walk is blocking git work, so each analysis gets its own thread
+        let runtime = Handle::current();
+        let name = repo_name.clone();
+        let url = repo_url.clone();
+        let manifest = manifest.clone();
+        task::spawn_blocking(move || {
+            runtime.block_on(async {
+                let summary = if resume_at < Stage::Analysed {
+                    println!("Processing: {}", url);
+                    // An earlier run may have stopped halfway through this repository
+                    client.delete_repository(&name).await?;
+                    let summary = analyser.analyze(client.as_ref(), &name).await?;
+                    manifest.advance(&name, &url, Stage::Analysed)?;
+                    Some(summary)
+                } else {
+                    println!("Resuming {} at hub scores", name);
+                    None
+                };
+                client.compute_hub_scores(&name, 0.0).await?;
+                manifest.advance(&name, &url, Stage::HubScored)?;
+                Ok::<_, String>(summary)
+            })
+        })
+        .await
+        .map_err(|e| format!("Analysis of {} panicked: {}", repo_name, e))?
+    }
+    .await;
+
+    match result {
+        Ok(summary) => {
+            if let Some(summary) = summary {
+                println!(
+                    "Saved {} with {} commits analyzed",
+                    repo_name, summary.commit_count
+                );
+                println!("Throughput: {}", summary.throughput());
+                for (reason, count) in &summary.skip_reasons {
+                    println!("Skipped {} commits: {}", count, reason);
                 }
-                Err(err) => println!("Error analysing {} repo : {}", repo_url, err),
+                outcome.commits = Some(summary.commit_count);
+                outcome.skipped_commits = summary.skip_reasons;
+            }
+            outcome.status = RepoStatus::Analysed;
+        }
+        Err(err) => {
+            println!("Error analysing {} repo : {}", repo_url, err);
+            if let Err(e) = manifest.fail(&repo_name, &repo_url, &err) {
+                println!("Could not record the failure of {}: {}", repo_name, e);
             }
+            outcome.error = Some(err);
+        }
+    }
+    if let Some(state) = manifest.state(&repo_name) {
+        outcome.stage = state.stage;
+    }
+    outcome.elapsed_secs = started.elapsed().as_secs_f64();
+    outcome
+}
+
+fn has_head(path: &Path) -> bool {
+    git2::Repository::open(path)
+        .and_then(|repo| repo.head()?.peel_to_commit().map(|_| ()))
+        .is_ok()
+}
+
+/// Prints the stage of every repository in the manifest at `manifest_path`, followed by
+/// the number of repositories per stage.
+pub fn print_manifest_status(
+    manifest_path: String,
+    failed_only: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !Path::new(&manifest_path).exists() {
+        return Err(format!("No manifest at {}", manifest_path).into());
+    }
+    let repos = Manifest::load(&manifest_path)?.repos();
+
+    let mut stages: BTreeMap<Stage, usize> = BTreeMap::new();
+    let mut failed = 0;
+    for (repo, state) in &repos {
+        *stages.entry(state.stage).or_default() += 1;
+        if state.error.is_some() {
+            failed += 1;
+        } else if failed_only {
+            continue;
+        }
+
+        println!("{:<40} {:<11} {}", repo, state.stage, state.updated_at);
+        if let Some(error) = &state.error {
+            println!("    failed {} times: {}", state.failures, error);
         }
     }
 
+    let counts: Vec<String> = stages
+        .iter()
+        .map(|(stage, count)| format!("{} {}", count, stage))
+        .collect();
+    println!(
+        "{} repositories: {}; {} failed",
+        repos.len(),
+        counts.join(", "),
+        failed
+    );
+
     Ok(())
 }
 
+/// Analyses the checkout `analyser` points at as `repo_name`. With `prune_threshold`,
+/// edges lighter than it and the files left without edges are then removed.
 pub async fn analyze_local_repo(
-    repo_path: String,
+    analyser: GitAnalyzer,
     repo_name: String,
-    neo4j_uri: String,
-    neo4j_database: String,
-    prune: bool,
-    threshold: i64,
-    max_files_per_commit: usize,
-    max_renames_per_commit: usize,
+    client: &dyn GraphStore,
+    incremental: bool,
+    prune_threshold: Option<i64>,
     hub_threshold: f64,
-    extension: String,
     output_csv: String,
 ) -> Result<i64, Box<dyn std::error::Error>> {
-    let client = Neo4jClient::new_with_database(&neo4j_uri, &neo4j_database).await?;
+    if incremental && !analyser.window().is_empty() {
+        return Err("--incremental cannot be combined with --since, --until or --range".into());
+    }
+
     client.init_schema().await?;
-    client.delete_all_nodes().await?;
-
-    let analyser = GitAnalyzer::new(repo_path.clone(), "null".to_string(), extension.clone());
-    let commit_count = analyser
-        .analyze(
-            &client,
-            &repo_name,
-            max_files_per_commit,
-            max_renames_per_commit,
-        )
-        .await?;
 
-    println!(
-        "Saved {} to Neo4j with {} commits analyzed",
-        repo_name, commit_count
-    );
+    let resume = incremental && client.get_checkpoint(&repo_name).await?.is_some();
+    if !resume {
+        client.delete_repository(&repo_name).await?;
+    }
+
+    let analyser = analyser.with_incremental(resume);
+    let summary = analyser.analyze(client, &repo_name).await?;
+
+    let commit_count = summary.commit_count;
+    if let Some(last_commit) = &summary.resumed_from {
+        println!(
+            "Updated {} with {} new commits after {}",
+            repo_name, commit_count, last_commit
+        );
+    } else {
+        println!("Saved {} with {} commits analyzed", repo_name, commit_count);
+    }
+    println!("Throughput: {}", summary.throughput());
+    for (reason, count) in &summary.skip_reasons {
+        println!("Skipped {} commits: {}", count, reason);
+    }
 
-    if prune {
-        let deleted = client.remove_low_importance_connections(threshold).await?;
-        println!("Pruned {} edges with weight < {}", deleted, threshold);
+    if let Some(threshold) = prune_threshold {
+        let deleted = client
+            .remove_low_importance_connections(&repo_name, threshold)
+            .await?;
+        let orphans = client.remove_orphan_files(&repo_name).await?;
+        println!(
+            "Pruned {} edges with weight < {} and {} orphan files",
+            deleted, threshold, orphans
+        );
     }
 
     client.compute_hub_scores(&repo_name, 0.0).await?;
@@ -146,13 +437,15 @@ pub async fn analyze_local_repo(
 
     let temp_folder = format!("/tmp/{}", repo_name);
     copy_files_by_hub_threshold(
-        neo4j_uri,
-        neo4j_database,
+        client,
         hub_threshold,
         temp_folder.clone(),
-        extension,
+        analyser.extensions(),
         vec![],
-        Some(repo_path.clone()),
+        FileSource::Local {
+            repo: repo_name.clone(),
+            path: analyser.repo_path().to_string(),
+        },
     )
     .await?;
 
@@ -163,19 +456,85 @@ pub async fn analyze_local_repo(
     Ok(commit_count)
 }
 
+pub fn export_timeline(
+    analyser: GitAnalyzer,
+    window: TimelineWindow,
+    step: usize,
+    min_coupling: f64,
+    output: String,
+    format: String,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let repo_name = Path::new(analyser.repo_path())
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let history = analyser.read_history()?;
+
+    let points = compute_timeline(&repo_name, &history, window, step, min_coupling);
+
+    match format.as_str() {
+        "csv" => {
+            let mut wtr = csv::Writer::from_path(&output)?;
+            for point in &points {
+                wtr.serialize(point)?;
+            }
+            wtr.flush()?;
+        }
+        "json" => {
+            let json = serde_json::to_string_pretty(&series_by_file(&points))?;
+            fs::write(&output, json)?;
+        }
+        other => {
+            return Err(format!("Unknown timeline format '{}', expected csv or json", other).into())
+        }
+    }
+
+    Ok(points.len())
+}
+
+pub fn export_szz(
+    analyser: GitAnalyzer,
+    output: String,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let history = analyser.read_history()?;
+
+    let repo = git2::Repository::open(analyser.repo_path())?;
+    let files = find_bug_introducing(&repo, &history)?;
+
+    let mut wtr = csv::Writer::from_path(&output)?;
+    for file in &files {
+        wtr.serialize(file)?;
+    }
+    wtr.flush()?;
+
+    Ok(files.len())
+}
+
+/// Where `copy_files_by_hub_threshold` reads the files to copy from.
+pub enum FileSource {
+    /// The checkout at `path` of `repo`, the only repository copied
+    Local { repo: String, path: String },
+    /// The clones in the cache, made when missing, of every repository in the store
+    Cache(CloneCache),
+}
+
+/// Copies the files of the repositories in `source` into `high/` or `low/` of
+/// `output_dir` by their hub score.
 pub async fn copy_files_by_hub_threshold(
-    neo4j_uri: String,
-    neo4j_database: String,
+    client: &dyn GraphStore,
     hub_threshold: f64,
     output_dir: String,
     extension: String,
     ignore_repos: Vec<String>,
-    local_path: Option<String>,
+    source: FileSource,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let client = Neo4jClient::new_with_database(&neo4j_uri, &neo4j_database).await?;
-
+    let only_repo = match &source {
+        FileSource::Local { repo, .. } => Some(repo.as_str()),
+        FileSource::Cache(_) => None,
+    };
     let repos_with_files = client
-        .get_files_by_hub_threshold(hub_threshold, &extension, &ignore_repos)
+        .get_files_by_hub_threshold(only_repo, hub_threshold, &extension, &ignore_repos)
         .await?;
 
     let high_dir = Path::new(&output_dir).join("high");
@@ -184,24 +543,9 @@ pub async fn copy_files_by_hub_threshold(
     fs::create_dir_all(&high_dir)?;
     fs::create_dir_all(&low_dir)?;
 
-    let cache_base = Path::new("./repo_cache");
-    fs::create_dir_all(cache_base)?;
-
-    let mut callbacks = RemoteCallbacks::new();
-    callbacks.credentials(|_url, username_from_url, _allowed_types| {
-        Cred::ssh_key(
-            username_from_url.unwrap(),
-            None,
-            std::path::Path::new(&format!("{}/.ssh/id_ed25519", env::var("HOME").unwrap())),
-            None,
-        )
-    });
-
-    let mut fo = git2::FetchOptions::new();
-    fo.remote_callbacks(callbacks);
-
-    let mut builder = git2::build::RepoBuilder::new();
-    builder.fetch_options(fo);
+    if let FileSource::Cache(cache) = &source {
+        fs::create_dir_all(&cache.dir)?;
+    }
 
     let mut high_count = 0;
     let mut low_count = 0;
@@ -216,98 +560,76 @@ pub async fn copy_files_by_hub_threshold(
             continue;
         }
 
-        if let Some(ref local) = local_path {
-            println!(
-                "Using local repo for {} - {} files (high: {})",
-                repo_name,
-                repo_data.files.len(),
-                is_high_risk
-            );
-            let source_base = Path::new(local);
-
-            for file in &repo_data.files {
-                let source_file = source_base.join(&file.path);
-                if !source_file.exists() {
-                    println!("  File not found: {}", file.path);
+        let checkout = match &source {
+            FileSource::Local { path, .. } => {
+                println!(
+                    "Using local repo for {} - {} files (high: {})",
+                    repo_name,
+                    repo_data.files.len(),
+                    is_high_risk
+                );
+                PathBuf::from(path)
+            }
+            FileSource::Cache(cache) => {
+                if repo_url.is_empty() {
+                    println!("Skipping {} - no repo URL", repo_name);
                     continue;
                 }
 
-                let dest_filename = format!("{}__{}", repo_name, file.path.replace('/', "_"));
-
-                let target_dir = if file.hub_score >= hub_threshold {
-                    &high_dir
+                println!(
+                    "Processing {} - {} files (high: {})",
+                    repo_name,
+                    repo_data.files.len(),
+                    is_high_risk
+                );
+
+                let repo_clone_path = cache.dir.join(repo_name);
+
+                if !repo_clone_path.exists() {
+                    let source = cache.source(repo_name, repo_url);
+                    println!("Cloning {} from {}...", repo_name, source);
+                    cache
+                        .clone_repo(&source, &repo_clone_path, &extension)
+                        .map_err(|e| format!("Failed to clone {}: {}", repo_url, e))?;
                 } else {
-                    &low_dir
-                };
-
-                if file.hub_score >= hub_threshold {
-                    high_count += 1;
-                } else {
-                    low_count += 1;
+                    println!("Using cached repo: {}", repo_name);
                 }
-
-                let dest_file = target_dir.join(&dest_filename);
-
-                fs::copy(&source_file, &dest_file)
-                    .map_err(|e| format!("Failed to copy {}: {}", file.path, e))?;
-
-                println!("  Copied: {}", dest_filename);
+                repo_clone_path
             }
-        } else {
-            if repo_url.is_empty() {
-                println!("Skipping {} - no repo URL", repo_name);
+        };
+
+        for file in &repo_data.files {
+            let source_file = checkout.join(&file.path);
+            if !source_file.exists() {
+                println!("  File not found: {}", file.path);
                 continue;
             }
 
-            println!(
-                "Processing {} - {} files (high: {})",
-                repo_name,
-                repo_data.files.len(),
-                is_high_risk
-            );
+            let dest_filename = format!("{}__{}", repo_name, file.path.replace('/', "_"));
 
-            let repo_clone_path = cache_base.join(repo_name);
+            let target_dir = if file.hub_score >= hub_threshold {
+                &high_dir
+            } else {
+                &low_dir
+            };
 
-            if !repo_clone_path.exists() {
-                println!("Cloning {}...", repo_name);
-                builder
-                    .clone(repo_url, &repo_clone_path)
-                    .map_err(|e| format!("Failed to clone {}: {}", repo_url, e))?;
+            if file.hub_score >= hub_threshold {
+                high_count += 1;
             } else {
-                println!("Using cached repo: {}", repo_name);
+                low_count += 1;
             }
 
-            for file in &repo_data.files {
-                let source_file = repo_clone_path.join(&file.path);
-                if !source_file.exists() {
-                    println!("  File not found: {}", file.path);
-                    continue;
-                }
+            let dest_file = target_dir.join(&dest_filename);
 
-                let dest_filename = format!("{}__{}", repo_name, file.path.replace('/', "_"));
+            fs::copy(&source_file, &dest_file)
+                .map_err(|e| format!("Failed to copy {}: {}", file.path, e))?;
 
-                let target_dir = if file.hub_score >= hub_threshold {
-                    &high_dir
-                } else {
-                    &low_dir
-                };
-
-                if file.hub_score >= hub_threshold {
-                    high_count += 1;
-                } else {
-                    low_count += 1;
-                }
-
-                let dest_file = target_dir.join(&dest_filename);
-
-                fs::copy(&source_file, &dest_file)
-                    .map_err(|e| format!("Failed to copy {}: {}", file.path, e))?;
-
-                println!("  Copied: {}", dest_filename);
-            }
+            println!("  Copied: {}", dest_filename);
         }
 
-        println!("Kept in cache: {}", repo_name);
+        if let FileSource::Cache(_) = &source {
+            println!("Kept in cache: {}", repo_name);
+        }
     }
 
     println!(
@@ -1,36 +1,237 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+use crate::commit_class::{ClassCounts, CommitClass};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Edge {
     pub target: String,
     pub weight: u32,
     pub target_commits: u32,
+    #[serde(default)]
+    pub decayed_weight: Option<f64>,
+}
+/// A commit author or committer after `.mailmap` resolution.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+}
+
+impl Identity {
+    /// Authors are keyed by lower-cased email, falling back to the name when a commit has
+    /// no email.
+    pub fn key(&self) -> String {
+        if self.email.is_empty() {
+            self.name.clone()
+        } else {
+            self.email.to_lowercase()
+        }
+    }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Author {
+    pub email: String,
+    pub name: String,
+    pub commit_count: u32,
+    pub committed_count: u32,
+}
+
+/// How much one author contributed to a file: commits touching it and their churn.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileAuthor {
+    pub email: String,
+    pub commits: u32,
+    pub churn: u32,
+}
+
+/// The commits referencing one issue-tracker key and how many of them touched each file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Issue {
+    pub key: String,
+    pub commits: Vec<String>,
+    pub files: Vec<IssueFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssueFile {
+    pub path: String,
+    pub commits: u32,
+}
+
 #[derive(Debug, Clone)]
 pub struct ChangedFile {
     pub path: String,
     pub additions: u32,
     pub deletions: u32,
+    /// Line counts with whitespace changes and formatting-only hunks left out; the raw
+    /// counts unless churn normalization is on.
+    pub normalized_additions: u32,
+    pub normalized_deletions: u32,
     pub is_deleted: bool,
     pub renamed_to: Option<String>,
 }
 
+/// Changed lines inside one function of a file. `name` includes the enclosing classes and
+/// namespaces (`Tracker::fit`).
+#[derive(Debug, Clone)]
+pub struct ChangedFunction {
+    pub path: String,
+    pub name: String,
+    pub churn: u32,
+}
+
+/// A function or method with the commits and changed lines that touched it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Function {
+    pub path: String,
+    pub name: String,
+    pub commit_count: u32,
+    pub churn: u32,
+}
+
+/// Undirected co-change between two functions, stored once with the source ordered
+/// before the target by `(path, name)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionEdge {
+    pub source_path: String,
+    pub source_name: String,
+    pub target_path: String,
+    pub target_name: String,
+    pub weight: u32,
+}
+
+/// Everything one analysed commit contributes to the graph. Renames are applied before
+/// the file changes; large commits only carry renames and deletions and skip co-change
+/// pairs. `decay` is the commit's time-decay factor when decayed weights are recorded,
+/// `class` the category of its message and `issues` the issue keys it references.
+/// `change_set_files` holds the files touched earlier in the commit's logical change set
+/// when consecutive commits are grouped, and `functions` the changed functions when
+/// function-level co-change is recorded.
+#[derive(Debug, Clone)]
+pub struct CommitChanges {
+    pub commit_hash: String,
+    pub commit_time: i64,
+    pub renames: Vec<(String, String)>,
+    pub files: Vec<ChangedFile>,
+    pub record_cochanges: bool,
+    pub decay: Option<f64>,
+    pub author: Identity,
+    pub committer: Identity,
+    pub class: CommitClass,
+    pub issues: Vec<String>,
+    pub change_set_files: Vec<String>,
+    pub functions: Vec<ChangedFunction>,
+}
+
+impl CommitChanges {
+    /// Pairs the commit adds to its change set: its new files with each other and with the
+    /// files already in the set. Pairs are ordered `(a, b)` with `a < b`.
+    pub fn cochange_pairs(&self) -> Vec<(&str, &str)> {
+        if !self.record_cochanges {
+            return Vec::new();
+        }
+
+        let mut unique_files: Vec<&str> = self
+            .files
+            .iter()
+            .map(|f| f.path.as_str())
+            .filter(|path| !self.change_set_files.iter().any(|p| p == path))
+            .collect();
+        unique_files.sort();
+        unique_files.dedup();
+
+        let mut pairs = Vec::new();
+        for i in 0..unique_files.len() {
+            for j in (i + 1)..unique_files.len() {
+                pairs.push((unique_files[i], unique_files[j]));
+            }
+            for earlier in &self.change_set_files {
+                let earlier = earlier.as_str();
+                if unique_files[i] < earlier {
+                    pairs.push((unique_files[i], earlier));
+                } else {
+                    pairs.push((earlier, unique_files[i]));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Every pair of functions the commit changed, ordered by `(path, name)`. Like file
+    /// pairs, large commits have none.
+    pub fn function_pairs(&self) -> Vec<(&ChangedFunction, &ChangedFunction)> {
+        if !self.record_cochanges {
+            return Vec::new();
+        }
+
+        let mut functions: Vec<&ChangedFunction> = self.functions.iter().collect();
+        functions.sort_by(|a, b| (&a.path, &a.name).cmp(&(&b.path, &b.name)));
+        functions.dedup_by(|a, b| a.path == b.path && a.name == b.name);
+
+        let mut pairs = Vec::new();
+        for i in 0..functions.len() {
+            for j in (i + 1)..functions.len() {
+                pairs.push((functions[i], functions[j]));
+            }
+        }
+        pairs
+    }
+
+    /// Files linked to the commit's issues. Large commits reference issues without
+    /// touching files, like their missing co-change pairs.
+    pub fn issue_files(&self) -> Vec<&str> {
+        if !self.record_cochanges {
+            return Vec::new();
+        }
+
+        let mut paths: Vec<&str> = self.files.iter().map(|f| f.path.as_str()).collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    pub fn deleted_at_commit(&self, file: &ChangedFile) -> Option<&str> {
+        if file.is_deleted {
+            Some(self.commit_hash.as_str())
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileNode {
     pub path: String,
     pub additions: u32,
     pub deletions: u32,
+    #[serde(default)]
+    pub normalized_additions: u32,
+    #[serde(default)]
+    pub normalized_deletions: u32,
     pub commit_count: u32,
     #[serde(default)]
     pub edges: Vec<Edge>,
     #[serde(default)]
     pub deleted_at_commit: Option<String>,
+    #[serde(default)]
+    pub decayed_churn: Option<f64>,
+    #[serde(default)]
+    pub decayed_commit_count: Option<f64>,
+    #[serde(default)]
+    pub authors: Vec<FileAuthor>,
+    #[serde(default)]
+    pub commit_classes: ClassCounts,
 }
 
+#[derive(Clone)]
 pub struct FileGraphBuilder {
     repo: String,
     node_map: HashMap<String, NodeBuilder>,
+    authors: HashMap<String, Author>,
+    issues: HashMap<String, IssueBuilder>,
+    functions: HashMap<String, HashMap<String, FunctionBuilder>>,
     total_commits: u32,
 }
 
@@ -39,20 +240,58 @@ pub struct FileGraph {
     pub repo: String,
     pub node_map: Vec<FileNode>,
     pub total_commits_analyzed: u32,
+    #[serde(default)]
+    pub authors: Vec<Author>,
+    #[serde(default)]
+    pub issues: Vec<Issue>,
+    #[serde(default)]
+    pub functions: Vec<Function>,
+    #[serde(default)]
+    pub function_edges: Vec<FunctionEdge>,
 }
 
+#[derive(Clone)]
 struct NodeBuilder {
     path: String,
     additions: u32,
     deletions: u32,
+    normalized_additions: u32,
+    normalized_deletions: u32,
     commit_count: u32,
     edges: HashMap<String, EdgeBuilder>,
     deleted_at_commit: Option<String>,
+    decayed_churn: Option<f64>,
+    decayed_commit_count: Option<f64>,
+    authors: HashMap<String, FileAuthor>,
+    commit_classes: ClassCounts,
+}
+
+#[derive(Clone, Default)]
+struct IssueBuilder {
+    commits: Vec<String>,
+    files: HashMap<String, u32>,
+}
+
+// Function edges are kept in both directions, keyed by the partner's (path, name)
+#[derive(Clone, Default)]
+struct FunctionBuilder {
+    commit_count: u32,
+    churn: u32,
+    edges: HashMap<(String, String), u32>,
 }
 
+#[derive(Clone)]
 struct EdgeBuilder {
     target: String,
     weight: u32,
+    decayed_weight: Option<f64>,
+}
+
+fn add_optional(total: Option<f64>, value: Option<f64>) -> Option<f64> {
+    match (total, value) {
+        (Some(total), Some(value)) => Some(total + value),
+        (total, value) => total.or(value),
+    }
 }
 
 impl FileGraphBuilder {
@@ -61,6 +300,9 @@ impl FileGraphBuilder {
             repo,
             total_commits: 0,
             node_map: HashMap::new(),
+            authors: HashMap::new(),
+            issues: HashMap::new(),
+            functions: HashMap::new(),
         }
     }
 
@@ -71,6 +313,141 @@ impl FileGraphBuilder {
         unique_files.dedup();
 
         for file in changed_files {
+            let deleted_at_commit = if file.is_deleted { commit_hash } else { None };
+            self.record_file_change(
+                &file.path,
+                file.additions,
+                file.deletions,
+                file.normalized_additions,
+                file.normalized_deletions,
+                deleted_at_commit,
+            );
+        }
+
+        for i in 0..unique_files.len() {
+            for j in (i + 1)..unique_files.len() {
+                self.record_cochange(unique_files[i], unique_files[j]);
+            }
+        }
+    }
+
+    /// Folds one analysed commit into the graph: renames first, then file changes and,
+    /// unless the commit was too large, its co-change pairs.
+    pub fn apply_commit(&mut self, commit: &CommitChanges) {
+        self.total_commits += 1;
+
+        for (old_path, new_path) in &commit.renames {
+            self.rename_file(old_path, new_path);
+        }
+
+        self.record_identity(&commit.author, 1, 0);
+        self.record_identity(&commit.committer, 0, 1);
+        let author = commit.author.key();
+
+        for key in &commit.issues {
+            let issue = self.issues.entry(key.clone()).or_default();
+            issue.commits.push(commit.commit_hash.clone());
+            for path in commit.issue_files() {
+                *issue.files.entry(path.to_string()).or_insert(0) += 1;
+            }
+        }
+
+        for file in &commit.files {
+            self.record_file_change(
+                &file.path,
+                file.additions,
+                file.deletions,
+                file.normalized_additions,
+                file.normalized_deletions,
+                commit.deleted_at_commit(file),
+            );
+            let node = self.node_map.get_mut(&file.path).unwrap();
+            node.commit_classes.add(commit.class);
+            let churn = file.additions + file.deletions;
+            if let Some(decay) = commit.decay {
+                *node.decayed_churn.get_or_insert(0.0) += decay * churn as f64;
+                *node.decayed_commit_count.get_or_insert(0.0) += decay;
+            }
+            let contribution = node
+                .authors
+                .entry(author.clone())
+                .or_insert_with(|| FileAuthor {
+                    email: author.clone(),
+                    commits: 0,
+                    churn: 0,
+                });
+            contribution.commits += 1;
+            contribution.churn += churn;
+        }
+
+        for function in &commit.functions {
+            let node = self
+                .functions
+                .entry(function.path.clone())
+                .or_default()
+                .entry(function.name.clone())
+                .or_default();
+            node.commit_count += 1;
+            node.churn += function.churn;
+        }
+        for (source, target) in commit.function_pairs() {
+            self.record_function_cochange(
+                (&source.path, &source.name),
+                (&target.path, &target.name),
+                1,
+            );
+        }
+
+        for (source, target) in commit.cochange_pairs() {
+            if !self.record_cochange(source, target) {
+                continue;
+            }
+            if let Some(decay) = commit.decay {
+                for (from, to) in [(source, target), (target, source)] {
+                    let edge = self
+                        .node_map
+                        .get_mut(from)
+                        .unwrap()
+                        .edges
+                        .get_mut(to)
+                        .unwrap();
+                    *edge.decayed_weight.get_or_insert(0.0) += decay;
+                }
+            }
+        }
+    }
+
+    /// Counts a commit towards an author's authored and committed totals, keeping the most
+    /// recently seen name.
+    pub fn record_identity(&mut self, identity: &Identity, authored: u32, committed: u32) {
+        let key = identity.key();
+        let author = self.authors.entry(key.clone()).or_insert_with(|| Author {
+            email: key,
+            name: String::new(),
+            commit_count: 0,
+            committed_count: 0,
+        });
+        author.name = identity.name.clone();
+        author.commit_count += authored;
+        author.committed_count += committed;
+    }
+
+    /// Multiplies every decayed churn, commit count and edge weight by `factor`, used to
+    /// move the decay reference time forward.
+    pub fn rescale_decay(&mut self, factor: f64) {
+        for node in self.node_map.values_mut() {
+            node.decayed_churn = node.decayed_churn.map(|v| v * factor);
+            node.decayed_commit_count = node.decayed_commit_count.map(|v| v * factor);
+            for edge in node.edges.values_mut() {
+                edge.decayed_weight = edge.decayed_weight.map(|v| v * factor);
+            }
+        }
+    }
+
+    /// Adds a finalized graph on top of this one, summing churn, commit counts and edge
+    /// weights for paths present in both.
+    pub fn merge_graph(&mut self, graph: &FileGraph) {
+        for file in &graph.node_map {
             let node = self
                 .node_map
                 .entry(file.path.clone())
@@ -78,50 +455,354 @@ impl FileGraphBuilder {
                     path: file.path.clone(),
                     additions: 0,
                     deletions: 0,
+                    normalized_additions: 0,
+                    normalized_deletions: 0,
                     commit_count: 0,
                     edges: HashMap::new(),
                     deleted_at_commit: None,
+                    decayed_churn: None,
+                    decayed_commit_count: None,
+                    authors: HashMap::new(),
+                    commit_classes: ClassCounts::default(),
                 });
-
             node.additions += file.additions;
             node.deletions += file.deletions;
-            node.commit_count += 1;
-
-            if file.is_deleted && node.deleted_at_commit.is_none() {
-                node.deleted_at_commit = commit_hash.map(|s| s.to_string());
+            node.normalized_additions += file.normalized_additions;
+            node.normalized_deletions += file.normalized_deletions;
+            node.commit_count += file.commit_count;
+            if node.deleted_at_commit.is_none() {
+                node.deleted_at_commit = file.deleted_at_commit.clone();
+            }
+            node.decayed_churn = add_optional(node.decayed_churn, file.decayed_churn);
+            node.decayed_commit_count =
+                add_optional(node.decayed_commit_count, file.decayed_commit_count);
+            node.commit_classes.merge(&file.commit_classes);
+            for author in &file.authors {
+                let merged =
+                    node.authors
+                        .entry(author.email.clone())
+                        .or_insert_with(|| FileAuthor {
+                            email: author.email.clone(),
+                            commits: 0,
+                            churn: 0,
+                        });
+                merged.commits += author.commits;
+                merged.churn += author.churn;
             }
         }
 
-        for i in 0..unique_files.len() {
-            for j in (i + 1)..unique_files.len() {
-                let source = unique_files[i];
-                let target = unique_files[j];
+        for function in &graph.functions {
+            let merged = self
+                .functions
+                .entry(function.path.clone())
+                .or_default()
+                .entry(function.name.clone())
+                .or_default();
+            merged.commit_count += function.commit_count;
+            merged.churn += function.churn;
+        }
+        for edge in &graph.function_edges {
+            self.record_function_cochange(
+                (&edge.source_path, &edge.source_name),
+                (&edge.target_path, &edge.target_name),
+                edge.weight,
+            );
+        }
+
+        for issue in &graph.issues {
+            let merged = self.issues.entry(issue.key.clone()).or_default();
+            merged.commits.extend(issue.commits.iter().cloned());
+            for file in &issue.files {
+                *merged.files.entry(file.path.clone()).or_insert(0) += file.commits;
+            }
+        }
 
-                let source_node = self.node_map.get_mut(source).unwrap();
+        for author in &graph.authors {
+            let merged = self
+                .authors
+                .entry(author.email.clone())
+                .or_insert_with(|| Author {
+                    email: author.email.clone(),
+                    name: String::new(),
+                    commit_count: 0,
+                    committed_count: 0,
+                });
+            merged.name = author.name.clone();
+            merged.commit_count += author.commit_count;
+            merged.committed_count += author.committed_count;
+        }
 
-                let edge = source_node
+        for file in &graph.node_map {
+            let node = self.node_map.get_mut(&file.path).unwrap();
+            for edge in &file.edges {
+                let merged = node
                     .edges
-                    .entry(target.to_string())
+                    .entry(edge.target.clone())
                     .or_insert_with(|| EdgeBuilder {
-                        target: target.to_string(),
+                        target: edge.target.clone(),
                         weight: 0,
+                        decayed_weight: None,
                     });
-                edge.weight += 1;
+                merged.weight += edge.weight;
+                merged.decayed_weight = add_optional(merged.decayed_weight, edge.decayed_weight);
+            }
+        }
+    }
 
-                let target_node = self.node_map.get_mut(target).unwrap();
-                let edge_to_source =
-                    target_node
-                        .edges
-                        .entry(source.to_string())
-                        .or_insert_with(|| EdgeBuilder {
-                            target: source.to_string(),
-                            weight: 0,
-                        });
-                edge_to_source.weight += 1;
+    pub fn repo(&self) -> &str {
+        &self.repo
+    }
+
+    pub fn total_commits(&self) -> u32 {
+        self.total_commits
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.node_map.len()
+    }
+
+    pub fn set_total_commits(&mut self, total_commits: u32) {
+        self.total_commits = total_commits;
+    }
+
+    pub fn record_file_change(
+        &mut self,
+        path: &str,
+        additions: u32,
+        deletions: u32,
+        normalized_additions: u32,
+        normalized_deletions: u32,
+        deleted_at_commit: Option<&str>,
+    ) {
+        let node = self
+            .node_map
+            .entry(path.to_string())
+            .or_insert_with(|| NodeBuilder {
+                path: path.to_string(),
+                additions: 0,
+                deletions: 0,
+                normalized_additions: 0,
+                normalized_deletions: 0,
+                commit_count: 0,
+                edges: HashMap::new(),
+                deleted_at_commit: None,
+                decayed_churn: None,
+                decayed_commit_count: None,
+                authors: HashMap::new(),
+                commit_classes: ClassCounts::default(),
+            });
+
+        node.additions += additions;
+        node.deletions += deletions;
+        node.normalized_additions += normalized_additions;
+        node.normalized_deletions += normalized_deletions;
+        node.commit_count += 1;
+
+        if node.deleted_at_commit.is_none() {
+            node.deleted_at_commit = deleted_at_commit.map(|s| s.to_string());
+        }
+    }
+
+    /// Increments the co-change weight between two existing files in both directions.
+    /// Returns false when either file has not been recorded yet.
+    pub fn record_cochange(&mut self, source: &str, target: &str) -> bool {
+        if source == target
+            || !self.node_map.contains_key(source)
+            || !self.node_map.contains_key(target)
+        {
+            return false;
+        }
+
+        for (from, to) in [(source, target), (target, source)] {
+            let node = self.node_map.get_mut(from).unwrap();
+            let edge = node
+                .edges
+                .entry(to.to_string())
+                .or_insert_with(|| EdgeBuilder {
+                    target: to.to_string(),
+                    weight: 0,
+                    decayed_weight: None,
+                });
+            edge.weight += 1;
+        }
+
+        true
+    }
+
+    /// Adds `weight` to the co-change between two recorded functions in both directions.
+    fn record_function_cochange(
+        &mut self,
+        source: (&str, &str),
+        target: (&str, &str),
+        weight: u32,
+    ) {
+        if source == target {
+            return;
+        }
+        for ((from_path, from_name), (to_path, to_name)) in [(source, target), (target, source)] {
+            if let Some(function) = self
+                .functions
+                .get_mut(from_path)
+                .and_then(|functions| functions.get_mut(from_name))
+            {
+                *function
+                    .edges
+                    .entry((to_path.to_string(), to_name.to_string()))
+                    .or_insert(0) += weight;
             }
         }
     }
 
+    /// Moves a file node to a new path, replacing any node already living there.
+    pub fn rename_file(&mut self, old_path: &str, new_path: &str) {
+        if old_path == new_path {
+            return;
+        }
+
+        self.remove_file(new_path);
+
+        let Some(mut node) = self.node_map.remove(old_path) else {
+            return;
+        };
+
+        for target in node.edges.keys() {
+            if let Some(target_node) = self.node_map.get_mut(target) {
+                if let Some(mut edge) = target_node.edges.remove(old_path) {
+                    edge.target = new_path.to_string();
+                    target_node.edges.insert(new_path.to_string(), edge);
+                }
+            }
+        }
+
+        node.path = new_path.to_string();
+        self.node_map.insert(new_path.to_string(), node);
+
+        if let Some(mut functions) = self.functions.remove(old_path) {
+            let moved = |(path, name): (String, String)| {
+                if path == old_path {
+                    (new_path.to_string(), name)
+                } else {
+                    (path, name)
+                }
+            };
+            for function in functions.values_mut() {
+                function.edges = std::mem::take(&mut function.edges)
+                    .into_iter()
+                    .map(|(partner, weight)| (moved(partner), weight))
+                    .collect();
+            }
+            for (name, function) in &functions {
+                for (partner_path, partner_name) in function.edges.keys() {
+                    let Some(partner) = self
+                        .functions
+                        .get_mut(partner_path)
+                        .and_then(|partners| partners.get_mut(partner_name))
+                    else {
+                        continue;
+                    };
+                    let old_key = (old_path.to_string(), name.clone());
+                    if let Some(weight) = partner.edges.remove(&old_key) {
+                        partner
+                            .edges
+                            .insert((new_path.to_string(), name.clone()), weight);
+                    }
+                }
+            }
+            self.functions.insert(new_path.to_string(), functions);
+        }
+
+        for issue in self.issues.values_mut() {
+            if let Some(commits) = issue.files.remove(old_path) {
+                issue.files.insert(new_path.to_string(), commits);
+            }
+        }
+    }
+
+    pub fn remove_file(&mut self, path: &str) -> bool {
+        let Some(node) = self.node_map.remove(path) else {
+            return false;
+        };
+
+        for target in node.edges.keys() {
+            if let Some(target_node) = self.node_map.get_mut(target) {
+                target_node.edges.remove(path);
+            }
+        }
+        for issue in self.issues.values_mut() {
+            issue.files.remove(path);
+        }
+        if let Some(functions) = self.functions.remove(path) {
+            for (name, function) in &functions {
+                for (partner_path, partner_name) in function.edges.keys() {
+                    if let Some(partner) = self
+                        .functions
+                        .get_mut(partner_path)
+                        .and_then(|partners| partners.get_mut(partner_name))
+                    {
+                        partner.edges.remove(&(path.to_string(), name.clone()));
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    pub fn contains_file(&self, path: &str) -> bool {
+        self.node_map.contains_key(path)
+    }
+
+    /// Drops co-change edges below `min_weight`, then removes files left without any
+    /// partner and live files without churn. Returns the number of undirected edges removed.
+    pub fn prune(&mut self, min_weight: u32) -> u64 {
+        let removed_edges = self.prune_edges(min_weight);
+        self.remove_orphans();
+        removed_edges
+    }
+
+    /// Drops co-change edges below `min_weight` and returns the number of undirected
+    /// edges removed.
+    pub fn prune_edges(&mut self, min_weight: u32) -> u64 {
+        let mut removed_edges = 0u64;
+        for node in self.node_map.values_mut() {
+            let before = node.edges.len();
+            node.edges.retain(|_, edge| edge.weight >= min_weight);
+            removed_edges += (before - node.edges.len()) as u64;
+        }
+
+        removed_edges / 2
+    }
+
+    /// Removes files without any co-change partner and live files without churn,
+    /// returning how many were removed.
+    pub fn remove_orphans(&mut self) -> u64 {
+        let before = self.node_map.len();
+        self.node_map.retain(|_, node| {
+            !node.edges.is_empty()
+                && (node.additions != 0 || node.deletions != 0 || node.deleted_at_commit.is_some())
+        });
+
+        let remaining: HashSet<String> = self.node_map.keys().cloned().collect();
+        for node in self.node_map.values_mut() {
+            node.edges.retain(|target, _| remaining.contains(target));
+        }
+        for issue in self.issues.values_mut() {
+            issue.files.retain(|path, _| remaining.contains(path));
+        }
+        self.functions.retain(|path, _| remaining.contains(path));
+        for function in self.functions.values_mut().flat_map(|f| f.values_mut()) {
+            function
+                .edges
+                .retain(|(path, _), _| remaining.contains(path));
+        }
+
+        (before - self.node_map.len()) as u64
+    }
+
+    pub fn snapshot(&self) -> FileGraph {
+        self.clone().finalize()
+    }
+
     pub fn finalize(self) -> FileGraph {
         let commit_counts: HashMap<String, u32> = self
             .node_map
@@ -143,27 +824,102 @@ impl FileGraphBuilder {
                             target: eb.target,
                             weight: eb.weight,
                             target_commits,
+                            decayed_weight: eb.decayed_weight,
                         }
                     })
                     .collect();
 
+                let mut authors: Vec<FileAuthor> = nb.authors.into_values().collect();
+                authors.sort_by(|a, b| a.email.cmp(&b.email));
+
                 FileNode {
                     path: nb.path,
                     additions: nb.additions,
                     deletions: nb.deletions,
+                    normalized_additions: nb.normalized_additions,
+                    normalized_deletions: nb.normalized_deletions,
                     commit_count: nb.commit_count,
                     edges,
                     deleted_at_commit: nb.deleted_at_commit,
+                    decayed_churn: nb.decayed_churn,
+                    decayed_commit_count: nb.decayed_commit_count,
+                    authors,
+                    commit_classes: nb.commit_classes,
                 }
             })
             .collect();
 
         nodes.sort_by(|a, b| b.commit_count.cmp(&a.commit_count));
 
+        let mut authors: Vec<Author> = self.authors.into_values().collect();
+        authors.sort_by(|a, b| a.email.cmp(&b.email));
+
+        let mut issues: Vec<Issue> = self
+            .issues
+            .into_iter()
+            .map(|(key, issue)| {
+                let mut files: Vec<IssueFile> = issue
+                    .files
+                    .into_iter()
+                    .map(|(path, commits)| IssueFile { path, commits })
+                    .collect();
+                files.sort_by(|a, b| a.path.cmp(&b.path));
+                Issue {
+                    key,
+                    commits: issue.commits,
+                    files,
+                }
+            })
+            .collect();
+        issues.sort_by(|a, b| a.key.cmp(&b.key));
+
+        let mut functions = Vec::new();
+        let mut function_edges = Vec::new();
+        for (path, file_functions) in self.functions {
+            for (name, function) in file_functions {
+                for ((target_path, target_name), weight) in function.edges {
+                    if (&path, &name) < (&target_path, &target_name) {
+                        function_edges.push(FunctionEdge {
+                            source_path: path.clone(),
+                            source_name: name.clone(),
+                            target_path,
+                            target_name,
+                            weight,
+                        });
+                    }
+                }
+                functions.push(Function {
+                    path: path.clone(),
+                    name,
+                    commit_count: function.commit_count,
+                    churn: function.churn,
+                });
+            }
+        }
+        functions.sort_by(|a, b| (&a.path, &a.name).cmp(&(&b.path, &b.name)));
+        function_edges.sort_by(|a, b| {
+            (
+                &a.source_path,
+                &a.source_name,
+                &a.target_path,
+                &a.target_name,
+            )
+                .cmp(&(
+                    &b.source_path,
+                    &b.source_name,
+                    &b.target_path,
+                    &b.target_name,
+                ))
+        });
+
         FileGraph {
             repo: self.repo,
             total_commits_analyzed: self.total_commits,
             node_map: nodes,
+            authors,
+            issues,
+            functions,
+            function_edges,
         }
     }
 }
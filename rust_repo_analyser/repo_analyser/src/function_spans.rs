@@ -0,0 +1,78 @@
+use rust_code_analysis::{get_function_spaces, FuncSpace, SpaceKind, LANG};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::file_graph::ChangedFunction;
+
+/// Lines of one function, 1-based and inclusive as rust-code-analysis reports them. The
+/// name is qualified with the enclosing classes and namespaces.
+#[derive(Debug, Clone)]
+pub struct FunctionSpan {
+    pub name: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// Parses a source file with the C++ grammar, like the metrics export, and lists every
+/// function and method in it. Files that fail to parse have no functions.
+pub fn function_spans(path: &str, source: Vec<u8>) -> Vec<FunctionSpan> {
+    let Some(unit) = get_function_spaces(&LANG::Cpp, source, Path::new(path), None) else {
+        return Vec::new();
+    };
+
+    let mut spans = Vec::new();
+    collect_spans(&unit, &[], &mut spans);
+    spans
+}
+
+fn collect_spans(space: &FuncSpace, scope: &[String], spans: &mut Vec<FunctionSpan>) {
+    for child in &space.spaces {
+        let mut qualified = scope.to_vec();
+        qualified.push(
+            child
+                .name
+                .clone()
+                .unwrap_or_else(|| "<anonymous>".to_string()),
+        );
+
+        if child.kind == SpaceKind::Function {
+            spans.push(FunctionSpan {
+                name: qualified.join("::"),
+                start_line: child.start_line,
+                end_line: child.end_line,
+            });
+        }
+        collect_spans(child, &qualified, spans);
+    }
+}
+
+/// Maps changed line numbers to the innermost function enclosing each of them. Lines
+/// outside any function, such as includes or class fields, are not attributed.
+pub fn changed_functions(
+    path: &str,
+    spans: &[FunctionSpan],
+    lines: &[u32],
+) -> Vec<ChangedFunction> {
+    let mut churn: HashMap<&str, u32> = HashMap::new();
+    for line in lines {
+        let line = *line as usize;
+        let enclosing = spans
+            .iter()
+            .filter(|span| span.start_line <= line && line <= span.end_line)
+            .min_by_key(|span| span.end_line - span.start_line);
+        if let Some(span) = enclosing {
+            *churn.entry(span.name.as_str()).or_insert(0) += 1;
+        }
+    }
+
+    let mut functions: Vec<ChangedFunction> = churn
+        .into_iter()
+        .map(|(name, churn)| ChangedFunction {
+            path: path.to_string(),
+            name: name.to_string(),
+            churn,
+        })
+        .collect();
+    functions.sort_by(|a, b| a.name.cmp(&b.name));
+    functions
+}
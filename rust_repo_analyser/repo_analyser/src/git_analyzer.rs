@@ -1,14 +1,301 @@
-use git2::{Delta, DiffFindOptions, Repository};
+use chrono::{DateTime, NaiveDate, NaiveTime, SecondsFormat, Utc};
+use git2::{Delta, DiffFindOptions, DiffOptions, Mailmap, Oid, Patch, Repository, Signature};
 use log::{debug, info};
-use std::collections::HashSet;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::time::{Duration, Instant};
 
-use crate::file_graph::ChangedFile;
-use crate::storage::Neo4jClient;
+use crate::change_set::ChangeSetGrouper;
+use crate::collaboration::CollaborationTracker;
+use crate::commit_class::CommitClassifier;
+use crate::commit_filter::CommitFilter;
+use crate::file_graph::{ChangedFile, ChangedFunction, CommitChanges, FileGraphBuilder, Identity};
+use crate::function_spans::{changed_functions, function_spans};
+use crate::issue_refs::extract_issue_refs;
+use crate::path_filter::PathFilter;
+use crate::storage::{AnalysisRun, ExcludedFile, GraphStore};
 
+pub const DEFAULT_BATCH_SIZE: usize = 500;
+pub const DEFAULT_MAX_FILES_PER_COMMIT: usize = 200;
+pub const DEFAULT_MAX_RENAMES_PER_COMMIT: usize = 300;
+
+/// Restricts the analysed history to a commit-time interval and/or a revision range such
+/// as `v1.0..v2.0`. `since` is inclusive and `until` exclusive, both in Unix seconds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnalysisWindow {
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub range: Option<String>,
+}
+
+impl AnalysisWindow {
+    /// Builds a window from CLI values. Dates are `YYYY-MM-DD` (midnight UTC) or RFC 3339.
+    pub fn parse(
+        since: Option<&str>,
+        until: Option<&str>,
+        range: Option<&str>,
+    ) -> Result<Self, String> {
+        let window = Self {
+            since: since.map(parse_date).transpose()?,
+            until: until.map(parse_date).transpose()?,
+            range: range.map(|r| r.to_string()),
+        };
+
+        if let (Some(since), Some(until)) = (window.since, window.until) {
+            if since >= until {
+                return Err("--since must be earlier than --until".to_string());
+            }
+        }
+
+        Ok(window)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.since.is_none() && self.until.is_none() && self.range.is_none()
+    }
+
+    pub fn contains(&self, commit_time: i64) -> bool {
+        self.since.is_none_or(|since| commit_time >= since)
+            && self.until.is_none_or(|until| commit_time < until)
+    }
+
+    /// Human-readable description stored with the graph, e.g.
+    /// `v1.0..v2.0 since 2022-01-01T00:00:00Z until 2024-01-01T00:00:00Z`.
+    pub fn describe(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut parts = Vec::new();
+        if let Some(range) = &self.range {
+            parts.push(range.clone());
+        }
+        if let Some(since) = self.since {
+            parts.push(format!("since {}", format_time(since)));
+        }
+        if let Some(until) = self.until {
+            parts.push(format!("until {}", format_time(until)));
+        }
+        Some(parts.join(" "))
+    }
+}
+
+fn parse_date(value: &str) -> Result<i64, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(NaiveTime::MIN).and_utc().timestamp());
+    }
+
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.timestamp())
+        .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD or RFC 3339", value))
+}
+
+fn identity(signature: &Signature) -> Identity {
+    Identity {
+        name: String::from_utf8_lossy(signature.name_bytes()).into_owned(),
+        email: String::from_utf8_lossy(signature.email_bytes()).into_owned(),
+    }
+}
+
+// The oldest commits of a shallow clone have no parents, so their whole tree would count
+// as added in one commit
+fn is_shallow_boundary(repo: &Repository, commit: &git2::Commit) -> bool {
+    if commit.parent_count() > 0 || !repo.is_shallow() {
+        return false;
+    }
+    let id = commit.id().to_string();
+    std::fs::read_to_string(repo.path().join("shallow"))
+        .map(|shallow| shallow.lines().any(|line| line.trim() == id))
+        .unwrap_or(false)
+}
+
+fn format_time(seconds: i64) -> String {
+    DateTime::<Utc>::from_timestamp(seconds, 0)
+        .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_else(|| seconds.to_string())
+}
+
+/// Exponential time decay: a commit `half_life` seconds older than `reference` counts half.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeDecay {
+    pub half_life: f64,
+    pub reference: i64,
+}
+
+impl TimeDecay {
+    pub fn factor(&self, commit_time: i64) -> f64 {
+        0.5f64.powf((self.reference - commit_time) as f64 / self.half_life)
+    }
+}
+
+/// How commit increments reach the store: streamed in batches while walking history, or
+/// folded into a `FileGraphBuilder` first and written with a single bulk load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IngestMode {
+    #[default]
+    Stream,
+    Bulk,
+}
+
+#[derive(Default)]
+struct CombinedDiff {
+    renames: Vec<(String, String)>,
+    deleted_paths: HashSet<String>,
+    all_paths: Vec<String>,
+    file_stats: HashMap<String, (u32, u32)>,
+    normalized_stats: HashMap<String, (u32, u32)>,
+    changed_lines: HashMap<String, Vec<u32>>,
+}
+
+impl CombinedDiff {
+    fn intersect(self, other: CombinedDiff) -> CombinedDiff {
+        let other_paths: HashSet<&String> = other.all_paths.iter().collect();
+        let all_paths: Vec<String> = self
+            .all_paths
+            .into_iter()
+            .filter(|path| other_paths.contains(path))
+            .collect();
+
+        let file_stats = min_stats(self.file_stats, &other.file_stats);
+        let normalized_stats = min_stats(self.normalized_stats, &other.normalized_stats);
+
+        let changed_lines = self
+            .changed_lines
+            .into_iter()
+            .filter_map(|(path, lines)| {
+                let other_lines: HashSet<&u32> = other.changed_lines.get(&path)?.iter().collect();
+                let lines: Vec<u32> = lines
+                    .into_iter()
+                    .filter(|line| other_lines.contains(line))
+                    .collect();
+                (!lines.is_empty()).then_some((path, lines))
+            })
+            .collect();
+
+        CombinedDiff {
+            renames: self
+                .renames
+                .into_iter()
+                .filter(|rename| other.renames.contains(rename))
+                .collect(),
+            deleted_paths: self
+                .deleted_paths
+                .intersection(&other.deleted_paths)
+                .cloned()
+                .collect(),
+            all_paths,
+            file_stats,
+            normalized_stats,
+            changed_lines,
+        }
+    }
+}
+
+fn min_stats(
+    stats: HashMap<String, (u32, u32)>,
+    other: &HashMap<String, (u32, u32)>,
+) -> HashMap<String, (u32, u32)> {
+    stats
+        .into_iter()
+        .filter_map(|(path, (additions, deletions))| {
+            let (other_additions, other_deletions) = other.get(&path)?;
+            Some((
+                path,
+                (
+                    additions.min(*other_additions),
+                    deletions.min(*other_deletions),
+                ),
+            ))
+        })
+        .collect()
+}
+
+/// Which changes a merge commit contributes: none, its diff against the first parent (the
+/// branch it was merged into), the union of its diffs against every parent, or, like
+/// `git diff --cc`, only the changes that differ from every parent, such as conflict
+/// resolutions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    Skip,
+    FirstParent,
+    #[default]
+    AllParents,
+    CombinedDiff,
+}
+
+impl MergeStrategy {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "skip" => Ok(Self::Skip),
+            "first-parent" => Ok(Self::FirstParent),
+            "all-parents" => Ok(Self::AllParents),
+            "combined-diff" => Ok(Self::CombinedDiff),
+            other => Err(format!(
+                "Unknown merge strategy '{}', expected skip, first-parent, all-parents or combined-diff",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct GitAnalyzer {
     repo_path: String,
     repo_url: String,
     extensions: Vec<String>,
+    batch_size: usize,
+    ingest_mode: IngestMode,
+    incremental: bool,
+    window: AnalysisWindow,
+    half_life_days: Option<f64>,
+    collaboration_window_days: Option<u32>,
+    classifier: CommitClassifier,
+    change_set_gap_minutes: Option<u32>,
+    function_level: bool,
+    merge_strategy: MergeStrategy,
+    first_parent: bool,
+    path_filter: PathFilter,
+    commit_filter: CommitFilter,
+    normalize_churn: bool,
+    analysed_paths_only: bool,
+    run_config: Option<String>,
+    max_files_per_commit: usize,
+    max_renames_per_commit: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisSummary {
+    pub commit_count: i64,
+    pub skipped_large_commits: i64,
+    pub skipped_merge_commits: i64,
+    pub filtered_commits: i64,
+    pub files_saved: i64,
+    pub deleted_files_saved: i64,
+    pub deleted_files_from_large_commits: i64,
+    pub cochange_pairs: i64,
+    pub write_batches: i64,
+    pub elapsed: Duration,
+    pub resumed_from: Option<String>,
+    /// Files with an analysed extension left out by the path filter, with the reason.
+    pub excluded_files: BTreeMap<String, String>,
+    /// Number of skipped commits per reason: merges, large commits and filtered commits.
+    pub skip_reasons: BTreeMap<String, i64>,
+}
+
+impl AnalysisSummary {
+    pub fn throughput(&self) -> String {
+        let secs = self.elapsed.as_secs_f64().max(f64::EPSILON);
+        format!(
+            "{} commits in {:.1}s ({:.1} commits/s, {:.1} file updates/s, {:.1} co-change pairs/s, {} write batches)",
+            self.commit_count,
+            self.elapsed.as_secs_f64(),
+            self.commit_count as f64 / secs,
+            self.files_saved as f64 / secs,
+            self.cochange_pairs as f64 / secs,
+            self.write_batches
+        )
+    }
 }
 
 impl GitAnalyzer {
@@ -22,16 +309,170 @@ impl GitAnalyzer {
             repo_path,
             repo_url,
             extensions,
+            batch_size: DEFAULT_BATCH_SIZE,
+            ingest_mode: IngestMode::Stream,
+            incremental: false,
+            window: AnalysisWindow::default(),
+            half_life_days: None,
+            collaboration_window_days: None,
+            classifier: CommitClassifier::default(),
+            change_set_gap_minutes: None,
+            function_level: false,
+            merge_strategy: MergeStrategy::default(),
+            first_parent: false,
+            path_filter: PathFilter::default(),
+            commit_filter: CommitFilter::default(),
+            normalize_churn: false,
+            analysed_paths_only: false,
+            run_config: None,
+            max_files_per_commit: DEFAULT_MAX_FILES_PER_COMMIT,
+            max_renames_per_commit: DEFAULT_MAX_RENAMES_PER_COMMIT,
+        }
+    }
+
+    /// A copy of this analyser with the same settings, for the repository at `repo_path`.
+    pub fn for_repo(&self, repo_path: String, repo_url: String) -> Self {
+        Self {
+            repo_path,
+            repo_url,
+            ..self.clone()
         }
     }
 
+    pub fn repo_path(&self) -> &str {
+        &self.repo_path
+    }
+
+    pub fn extensions(&self) -> String {
+        self.extensions.join(",")
+    }
+
+    pub fn window(&self) -> &AnalysisWindow {
+        &self.window
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn with_ingest_mode(mut self, ingest_mode: IngestMode) -> Self {
+        self.ingest_mode = ingest_mode;
+        self
+    }
+
+    /// Resume from the commit recorded by the previous analysis instead of walking the
+    /// whole history. Falls back to a full walk when the repository has no checkpoint.
+    pub fn with_incremental(mut self, incremental: bool) -> Self {
+        self.incremental = incremental;
+        self
+    }
+
+    pub fn with_window(mut self, window: AnalysisWindow) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Also record time-decayed churn and co-change weights, halving a commit's
+    /// contribution for every `half_life_days` it is older than the newest analysed commit.
+    pub fn with_half_life_days(mut self, half_life_days: Option<f64>) -> Self {
+        self.half_life_days = half_life_days.filter(|days| *days > 0.0);
+        self
+    }
+
+    /// Also build the author collaboration network, linking authors who change the same
+    /// file within `window_days` of each other.
+    pub fn with_collaboration_window(mut self, window_days: Option<u32>) -> Self {
+        self.collaboration_window_days = window_days.filter(|days| *days > 0);
+        self
+    }
+
+    /// Replaces the built-in rules used to sort commits into bugfix, feature, refactor,
+    /// docs and merge classes.
+    pub fn with_classifier(mut self, classifier: CommitClassifier) -> Self {
+        self.classifier = classifier;
+        self
+    }
+
+    /// Treats consecutive commits by the same author, each within `gap_minutes` of the
+    /// previous one, as one logical change set when counting co-changes.
+    pub fn with_change_set_gap(mut self, gap_minutes: Option<u32>) -> Self {
+        self.change_set_gap_minutes = gap_minutes.filter(|minutes| *minutes > 0);
+        self
+    }
+
+    /// Also maps every commit's changed lines to the enclosing functions, recording
+    /// function-level co-change next to the file graph.
+    pub fn with_function_level(mut self, function_level: bool) -> Self {
+        self.function_level = function_level;
+        self
+    }
+
+    pub fn with_merge_strategy(mut self, merge_strategy: MergeStrategy) -> Self {
+        self.merge_strategy = merge_strategy;
+        self
+    }
+
+    /// Follows only the first parent of merge commits when walking history, so commits
+    /// made on merged branches are not analysed on their own.
+    pub fn with_first_parent(mut self, first_parent: bool) -> Self {
+        self.first_parent = first_parent;
+        self
+    }
+
+    /// Include/exclude globs and vendored-code detection applied on top of the extensions.
+    pub fn with_path_filter(mut self, path_filter: PathFilter) -> Self {
+        self.path_filter = path_filter;
+        self
+    }
+
+    /// Skips bot, formatting and other automated commits. Like large commits, they still
+    /// carry their renames and deletions but add no churn or co-change.
+    pub fn with_commit_filter(mut self, commit_filter: CommitFilter) -> Self {
+        self.commit_filter = commit_filter;
+        self
+    }
+
+    /// Also diffs every commit with whitespace ignored and drops formatting-only hunks,
+    /// recording the result as normalized churn next to the raw line counts.
+    pub fn with_normalized_churn(mut self, normalize_churn: bool) -> Self {
+        self.normalize_churn = normalize_churn;
+        self
+    }
+
+    /// Restricts every diff to files with the analysed extensions, so the contents of other
+    /// files are never read, as needed for a blobless clone that only has the analysed
+    /// files' blobs. Renames between an analysed and another extension then appear as an
+    /// addition and a deletion.
+    pub fn with_analysed_paths_only(mut self, analysed_paths_only: bool) -> Self {
+        self.analysed_paths_only = analysed_paths_only;
+        self
+    }
+
+    /// The effective pipeline configuration, as TOML, recorded with the analysis run.
+    pub fn with_run_config(mut self, run_config: Option<String>) -> Self {
+        self.run_config = run_config;
+        self
+    }
+
+    /// Commits touching more files, or renaming more files, than these are skipped.
+    pub fn with_commit_limits(
+        mut self,
+        max_files_per_commit: usize,
+        max_renames_per_commit: usize,
+    ) -> Self {
+        self.max_files_per_commit = max_files_per_commit;
+        self.max_renames_per_commit = max_renames_per_commit;
+        self
+    }
+
     pub async fn analyze(
         &self,
-        client: &Neo4jClient,
+        client: &dyn GraphStore,
         repo_name: &str,
-        max_files_per_commit: usize,
-        max_renames_per_commit: usize,
-    ) -> Result<i64, String> {
+    ) -> Result<AnalysisSummary, String> {
+        let started = Instant::now();
+        let started_at = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
         let repo = Repository::open(&self.repo_path)
             .map_err(|e| format!("Failed to open repository: {}", e))?;
 
@@ -42,225 +483,792 @@ impl GitAnalyzer {
             .await
             .map_err(|e| format!("Failed to save repository: {}", e))?;
 
-        let mut revwalk = repo
-            .revwalk()
-            .map_err(|err| format!("Error creating revwalk: {}", err))?;
+        let head = repo
+            .head()
+            .and_then(|head| head.peel_to_commit())
+            .map_err(|e| format!("Failed to resolve HEAD: {}", e))?
+            .id();
 
-        revwalk
-            .push_head()
-            .map_err(|err| format!("Error pushing revwalk {}", err))?;
-        revwalk
-            .set_sorting(git2::Sort::TIME | git2::Sort::REVERSE)
-            .map_err(|err| format!("Sorting failed {}", err))?;
+        if self.incremental && !self.window.is_empty() {
+            return Err(
+                "Incremental analysis cannot be combined with --since, --until or a revision range"
+                    .to_string(),
+            );
+        }
 
-        let mut commit_count = 0i64;
-        let mut skipped_commits: Vec<String> = Vec::new();
-        let mut total_files_saved = 0i64;
-        let mut total_deleted_files_saved = 0i64;
-        let mut deleted_files_from_large_commits = 0i64;
+        let checkpoint = if self.incremental {
+            client
+                .get_checkpoint(repo_name)
+                .await
+                .map_err(|e| format!("Failed to load checkpoint: {}", e))?
+        } else {
+            None
+        };
 
-        for rev in revwalk {
-            let current_commit = rev.map_err(|err| format!("Error unwrapping revwalk:{}", err))?;
-            let commit = repo
-                .find_commit(current_commit)
-                .map_err(|e| format!("Failed to find commit: {}", e))?;
-            debug!("Getting changed files");
-            let (changed_files, renames) = self.get_changed_files(&repo, &commit)?;
+        let last_commit = match &checkpoint {
+            Some(checkpoint) => Some(self.resume_point(&repo, head, &checkpoint.last_commit)?),
+            None => None,
+        };
+        let commit_ids = self.walk_commit_ids(&repo, last_commit)?;
+        let limits = (self.max_files_per_commit, self.max_renames_per_commit);
+        let mut summary = AnalysisSummary {
+            resumed_from: checkpoint.as_ref().map(|c| c.last_commit.clone()),
+            ..Default::default()
+        };
 
-            let commit_hash = commit.id().to_string();
+        if let Some(checkpoint) = &checkpoint {
+            info!(
+                "Resuming {} after commit {} ({} new commits)",
+                repo_name,
+                checkpoint.last_commit,
+                commit_ids.len()
+            );
+        }
 
-            if changed_files.len() > max_files_per_commit {
-                debug!(
-                    "Large commit detected: {} ({} files)",
-                    commit_hash,
-                    changed_files.len()
-                );
+        let decay = self.time_decay(&repo, head, &commit_ids)?;
+        if let (Some(decay), Some(last_commit)) = (decay, last_commit) {
+            // Stored decayed values are relative to the previous run's newest commit
+            let previous_reference = repo
+                .find_commit(last_commit)
+                .map_err(|e| format!("Failed to find commit: {}", e))?
+                .time()
+                .seconds();
+            client
+                .rescale_decay(repo_name, decay.factor(previous_reference))
+                .await
+                .map_err(|e| format!("Failed to rescale decayed weights: {}", e))?;
+        }
 
-                // Still process renames if under threshold
-                if !renames.is_empty() && renames.len() <= max_renames_per_commit {
-                    debug!(
-                        "Processing {} renames for large commit {}",
-                        renames.len(),
-                        commit_hash
-                    );
-                    for (old_path, new_path) in &renames {
-                        match client.rename_file_node(repo_name, old_path, new_path).await {
-                            Ok(_) => {}
-                            Err(e) => {
-                                log::warn!(
-                                    "Failed to rename file node {} -> {}: {}",
-                                    old_path,
-                                    new_path,
-                                    e
-                                );
-                            }
-                        }
-                    }
+        let mut collaboration = match self.collaboration_window_days {
+            Some(window_days) => {
+                let mut tracker = CollaborationTracker::new(window_days);
+                if let Some(last_commit) = last_commit {
+                    self.seed_collaboration(&repo, last_commit, window_days, limits, &mut tracker)?;
                 }
+                Some(tracker)
+            }
+            None => None,
+        };
 
-                // Always process deleted files even in large commits
-                let deleted_files: Vec<&ChangedFile> =
-                    changed_files.iter().filter(|f| f.is_deleted).collect();
-                if !deleted_files.is_empty() {
-                    debug!(
-                        "Processing {} deleted files for large commit {}",
-                        deleted_files.len(),
-                        commit_hash
+        let mut change_sets = match self.change_set_gap_minutes {
+            Some(gap_minutes) => {
+                let mut grouper = ChangeSetGrouper::new(gap_minutes);
+                if let Some(last_commit) = last_commit {
+                    self.seed_change_set(&repo, last_commit, gap_minutes, limits, &mut grouper)?;
+                }
+                Some(grouper)
+            }
+            None => None,
+        };
+
+        // Renames in new commits may refer to files only the store knows about, so an
+        // incremental run always streams its commits.
+        let ingest_mode = if checkpoint.is_some() {
+            IngestMode::Stream
+        } else {
+            self.ingest_mode
+        };
+
+        match ingest_mode {
+            IngestMode::Stream => {
+                for chunk in commit_ids.chunks(self.batch_size) {
+                    let mut pending =
+                        self.read_commits(&repo, chunk, limits, decay, &mut summary)?;
+                    if let Some(grouper) = &mut change_sets {
+                        pending.iter_mut().for_each(|commit| grouper.assign(commit));
+                    }
+                    if let Some(tracker) = &mut collaboration {
+                        pending.iter().for_each(|commit| tracker.record(commit));
+                    }
+                    self.flush_batch(client, repo_name, &mut pending, &mut summary)
+                        .await?;
+                    info!(
+                        "Processed {} commits ({:.1} commits/s)",
+                        summary.commit_count,
+                        summary.commit_count as f64 / started.elapsed().as_secs_f64()
                     );
-                    deleted_files_from_large_commits += deleted_files.len() as i64;
-                    for file in deleted_files {
-                        if let Err(e) = client
-                            .save_file_node(
-                                repo_name,
-                                &file.path,
-                                file.additions as i64,
-                                file.deletions as i64,
-                                Some(&commit_hash),
-                            )
-                            .await
-                        {
-                            log::warn!("Failed to save deleted file node {}: {}", file.path, e);
+                }
+            }
+            IngestMode::Bulk => {
+                let mut builder = FileGraphBuilder::new(repo_name.to_string());
+
+                for chunk in commit_ids.chunks(self.batch_size) {
+                    for mut changes in
+                        self.read_commits(&repo, chunk, limits, decay, &mut summary)?
+                    {
+                        if let Some(grouper) = &mut change_sets {
+                            grouper.assign(&mut changes);
                         }
+                        if let Some(tracker) = &mut collaboration {
+                            tracker.record(&changes);
+                        }
+                        builder.apply_commit(&changes);
                     }
                 }
 
-                skipped_commits.push(commit_hash.clone());
+                let graph = builder.finalize();
                 info!(
-                    "Skipped large commit {} ({} files, {} renames)",
-                    commit_hash,
-                    changed_files.len(),
-                    renames.len()
+                    "Built in-memory graph for {} in {:.1}s ({} files), bulk loading",
+                    repo_name,
+                    started.elapsed().as_secs_f64(),
+                    graph.node_map.len()
                 );
-            } else if !changed_files.is_empty() || !renames.is_empty() {
-                debug!("Save to Neo4j");
-                let (files, deleted) = self
-                    .save_to_neo4j(
-                        client,
-                        repo_name,
-                        &changed_files,
-                        &renames,
-                        Some(&commit_hash),
-                    )
-                    .await?;
-                total_files_saved += files;
-                total_deleted_files_saved += deleted;
-                debug!("Saved to Neo4j");
+                client
+                    .bulk_load_graph(&graph)
+                    .await
+                    .map_err(|e| format!("Failed to bulk load graph: {}", e))?;
+                summary.write_batches += 1;
             }
+        }
 
-            commit_count += 1;
-            if commit_count % 1 == 0 {
-                info!("Processed {} commits", commit_count);
-                info!("Processed {} commit", commit.id());
-            }
+        if let Some(tracker) = &collaboration {
+            let collaborations = tracker.collaborations();
+            client
+                .save_collaborations(repo_name, &collaborations)
+                .await
+                .map_err(|e| format!("Failed to save collaborations: {}", e))?;
+            info!(
+                "Saved {} author collaborations for {}",
+                collaborations.len(),
+                repo_name
+            );
         }
 
+        if !summary.excluded_files.is_empty() {
+            let excluded: Vec<ExcludedFile> = summary
+                .excluded_files
+                .iter()
+                .map(|(path, reason)| ExcludedFile {
+                    path: path.clone(),
+                    reason: reason.clone(),
+                })
+                .collect();
+            client
+                .save_excluded_files(repo_name, &excluded)
+                .await
+                .map_err(|e| format!("Failed to save excluded files: {}", e))?;
+            info!("Excluded {} files from {}", excluded.len(), repo_name);
+        }
+
+        let previous_commits = checkpoint.map_or(0, |c| c.total_commits);
         client
-            .update_commit_count(repo_name, commit_count)
+            .update_commit_count(repo_name, previous_commits + summary.commit_count)
             .await
             .map_err(|e| format!("Failed to update commit count: {}", e))?;
 
+        if let Some(config) = &self.run_config {
+            let run = AnalysisRun {
+                started_at,
+                config: config.clone(),
+            };
+            client
+                .save_analysis_run(repo_name, &run)
+                .await
+                .map_err(|e| format!("Failed to save analysis run: {}", e))?;
+        }
+
+        // A windowed graph does not end at HEAD, so it must not seed incremental runs
+        if let Some(window) = self.window.describe() {
+            client
+                .set_analysis_window(repo_name, &window)
+                .await
+                .map_err(|e| format!("Failed to save analysis window: {}", e))?;
+            info!("Analysis window for {}: {}", repo_name, window);
+        } else {
+            client
+                .set_last_commit(repo_name, &head.to_string())
+                .await
+                .map_err(|e| format!("Failed to save last commit: {}", e))?;
+        }
+
         client
             .link_all_files_to_repo(repo_name)
             .await
             .map_err(|e| format!("Failed to link files to repo: {}", e))?;
 
-        info!("Total commits analyzed: {}", commit_count);
+        summary.elapsed = started.elapsed();
+
+        info!("Total commits analyzed: {}", summary.commit_count);
+
+        if summary.skipped_merge_commits > 0 {
+            info!(
+                "Skipped {} merge commits (merge strategy: skip)",
+                summary.skipped_merge_commits
+            );
+        }
+
+        if summary.filtered_commits > 0 {
+            info!(
+                "Skipped {} commits matching the commit filters",
+                summary.filtered_commits
+            );
+        }
 
-        if !skipped_commits.is_empty() {
+        if summary.skipped_large_commits > 0 {
             info!(
                 "Skipped {} large commits out of {} total ({}%)",
-                skipped_commits.len(),
-                commit_count,
-                (skipped_commits.len() as f64 / commit_count as f64 * 100.0) as i64
+                summary.skipped_large_commits,
+                summary.commit_count,
+                (summary.skipped_large_commits as f64 / summary.commit_count as f64 * 100.0) as i64
             );
         }
 
         info!(
             "Git analysis summary for {}: {} commits analyzed, {} skipped (large), {} files saved ({} deleted from small commits, {} deleted from large commits)",
             repo_name,
-            commit_count,
-            skipped_commits.len(),
-            total_files_saved,
-            total_deleted_files_saved,
-            deleted_files_from_large_commits
+            summary.commit_count,
+            summary.skipped_large_commits,
+            summary.files_saved,
+            summary.deleted_files_saved,
+            summary.deleted_files_from_large_commits
+        );
+        info!(
+            "Ingestion throughput for {}: {}",
+            repo_name,
+            summary.throughput()
         );
 
-        Ok(commit_count)
+        Ok(summary)
     }
 
-    async fn save_to_neo4j(
+    /// Walks the (windowed) history and returns each commit's changes without writing
+    /// anything, for analyses that run entirely in memory such as the timeline.
+    pub fn read_history(&self) -> Result<Vec<CommitChanges>, String> {
+        let repo = Repository::open(&self.repo_path)
+            .map_err(|e| format!("Failed to open repository: {}", e))?;
+
+        let limits = (self.max_files_per_commit, self.max_renames_per_commit);
+        let mut summary = AnalysisSummary::default();
+        let commit_ids = self.walk_commit_ids(&repo, None)?;
+        let mut history = self.read_commits(&repo, &commit_ids, limits, None, &mut summary)?;
+        if let Some(gap_minutes) = self.change_set_gap_minutes {
+            let mut grouper = ChangeSetGrouper::new(gap_minutes);
+            history.iter_mut().for_each(|commit| grouper.assign(commit));
+        }
+
+        info!(
+            "Read {} commits ({} touching tracked files)",
+            summary.commit_count,
+            history.len()
+        );
+        for (reason, count) in &summary.skip_reasons {
+            info!("Skipped {} commits: {}", count, reason);
+        }
+
+        Ok(history)
+    }
+
+    /// Decay is measured from HEAD, or from the newest commit inside an analysis window,
+    /// so an incremental run can rescale what earlier runs stored.
+    fn time_decay(
         &self,
-        client: &Neo4jClient,
-        repo_name: &str,
-        changed_files: &[ChangedFile],
-        renames: &[(String, String)],
-        commit_hash: Option<&str>,
-    ) -> Result<(i64, i64), String> {
-        let mut files_saved = 0i64;
-        let mut deleted_files_saved = 0i64;
-
-        debug!("Renaming files Started");
-        for (old_path, new_path) in renames {
-            match client.rename_file_node(repo_name, old_path, new_path).await {
-                Ok(_) => {}
-                Err(e) => {
-                    log::warn!(
-                        "Failed to rename file node {} -> {}: {}",
-                        old_path,
-                        new_path,
-                        e
-                    );
+        repo: &Repository,
+        head: Oid,
+        commit_ids: &[Oid],
+    ) -> Result<Option<TimeDecay>, String> {
+        let Some(half_life_days) = self.half_life_days else {
+            return Ok(None);
+        };
+
+        let commit_time = |id: Oid| {
+            repo.find_commit(id)
+                .map(|commit| commit.time().seconds())
+                .map_err(|e| format!("Failed to find commit: {}", e))
+        };
+
+        let reference = if self.window.is_empty() {
+            commit_time(head)?
+        } else {
+            let mut newest = i64::MIN;
+            for id in commit_ids {
+                newest = newest.max(commit_time(*id)?);
+            }
+            if newest == i64::MIN {
+                return Ok(None);
+            }
+            newest
+        };
+
+        Ok(Some(TimeDecay {
+            half_life: half_life_days * 86_400.0,
+            reference,
+        }))
+    }
+
+    /// Replays the already analysed commits that fall inside the collaboration window
+    /// before `last_commit`, so links spanning the resume point are still found.
+    fn seed_collaboration(
+        &self,
+        repo: &Repository,
+        last_commit: Oid,
+        window_days: u32,
+        limits: (usize, usize),
+        tracker: &mut CollaborationTracker,
+    ) -> Result<(), String> {
+        let last_time = repo
+            .find_commit(last_commit)
+            .map_err(|e| format!("Failed to find commit: {}", e))?
+            .time()
+            .seconds();
+        let since = last_time - window_days as i64 * 86_400;
+
+        let mut revwalk = repo
+            .revwalk()
+            .map_err(|err| format!("Error creating revwalk: {}", err))?;
+        revwalk
+            .push(last_commit)
+            .map_err(|err| format!("Error pushing revwalk {}", err))?;
+        self.simplify_walk(&mut revwalk)?;
+        revwalk
+            .set_sorting(git2::Sort::TIME | git2::Sort::REVERSE)
+            .map_err(|err| format!("Sorting failed {}", err))?;
+
+        let mut commit_ids = Vec::new();
+        for rev in revwalk {
+            let commit_id = rev.map_err(|err| format!("Error unwrapping revwalk:{}", err))?;
+            let commit = repo
+                .find_commit(commit_id)
+                .map_err(|e| format!("Failed to find commit: {}", e))?;
+            if commit.time().seconds() >= since {
+                commit_ids.push(commit_id);
+            }
+        }
+
+        let mut summary = AnalysisSummary::default();
+        for commit in self.read_commits(repo, &commit_ids, limits, None, &mut summary)? {
+            tracker.observe(&commit);
+        }
+
+        Ok(())
+    }
+
+    /// Replays the change set still open at `last_commit`: the unbroken run of commits by
+    /// its author leading up to it, so commits after the resume point can join it.
+    fn seed_change_set(
+        &self,
+        repo: &Repository,
+        last_commit: Oid,
+        gap_minutes: u32,
+        limits: (usize, usize),
+        grouper: &mut ChangeSetGrouper,
+    ) -> Result<(), String> {
+        let mailmap = repo
+            .mailmap()
+            .map_err(|e| format!("Failed to load mailmap: {}", e))?;
+
+        let mut revwalk = repo
+            .revwalk()
+            .map_err(|err| format!("Error creating revwalk: {}", err))?;
+        revwalk
+            .push(last_commit)
+            .map_err(|err| format!("Error pushing revwalk {}", err))?;
+        self.simplify_walk(&mut revwalk)?;
+        revwalk
+            .set_sorting(git2::Sort::TIME)
+            .map_err(|err| format!("Sorting failed {}", err))?;
+
+        let gap = gap_minutes as i64 * 60;
+        let mut summary = AnalysisSummary::default();
+        let mut open_set: Vec<CommitChanges> = Vec::new();
+        for rev in revwalk {
+            let commit_id = rev.map_err(|err| format!("Error unwrapping revwalk:{}", err))?;
+            let Some(changes) =
+                self.commit_changes(repo, commit_id, &mailmap, limits, &mut summary)?
+            else {
+                continue;
+            };
+            if !changes.record_cochanges {
+                break;
+            }
+            if let Some(next) = open_set.last() {
+                if changes.author.key() != next.author.key()
+                    || next.commit_time - changes.commit_time > gap
+                {
+                    break;
                 }
             }
+            open_set.push(changes);
+        }
+
+        for mut changes in open_set.into_iter().rev() {
+            grouper.assign(&mut changes);
         }
-        debug!("Renaming files Ended");
 
-        let mut unique_files: Vec<&str> = changed_files.iter().map(|f| f.path.as_str()).collect();
-        unique_files.sort();
-        unique_files.dedup();
+        Ok(())
+    }
 
-        debug!("Saving file nodes");
-        for file in changed_files {
-            let deleted_at_commit = if file.is_deleted { commit_hash } else { None };
-            files_saved += 1;
-            if file.is_deleted {
-                deleted_files_saved += 1;
+    /// Checks that the recorded commit is still part of HEAD's history. A rewritten history
+    /// would leave stale increments in the store, so it needs a full re-analysis.
+    fn resume_point(&self, repo: &Repository, head: Oid, last_commit: &str) -> Result<Oid, String> {
+        let last = Oid::from_str(last_commit)
+            .map_err(|e| format!("Invalid last commit {}: {}", last_commit, e))?;
+
+        let reachable = head == last
+            || repo
+                .graph_descendant_of(head, last)
+                .map_err(|e| format!("Failed to check last commit {}: {}", last_commit, e))?;
+        if !reachable {
+            return Err(format!(
+                "Last analysed commit {} is not an ancestor of HEAD; rerun without --incremental",
+                last_commit
+            ));
+        }
+
+        Ok(last)
+    }
+
+    fn walk_commit_ids(&self, repo: &Repository, after: Option<Oid>) -> Result<Vec<Oid>, String> {
+        let mut revwalk = repo
+            .revwalk()
+            .map_err(|err| format!("Error creating revwalk: {}", err))?;
+
+        match &self.window.range {
+            Some(range) => revwalk
+                .push_range(range)
+                .map_err(|err| format!("Invalid revision range {}: {}", range, err))?,
+            None => revwalk
+                .push_head()
+                .map_err(|err| format!("Error pushing revwalk {}", err))?,
+        }
+        if let Some(after) = after {
+            revwalk
+                .hide(after)
+                .map_err(|err| format!("Error hiding analysed commits {}", err))?;
+        }
+        self.simplify_walk(&mut revwalk)?;
+        revwalk
+            .set_sorting(git2::Sort::TIME | git2::Sort::REVERSE)
+            .map_err(|err| format!("Sorting failed {}", err))?;
+
+        let mut commit_ids = Vec::new();
+        for rev in revwalk {
+            let commit_id = rev.map_err(|err| format!("Error unwrapping revwalk:{}", err))?;
+            if self.window.since.is_some() || self.window.until.is_some() {
+                let commit = repo
+                    .find_commit(commit_id)
+                    .map_err(|e| format!("Failed to find commit: {}", e))?;
+                if !self.window.contains(commit.time().seconds()) {
+                    continue;
+                }
             }
-            client
-                .save_file_node(
-                    repo_name,
-                    &file.path,
-                    file.additions as i64,
-                    file.deletions as i64,
-                    deleted_at_commit,
-                )
-                .await
-                .map_err(|e| format!("Failed to save file node: {}", e))?;
+            commit_ids.push(commit_id);
         }
-        debug!("file nodes saved");
 
-        debug!("Relationships saving");
+        Ok(commit_ids)
+    }
 
-        for i in 0..unique_files.len() {
-            for j in (i + 1)..unique_files.len() {
-                let source = unique_files[i];
-                let target = unique_files[j];
+    fn simplify_walk(&self, revwalk: &mut git2::Revwalk) -> Result<(), String> {
+        if self.first_parent {
+            revwalk
+                .simplify_first_parent()
+                .map_err(|err| format!("Error simplifying revwalk {}", err))?;
+        }
+        Ok(())
+    }
 
-                client
-                    .save_cochange_relationship(repo_name, source, target)
-                    .await
-                    .map_err(|e| format!("Failed to save co-change relationship: {}", e))?;
+    /// Reads the changes of `commit_ids` in order, resolving authors and committers through
+    /// the repository's `.mailmap`. The mailmap cannot be held across an await, so streamed
+    /// runs call this once per batch.
+    fn read_commits(
+        &self,
+        repo: &Repository,
+        commit_ids: &[Oid],
+        limits: (usize, usize),
+        decay: Option<TimeDecay>,
+        summary: &mut AnalysisSummary,
+    ) -> Result<Vec<CommitChanges>, String> {
+        let mailmap = repo
+            .mailmap()
+            .map_err(|e| format!("Failed to load mailmap: {}", e))?;
+
+        let mut commits = Vec::with_capacity(commit_ids.len());
+        for commit_id in commit_ids {
+            if let Some(mut changes) =
+                self.commit_changes(repo, *commit_id, &mailmap, limits, summary)?
+            {
+                changes.decay = decay.map(|d| d.factor(changes.commit_time));
+                commits.push(changes);
+            }
+        }
+
+        Ok(commits)
+    }
+
+    /// Turns one commit into the changes to store, or `None` when it touches nothing of
+    /// interest. Large and filtered commits keep only their renames and deletions.
+    fn commit_changes(
+        &self,
+        repo: &Repository,
+        commit_id: Oid,
+        mailmap: &Mailmap,
+        (max_files_per_commit, max_renames_per_commit): (usize, usize),
+        summary: &mut AnalysisSummary,
+    ) -> Result<Option<CommitChanges>, String> {
+        let commit = repo
+            .find_commit(commit_id)
+            .map_err(|e| format!("Failed to find commit: {}", e))?;
+        if commit.parent_count() > 1 && self.merge_strategy == MergeStrategy::Skip {
+            debug!("Skipping merge commit {}", commit_id);
+            summary.skipped_merge_commits += 1;
+            *summary
+                .skip_reasons
+                .entry("merge commit".to_string())
+                .or_insert(0) += 1;
+            return Ok(None);
+        }
+        debug!("Getting changed files");
+        let mut changed_lines = HashMap::new();
+        let (changed_files, renames) = self.get_changed_files(
+            repo,
+            &commit,
+            self.function_level.then_some(&mut changed_lines),
+            &mut summary.excluded_files,
+        )?;
+
+        let commit_hash = commit.id().to_string();
+        let commit_time = commit.time().seconds();
+        let author = commit
+            .author_with_mailmap(mailmap)
+            .map_err(|e| format!("Failed to resolve author of {}: {}", commit_hash, e))?;
+        let committer = commit
+            .committer_with_mailmap(mailmap)
+            .map_err(|e| format!("Failed to resolve committer of {}: {}", commit_hash, e))?;
+        let message = String::from_utf8_lossy(commit.message_bytes());
+        let class = self.classifier.classify(&message, commit.parent_count());
+        let issues = extract_issue_refs(&message);
+        summary.commit_count += 1;
+
+        let skip_reason = if is_shallow_boundary(repo, &commit) {
+            Some("shallow clone boundary".to_string())
+        } else if changed_files.len() > max_files_per_commit {
+            debug!(
+                "Large commit detected: {} ({} files)",
+                commit_hash,
+                changed_files.len()
+            );
+            summary.skipped_large_commits += 1;
+            Some(format!("more than {} files", max_files_per_commit))
+        } else if changed_files.is_empty() {
+            None
+        } else {
+            let reason = self.filter_reason(
+                repo,
+                &commit,
+                &identity(&author),
+                &message,
+                &changed_files,
+                &renames,
+            )?;
+            if reason.is_some() {
+                summary.filtered_commits += 1;
+            }
+            reason
+        };
+
+        if let Some(reason) = skip_reason {
+            // Still process renames if under threshold, and always keep deletions
+            let kept_renames = if renames.len() <= max_renames_per_commit {
+                renames.clone()
+            } else {
+                Vec::new()
+            };
+            let deleted_files: Vec<ChangedFile> = changed_files
+                .iter()
+                .filter(|f| f.is_deleted)
+                .cloned()
+                .collect();
+            summary.deleted_files_from_large_commits += deleted_files.len() as i64;
+
+            info!(
+                "Skipped commit {}, {} ({} files, {} renames)",
+                commit_hash,
+                reason,
+                changed_files.len(),
+                renames.len()
+            );
+            *summary.skip_reasons.entry(reason).or_insert(0) += 1;
+
+            if kept_renames.is_empty() && deleted_files.is_empty() {
+                return Ok(None);
+            }
+
+            return Ok(Some(CommitChanges {
+                commit_hash,
+                commit_time,
+                renames: kept_renames,
+                files: deleted_files,
+                record_cochanges: false,
+                decay: None,
+                author: identity(&author),
+                committer: identity(&committer),
+                class,
+                issues,
+                change_set_files: Vec::new(),
+                functions: Vec::new(),
+            }));
+        }
+
+        if changed_files.is_empty() && renames.is_empty() {
+            return Ok(None);
+        }
+
+        summary.files_saved += changed_files.len() as i64;
+        summary.deleted_files_saved += changed_files.iter().filter(|f| f.is_deleted).count() as i64;
+        let unique_files = changed_files
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect::<HashSet<_>>()
+            .len();
+        summary.cochange_pairs += (unique_files * unique_files.saturating_sub(1) / 2) as i64;
+        let functions = self.changed_functions(repo, &commit, &changed_files, &changed_lines)?;
+
+        Ok(Some(CommitChanges {
+            commit_hash,
+            commit_time,
+            renames,
+            files: changed_files,
+            record_cochanges: true,
+            decay: None,
+            author: identity(&author),
+            committer: identity(&committer),
+            class,
+            issues,
+            change_set_files: Vec::new(),
+            functions,
+        }))
+    }
+
+    fn filter_reason(
+        &self,
+        repo: &Repository,
+        commit: &git2::Commit,
+        author: &Identity,
+        message: &str,
+        changed_files: &[ChangedFile],
+        renames: &[(String, String)],
+    ) -> Result<Option<String>, String> {
+        if let Some(reason) = self.commit_filter.exclusion(author, message) {
+            return Ok(Some(reason));
+        }
+        if self.commit_filter.skips_whitespace_only()
+            && renames.is_empty()
+            && self.whitespace_only(repo, commit, changed_files)?
+        {
+            return Ok(Some("whitespace-only changes".to_string()));
+        }
+        Ok(None)
+    }
+
+    /// Checks whether every analysed file the commit modifies differs from its parent only
+    /// in whitespace. Merges and added or deleted files always count as real changes.
+    fn whitespace_only(
+        &self,
+        repo: &Repository,
+        commit: &git2::Commit,
+        changed_files: &[ChangedFile],
+    ) -> Result<bool, String> {
+        if commit.parent_count() != 1 || changed_files.iter().any(|f| f.is_deleted) {
+            return Ok(false);
+        }
+
+        let tree = commit
+            .tree()
+            .map_err(|e| format!("Failed to get commit tree: {}", e))?;
+        let parent_tree = commit
+            .parent(0)
+            .and_then(|parent| parent.tree())
+            .map_err(|e| format!("Failed to get parent tree: {}", e))?;
+
+        let mut options = DiffOptions::new();
+        options.ignore_whitespace(true).disable_pathspec_match(true);
+        for file in changed_files {
+            options.pathspec(&file.path);
+        }
+        let diff = repo
+            .diff_tree_to_tree(Some(&parent_tree), Some(&tree), Some(&mut options))
+            .map_err(|e| format!("Failed to get diff: {}", e))?;
+
+        for (idx, delta) in diff.deltas().enumerate() {
+            if delta.status() != Delta::Modified {
+                return Ok(false);
+            }
+            let patch =
+                Patch::from_diff(&diff, idx).map_err(|e| format!("Failed to get patch: {}", e))?;
+            if let Some(patch) = patch {
+                let (_, additions, deletions) = patch
+                    .line_stats()
+                    .map_err(|e| format!("Failed to get line stats: {}", e))?;
+                if additions + deletions > 0 {
+                    return Ok(false);
+                }
             }
         }
-        debug!("Relationships saved");
 
-        Ok((files_saved, deleted_files_saved))
+        Ok(true)
+    }
+
+    /// Parses the commit's version of every changed file and attributes its changed lines
+    /// to functions.
+    fn changed_functions(
+        &self,
+        repo: &Repository,
+        commit: &git2::Commit,
+        changed_files: &[ChangedFile],
+        changed_lines: &HashMap<String, Vec<u32>>,
+    ) -> Result<Vec<ChangedFunction>, String> {
+        if changed_lines.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let tree = commit
+            .tree()
+            .map_err(|e| format!("Failed to get commit tree: {}", e))?;
+        let mut functions = Vec::new();
+        for file in changed_files.iter().filter(|f| !f.is_deleted) {
+            let Some(lines) = changed_lines.get(&file.path) else {
+                continue;
+            };
+            let Ok(entry) = tree.get_path(Path::new(&file.path)) else {
+                continue;
+            };
+            let blob = repo
+                .find_blob(entry.id())
+                .map_err(|e| format!("Failed to read {}: {}", file.path, e))?;
+            let spans = function_spans(&file.path, blob.content().to_vec());
+            functions.extend(changed_functions(&file.path, &spans, lines));
+        }
+
+        Ok(functions)
+    }
+
+    async fn flush_batch(
+        &self,
+        client: &dyn GraphStore,
+        repo_name: &str,
+        pending: &mut Vec<CommitChanges>,
+        summary: &mut AnalysisSummary,
+    ) -> Result<(), String> {
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        debug!("Writing batch of {} commits", pending.len());
+        client
+            .save_commit_batch(repo_name, pending)
+            .await
+            .map_err(|e| format!("Failed to save commit batch: {}", e))?;
+        summary.write_batches += 1;
+        pending.clear();
+
+        Ok(())
     }
 
     fn get_changed_files(
         &self,
         repo: &Repository,
         commit: &git2::Commit,
+        mut changed_lines: Option<&mut HashMap<String, Vec<u32>>>,
+        excluded: &mut BTreeMap<String, String>,
     ) -> Result<(Vec<ChangedFile>, Vec<(String, String)>), String> {
         let tree = commit
             .tree()
@@ -272,7 +1280,7 @@ impl GitAnalyzer {
 
         if commit.parent_count() == 0 {
             let mut diff = repo
-                .diff_tree_to_tree(None, Some(&tree), None)
+                .diff_tree_to_tree(None, Some(&tree), Some(&mut self.diff_options()))
                 .map_err(|e| format!("Failed to get diff: {}", e))?;
 
             diff.find_similar(Some(&mut DiffFindOptions::new().renames(true)))
@@ -280,43 +1288,26 @@ impl GitAnalyzer {
 
             let mut file_stats: std::collections::HashMap<String, (u32, u32)> =
                 std::collections::HashMap::new();
+            let mut normalized_stats = HashMap::new();
             Self::collect_diff_entries(
                 &diff,
                 &mut renames,
                 &mut deleted_paths,
                 &mut all_paths,
                 &mut file_stats,
-                &self.extensions,
+                changed_lines.as_deref_mut(),
+                &|path| self.analyses(path),
             )?;
-
-            let mut result: Vec<ChangedFile> = all_paths
-                .iter()
-                .collect::<std::collections::HashSet<_>>()
-                .into_iter()
-                .filter_map(|path| {
-                    let (additions, deletions) = file_stats.remove(path).unwrap_or((0, 0));
-                    Some(ChangedFile {
-                        path: path.clone(),
-                        additions,
-                        deletions,
-                        is_deleted: false,
-                        renamed_to: None,
-                    })
-                })
-                .collect();
-
-            for path in deleted_paths {
-                result.push(ChangedFile {
-                    path,
-                    additions: 0,
-                    deletions: 0,
-                    is_deleted: true,
-                    renamed_to: None,
-                });
+            self.record_exclusions(&diff, excluded);
+            if self.normalize_churn {
+                self.normalized_stats(repo, None, &tree, &mut normalized_stats)?;
             }
 
+            let result =
+                self.changed_file_list(&all_paths, deleted_paths, file_stats, normalized_stats);
+
             return Ok((result, renames));
-        } else if commit.parent_count() == 1 {
+        } else if commit.parent_count() == 1 || self.merge_strategy == MergeStrategy::FirstParent {
             let parent = commit
                 .parent(0)
                 .map_err(|e| format!("Failed to get parent commit: {}", e))?;
@@ -325,7 +1316,11 @@ impl GitAnalyzer {
                 .map_err(|e| format!("Failed to get parent tree: {}", e))?;
 
             let mut diff = repo
-                .diff_tree_to_tree(Some(&parent_tree), Some(&tree), None)
+                .diff_tree_to_tree(
+                    Some(&parent_tree),
+                    Some(&tree),
+                    Some(&mut self.diff_options()),
+                )
                 .map_err(|e| format!("Failed to get diff: {}", e))?;
 
             diff.find_similar(Some(&mut DiffFindOptions::new().renames(true)))
@@ -333,98 +1328,293 @@ impl GitAnalyzer {
 
             let mut file_stats: std::collections::HashMap<String, (u32, u32)> =
                 std::collections::HashMap::new();
+            let mut normalized_stats = HashMap::new();
             Self::collect_diff_entries(
                 &diff,
                 &mut renames,
                 &mut deleted_paths,
                 &mut all_paths,
                 &mut file_stats,
-                &self.extensions,
+                changed_lines.as_deref_mut(),
+                &|path| self.analyses(path),
             )?;
-
-            let mut result: Vec<ChangedFile> = all_paths
-                .iter()
-                .collect::<std::collections::HashSet<_>>()
-                .into_iter()
-                .filter_map(|path| {
-                    let (additions, deletions) = file_stats.remove(path).unwrap_or((0, 0));
-                    Some(ChangedFile {
-                        path: path.clone(),
-                        additions,
-                        deletions,
-                        is_deleted: false,
-                        renamed_to: None,
-                    })
-                })
-                .collect();
-
-            for path in deleted_paths {
-                result.push(ChangedFile {
-                    path,
-                    additions: 0,
-                    deletions: 0,
-                    is_deleted: true,
-                    renamed_to: None,
-                });
+            self.record_exclusions(&diff, excluded);
+            if self.normalize_churn {
+                self.normalized_stats(repo, Some(&parent_tree), &tree, &mut normalized_stats)?;
             }
 
+            let result =
+                self.changed_file_list(&all_paths, deleted_paths, file_stats, normalized_stats);
+
             return Ok((result, renames));
         } else {
             let mut file_stats: std::collections::HashMap<String, (u32, u32)> =
                 std::collections::HashMap::new();
+            let mut normalized_stats = HashMap::new();
+
+            if self.merge_strategy == MergeStrategy::CombinedDiff {
+                let combined = self.combined_diff(repo, commit, &tree, excluded)?;
+                renames = combined.renames;
+                deleted_paths = combined.deleted_paths;
+                all_paths = combined.all_paths;
+                file_stats = combined.file_stats;
+                normalized_stats = combined.normalized_stats;
+                if let Some(changed_lines) = changed_lines {
+                    *changed_lines = combined.changed_lines;
+                }
+            } else {
+                for i in 0..commit.parent_count() {
+                    let parent = commit
+                        .parent(i)
+                        .map_err(|e| format!("Failed to get parent commit: {}", e))?;
+                    let parent_tree = parent
+                        .tree()
+                        .map_err(|e| format!("Failed to get parent tree: {}", e))?;
 
-            for i in 0..commit.parent_count() {
-                let parent = commit
-                    .parent(i)
-                    .map_err(|e| format!("Failed to get parent commit: {}", e))?;
-                let parent_tree = parent
-                    .tree()
-                    .map_err(|e| format!("Failed to get parent tree: {}", e))?;
-
-                let mut diff = repo
-                    .diff_tree_to_tree(Some(&parent_tree), Some(&tree), None)
-                    .map_err(|e| format!("Failed to get diff: {}", e))?;
-
-                diff.find_similar(Some(&mut DiffFindOptions::new().renames(true)))
-                    .map_err(|e| format!("Failed to find similar: {}", e))?;
-
-                Self::collect_diff_entries(
-                    &diff,
-                    &mut renames,
-                    &mut deleted_paths,
-                    &mut all_paths,
-                    &mut file_stats,
-                    &self.extensions,
-                )?;
+                    let mut diff = repo
+                        .diff_tree_to_tree(
+                            Some(&parent_tree),
+                            Some(&tree),
+                            Some(&mut self.diff_options()),
+                        )
+                        .map_err(|e| format!("Failed to get diff: {}", e))?;
+
+                    diff.find_similar(Some(&mut DiffFindOptions::new().renames(true)))
+                        .map_err(|e| format!("Failed to find similar: {}", e))?;
+
+                    Self::collect_diff_entries(
+                        &diff,
+                        &mut renames,
+                        &mut deleted_paths,
+                        &mut all_paths,
+                        &mut file_stats,
+                        changed_lines.as_deref_mut(),
+                        &|path| self.analyses(path),
+                    )?;
+                    self.record_exclusions(&diff, excluded);
+                    if self.normalize_churn {
+                        self.normalized_stats(
+                            repo,
+                            Some(&parent_tree),
+                            &tree,
+                            &mut normalized_stats,
+                        )?;
+                    }
+                }
             }
 
-            let mut result: Vec<ChangedFile> = all_paths
-                .iter()
-                .collect::<std::collections::HashSet<_>>()
-                .into_iter()
-                .filter_map(|path| {
-                    let (additions, deletions) = file_stats.remove(path).unwrap_or((0, 0));
-                    Some(ChangedFile {
-                        path: path.clone(),
-                        additions,
-                        deletions,
-                        is_deleted: false,
-                        renamed_to: None,
-                    })
-                })
-                .collect();
+            let result =
+                self.changed_file_list(&all_paths, deleted_paths, file_stats, normalized_stats);
+
+            return Ok((result, renames));
+        }
+    }
 
-            for path in deleted_paths {
-                result.push(ChangedFile {
-                    path,
-                    additions: 0,
-                    deletions: 0,
-                    is_deleted: true,
+    /// Diffs a merge against each of its parents and keeps what changed relative to all of
+    /// them: files and renames present in every diff, the smallest line counts and the
+    /// added or removed lines every diff agrees on.
+    fn combined_diff(
+        &self,
+        repo: &Repository,
+        commit: &git2::Commit,
+        tree: &git2::Tree,
+        excluded: &mut BTreeMap<String, String>,
+    ) -> Result<CombinedDiff, String> {
+        let mut combined: Option<CombinedDiff> = None;
+        for i in 0..commit.parent_count() {
+            let parent = commit
+                .parent(i)
+                .map_err(|e| format!("Failed to get parent commit: {}", e))?;
+            let parent_tree = parent
+                .tree()
+                .map_err(|e| format!("Failed to get parent tree: {}", e))?;
+
+            let mut diff = repo
+                .diff_tree_to_tree(
+                    Some(&parent_tree),
+                    Some(tree),
+                    Some(&mut self.diff_options()),
+                )
+                .map_err(|e| format!("Failed to get diff: {}", e))?;
+
+            diff.find_similar(Some(&mut DiffFindOptions::new().renames(true)))
+                .map_err(|e| format!("Failed to find similar: {}", e))?;
+
+            let mut side = CombinedDiff::default();
+            Self::collect_diff_entries(
+                &diff,
+                &mut side.renames,
+                &mut side.deleted_paths,
+                &mut side.all_paths,
+                &mut side.file_stats,
+                Some(&mut side.changed_lines),
+                &|path| self.analyses(path),
+            )?;
+            self.record_exclusions(&diff, excluded);
+            if self.normalize_churn {
+                self.normalized_stats(repo, Some(&parent_tree), tree, &mut side.normalized_stats)?;
+            }
+
+            combined = Some(match combined {
+                None => side,
+                Some(combined) => combined.intersect(side),
+            });
+        }
+
+        Ok(combined.unwrap_or_default())
+    }
+
+    /// Pairs every changed path with its line counts. Without churn normalization the
+    /// normalized counts repeat the raw ones.
+    fn changed_file_list(
+        &self,
+        all_paths: &[String],
+        deleted_paths: HashSet<String>,
+        mut file_stats: HashMap<String, (u32, u32)>,
+        mut normalized_stats: HashMap<String, (u32, u32)>,
+    ) -> Vec<ChangedFile> {
+        let mut result: Vec<ChangedFile> = all_paths
+            .iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .map(|path| {
+                let (additions, deletions) = file_stats.remove(path).unwrap_or((0, 0));
+                let (normalized_additions, normalized_deletions) = if self.normalize_churn {
+                    normalized_stats.remove(path).unwrap_or((0, 0))
+                } else {
+                    (additions, deletions)
+                };
+                ChangedFile {
+                    path: path.clone(),
+                    additions,
+                    deletions,
+                    normalized_additions,
+                    normalized_deletions,
+                    is_deleted: false,
                     renamed_to: None,
-                });
+                }
+            })
+            .collect();
+
+        for path in deleted_paths {
+            result.push(ChangedFile {
+                path,
+                additions: 0,
+                deletions: 0,
+                normalized_additions: 0,
+                normalized_deletions: 0,
+                is_deleted: true,
+                renamed_to: None,
+            });
+        }
+
+        result
+    }
+
+    /// Line counts of the same change diffed with whitespace ignored, leaving out hunks
+    /// whose removed and added lines only differ in layout, such as a re-wrapped statement
+    /// or a moved brace.
+    fn normalized_stats(
+        &self,
+        repo: &Repository,
+        old_tree: Option<&git2::Tree>,
+        new_tree: &git2::Tree,
+        stats: &mut HashMap<String, (u32, u32)>,
+    ) -> Result<(), String> {
+        let mut options = self.diff_options();
+        options.ignore_whitespace(true);
+        let mut diff = repo
+            .diff_tree_to_tree(old_tree, Some(new_tree), Some(&mut options))
+            .map_err(|e| format!("Failed to get diff: {}", e))?;
+
+        diff.find_similar(Some(&mut DiffFindOptions::new().renames(true)))
+            .map_err(|e| format!("Failed to find similar: {}", e))?;
+
+        for (idx, delta) in diff.deltas().enumerate() {
+            let Some(path) = delta.new_file().path() else {
+                continue;
+            };
+            let path = path.to_string_lossy();
+            if !self.analyses(&path) {
+                continue;
             }
+            let Some(patch) =
+                Patch::from_diff(&diff, idx).map_err(|e| format!("Failed to get patch: {}", e))?
+            else {
+                continue;
+            };
 
-            return Ok((result, renames));
+            let stats = stats.entry(path.into_owned()).or_insert((0, 0));
+            for hunk in 0..patch.num_hunks() {
+                let lines = patch
+                    .num_lines_in_hunk(hunk)
+                    .map_err(|e| format!("Failed to get hunk: {}", e))?;
+                let (mut added, mut removed) = (String::new(), String::new());
+                let (mut additions, mut deletions) = (0, 0);
+                for line_idx in 0..lines {
+                    let line = patch
+                        .line_in_hunk(hunk, line_idx)
+                        .map_err(|e| format!("Failed to get hunk line: {}", e))?;
+                    let text = match line.origin() {
+                        '+' => {
+                            additions += 1;
+                            &mut added
+                        }
+                        '-' => {
+                            deletions += 1;
+                            &mut removed
+                        }
+                        _ => continue,
+                    };
+                    text.extend(
+                        String::from_utf8_lossy(line.content())
+                            .chars()
+                            .filter(|c| !c.is_whitespace()),
+                    );
+                }
+                if added != removed {
+                    stats.0 += additions;
+                    stats.1 += deletions;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn diff_options(&self) -> DiffOptions {
+        let mut options = DiffOptions::new();
+        if self.analysed_paths_only {
+            for extension in &self.extensions {
+                options.pathspec(format!("*{}", extension));
+            }
+        }
+        options
+    }
+
+    fn has_extension(&self, path: &str) -> bool {
+        self.extensions.is_empty() || self.extensions.iter().any(|ext| path.ends_with(ext))
+    }
+
+    fn analyses(&self, path: &str) -> bool {
+        self.has_extension(path) && self.path_filter.exclusion(path).is_none()
+    }
+
+    fn record_exclusions(&self, diff: &git2::Diff, excluded: &mut BTreeMap<String, String>) {
+        for delta in diff.deltas() {
+            if delta.status() == Delta::Deleted {
+                continue;
+            }
+            let Some(path) = delta.new_file().path() else {
+                continue;
+            };
+            let path = path.to_string_lossy();
+            if !self.has_extension(&path) || excluded.contains_key(path.as_ref()) {
+                continue;
+            }
+            if let Some(reason) = self.path_filter.exclusion(&path) {
+                excluded.insert(path.into_owned(), reason);
+            }
         }
     }
 
@@ -434,8 +1624,14 @@ impl GitAnalyzer {
         deleted_paths: &mut HashSet<String>,
         all_paths: &mut Vec<String>,
         file_stats: &mut std::collections::HashMap<String, (u32, u32)>,
-        extensions: &[String],
+        mut changed_lines: Option<&mut HashMap<String, Vec<u32>>>,
+        analyses: &dyn Fn(&str) -> bool,
     ) -> Result<(), String> {
+        // Line in the new file where the next removed line was, so deletions can be
+        // attributed to the function they were removed from
+        let cursor = Cell::new(0u32);
+        // Whether the file of the current hunk is analysed, so lines skip the path check
+        let analysed = Cell::new(false);
         diff.foreach(
             &mut |delta, _| {
                 match delta.status() {
@@ -462,21 +1658,38 @@ impl GitAnalyzer {
                 true
             },
             None,
-            Some(&mut |delta, _| {
+            Some(&mut |delta, hunk| {
+                cursor.set(hunk.new_start());
+                analysed.set(false);
                 if let Some(path) = delta.new_file().path() {
                     let path_str = path.to_string_lossy().to_string();
-                    if extensions.is_empty() || extensions.iter().any(|ext| path_str.ends_with(ext))
-                    {
+                    if analyses(&path_str) {
+                        analysed.set(true);
                         all_paths.push(path_str);
                     }
                 }
                 true
             }),
             Some(&mut |delta, _hunk, line| {
+                if let Some(new_lineno) = line.new_lineno() {
+                    cursor.set(new_lineno + 1);
+                }
                 if let Some(path) = delta.new_file().path() {
                     let path_str = path.to_string_lossy().to_string();
-                    if extensions.is_empty() || extensions.iter().any(|ext| path_str.ends_with(ext))
-                    {
+                    if analysed.get() {
+                        if let Some(changed_lines) = changed_lines.as_deref_mut() {
+                            let changed_line = match line.origin() {
+                                '+' => line.new_lineno(),
+                                '-' => Some(cursor.get().max(1)),
+                                _ => None,
+                            };
+                            if let Some(changed_line) = changed_line {
+                                changed_lines
+                                    .entry(path_str.clone())
+                                    .or_default()
+                                    .push(changed_line);
+                            }
+                        }
                         let stats = file_stats.entry(path_str).or_insert((0, 0));
                         if line.origin() == '+' {
                             stats.0 += 1;
@@ -493,3 +1706,68 @@ impl GitAnalyzer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_accepts_dates_and_rfc3339_times() {
+        let window =
+            AnalysisWindow::parse(Some("2024-01-01"), Some("2024-01-02T12:00:00Z"), None).unwrap();
+        assert_eq!(window.since, Some(1704067200));
+        assert_eq!(window.until, Some(1704196800));
+        assert!(window.contains(1704067200));
+        assert!(!window.contains(1704196800));
+    }
+
+    #[test]
+    fn window_rejects_invalid_dates() {
+        let err = AnalysisWindow::parse(Some("01/02/2024"), None, None).unwrap_err();
+        assert!(err.contains("Invalid date '01/02/2024'"), "{}", err);
+        assert!(AnalysisWindow::parse(None, Some("2024-13-01"), None).is_err());
+    }
+
+    #[test]
+    fn window_rejects_since_not_before_until() {
+        let err = AnalysisWindow::parse(Some("2024-01-01"), Some("2024-01-01"), None).unwrap_err();
+        assert_eq!(err, "--since must be earlier than --until");
+    }
+
+    #[test]
+    fn invalid_revision_ranges_are_reported() {
+        let path = std::env::temp_dir().join(format!("range-test-{}", std::process::id()));
+        let repo = Repository::init(&path).unwrap();
+        let window = AnalysisWindow::parse(None, None, Some("v1.0..v2.0")).unwrap();
+        let analyzer = GitAnalyzer::new(path.display().to_string(), String::new(), String::new())
+            .with_window(window);
+
+        let err = analyzer.walk_commit_ids(&repo, None).unwrap_err();
+        std::fs::remove_dir_all(&path).unwrap();
+        assert!(
+            err.starts_with("Invalid revision range v1.0..v2.0"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn merge_strategy_rejects_unknown_values() {
+        assert_eq!(
+            MergeStrategy::parse("first-parent"),
+            Ok(MergeStrategy::FirstParent)
+        );
+        assert_eq!(
+            MergeStrategy::parse("combined-diff"),
+            Ok(MergeStrategy::CombinedDiff)
+        );
+
+        let err = MergeStrategy::parse("octopus").unwrap_err();
+        assert!(
+            err.starts_with("Unknown merge strategy 'octopus'"),
+            "{}",
+            err
+        );
+        assert!(MergeStrategy::parse("First-Parent").is_err());
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::storage::{Edge, FileNode, GraphData};
+
+#[derive(Debug, Clone)]
+pub struct HubScore {
+    pub path: String,
+    pub partner_count: i64,
+    pub avg_coupling: f64,
+    pub hub_score: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionHubScore {
+    pub path: String,
+    pub name: String,
+    pub partner_count: i64,
+    pub avg_coupling: f64,
+    pub hub_score: f64,
+}
+
+/// Rust port of the hub score query in `Neo4jClient::compute_hub_scores`, used by the
+/// stores that have no query engine of their own. Edges are treated as directed, exactly
+/// like the `(f)-[:CO_CHANGED]->(t)` pattern in Cypher.
+pub fn compute_hub_scores(graph: &GraphData, min_coupling: f64) -> Vec<HubScore> {
+    score_files(
+        graph,
+        min_coupling,
+        |f| Some((f.additions + f.deletions) as f64),
+        |f| Some(f.commit_count as f64),
+        |e| Some(e.weight as f64),
+    )
+}
+
+/// Same formula with churn that leaves out whitespace changes and formatting-only hunks.
+/// Without churn normalization this equals the raw churn, and so do the scores.
+pub fn compute_normalized_hub_scores(graph: &GraphData, min_coupling: f64) -> Vec<HubScore> {
+    score_files(
+        graph,
+        min_coupling,
+        |f| Some((f.normalized_additions + f.normalized_deletions) as f64),
+        |f| Some(f.commit_count as f64),
+        |e| Some(e.weight as f64),
+    )
+}
+
+/// Same formula over the time-decayed churn, commit counts and edge weights. Files and
+/// edges recorded without decay are ignored, so this is empty for undecayed graphs.
+pub fn compute_decayed_hub_scores(graph: &GraphData, min_coupling: f64) -> Vec<HubScore> {
+    score_files(
+        graph,
+        min_coupling,
+        |f| f.decayed_churn,
+        |f| f.decayed_commit_count,
+        |e| e.decayed_weight,
+    )
+}
+
+fn score_files(
+    graph: &GraphData,
+    min_coupling: f64,
+    churn_of: impl Fn(&FileNode) -> Option<f64>,
+    commits_of: impl Fn(&FileNode) -> Option<f64>,
+    weight_of: impl Fn(&Edge) -> Option<f64>,
+) -> Vec<HubScore> {
+    let live_files = graph.files.iter().filter(|f| f.deleted_at_commit.is_none());
+    let total_files = live_files.clone().count() as i64;
+    let total_churn: f64 = live_files.filter_map(&churn_of).sum();
+
+    if total_files == 0 || total_churn == 0.0 {
+        return Vec::new();
+    }
+
+    let commit_counts: HashMap<&str, f64> = graph
+        .files
+        .iter()
+        .filter_map(|f| Some((f.path.as_str(), commits_of(f)?)))
+        .collect();
+
+    let mut couplings: HashMap<&str, Vec<f64>> = HashMap::new();
+    for edge in &graph.edges {
+        let Some(&target_commits) = commit_counts.get(edge.target.as_str()) else {
+            continue;
+        };
+        if !commit_counts.contains_key(edge.source.as_str()) {
+            continue;
+        }
+        let Some(weight) = weight_of(edge) else {
+            continue;
+        };
+        let coupling = weight / target_commits;
+        if coupling >= min_coupling {
+            couplings
+                .entry(edge.source.as_str())
+                .or_default()
+                .push(coupling);
+        }
+    }
+
+    graph
+        .files
+        .iter()
+        .filter_map(|file| {
+            let valid_edges = couplings.get(file.path.as_str())?;
+            let partner_count = valid_edges.len() as i64;
+            let avg_coupling = valid_edges.iter().sum::<f64>() / partner_count as f64;
+            let file_churn = churn_of(file).unwrap_or(0.0);
+
+            let hub_score = (avg_coupling
+                * (partner_count as f64 / total_files as f64)
+                * (file_churn / total_churn))
+                * 1000.0;
+
+            Some(HubScore {
+                path: file.path.clone(),
+                partner_count,
+                avg_coupling,
+                hub_score,
+            })
+        })
+        .collect()
+}
+
+/// The file hub score formula applied to the function graph: churn is the number of
+/// changed lines inside a function and only functions of live files are counted.
+pub fn compute_function_hub_scores(graph: &GraphData, min_coupling: f64) -> Vec<FunctionHubScore> {
+    let live_files: HashSet<&str> = graph
+        .files
+        .iter()
+        .filter(|f| f.deleted_at_commit.is_none())
+        .map(|f| f.path.as_str())
+        .collect();
+    let functions: Vec<_> = graph
+        .functions
+        .iter()
+        .filter(|f| live_files.contains(f.path.as_str()))
+        .collect();
+
+    let total_functions = functions.len() as i64;
+    let total_churn: f64 = functions.iter().map(|f| f.churn as f64).sum();
+    if total_functions == 0 || total_churn == 0.0 {
+        return Vec::new();
+    }
+
+    let commit_counts: HashMap<(&str, &str), f64> = functions
+        .iter()
+        .map(|f| ((f.path.as_str(), f.name.as_str()), f.commit_count as f64))
+        .collect();
+
+    let mut couplings: HashMap<(&str, &str), Vec<f64>> = HashMap::new();
+    for edge in &graph.function_edges {
+        let source = (edge.source_path.as_str(), edge.source_name.as_str());
+        let target = (edge.target_path.as_str(), edge.target_name.as_str());
+        let Some(&target_commits) = commit_counts.get(&target) else {
+            continue;
+        };
+        if !commit_counts.contains_key(&source) {
+            continue;
+        }
+        let coupling = edge.weight as f64 / target_commits;
+        if coupling >= min_coupling {
+            couplings.entry(source).or_default().push(coupling);
+        }
+    }
+
+    functions
+        .iter()
+        .filter_map(|function| {
+            let valid_edges = couplings.get(&(function.path.as_str(), function.name.as_str()))?;
+            let partner_count = valid_edges.len() as i64;
+            let avg_coupling = valid_edges.iter().sum::<f64>() / partner_count as f64;
+
+            let hub_score = (avg_coupling
+                * (partner_count as f64 / total_functions as f64)
+                * (function.churn as f64 / total_churn))
+                * 1000.0;
+
+            Some(FunctionHubScore {
+                path: function.path.clone(),
+                name: function.name.clone(),
+                partner_count,
+                avg_coupling,
+                hub_score,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, churn: i64, commit_count: i64) -> FileNode {
+        FileNode {
+            path: path.to_string(),
+            additions: churn,
+            deletions: 0,
+            // Without churn normalization the normalized counts equal the raw ones
+            normalized_additions: churn,
+            normalized_deletions: 0,
+            commit_count,
+            deleted_at_commit: None,
+            decayed_churn: None,
+            decayed_commit_count: None,
+            authors: Vec::new(),
+            commit_classes: Default::default(),
+        }
+    }
+
+    fn edge(source: &str, target: &str, weight: i64) -> Edge {
+        Edge {
+            source: source.to_string(),
+            target: target.to_string(),
+            weight,
+            decayed_weight: None,
+        }
+    }
+
+    fn fixture() -> GraphData {
+        let mut deleted = file("d.cpp", 60, 1);
+        deleted.deleted_at_commit = Some("abc".to_string());
+
+        GraphData {
+            repo: "fixture".to_string(),
+            total_commits_analyzed: 6,
+            files: vec![
+                file("a.cpp", 40, 4),
+                file("b.cpp", 10, 2),
+                file("c.cpp", 10, 5),
+                deleted,
+            ],
+            edges: vec![
+                edge("a.cpp", "b.cpp", 2),
+                edge("a.cpp", "c.cpp", 1),
+                edge("b.cpp", "a.cpp", 2),
+            ],
+            window: None,
+            authors: Vec::new(),
+            collaborations: Vec::new(),
+            issues: Vec::new(),
+            issue_edges: Vec::new(),
+            functions: Vec::new(),
+            function_edges: Vec::new(),
+            excluded_files: Vec::new(),
+            analysis_runs: Vec::new(),
+        }
+    }
+
+    fn score<'a>(scores: &'a [HubScore], path: &str) -> &'a HubScore {
+        scores.iter().find(|s| s.path == path).unwrap()
+    }
+
+    #[test]
+    fn coupling_is_edge_weight_over_target_commits() {
+        let scores = compute_hub_scores(&fixture(), 0.0);
+        assert_eq!(scores.len(), 2);
+
+        // a -> b: 2 / 2 commits, a -> c: 1 / 5 commits
+        let a = score(&scores, "a.cpp");
+        assert_eq!(a.partner_count, 2);
+        assert!((a.avg_coupling - 0.6).abs() < 1e-9);
+
+        // b -> a: 2 / 4 commits
+        let b = score(&scores, "b.cpp");
+        assert_eq!(b.partner_count, 1);
+        assert!((b.avg_coupling - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hub_score_weighs_partners_and_churn_of_live_files() {
+        let scores = compute_hub_scores(&fixture(), 0.3);
+
+        // 3 live files with 60 lines of churn; the deleted file counts for neither
+        let a = score(&scores, "a.cpp");
+        assert_eq!(a.partner_count, 1);
+        assert!((a.hub_score - 1.0 * (1.0 / 3.0) * (40.0 / 60.0) * 1000.0).abs() < 1e-9);
+
+        let b = score(&scores, "b.cpp");
+        assert!((b.hub_score - 0.5 * (1.0 / 3.0) * (10.0 / 60.0) * 1000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn normalized_scores_equal_raw_scores_without_normalization() {
+        let graph = fixture();
+        let raw = compute_hub_scores(&graph, 0.0);
+        let normalized = compute_normalized_hub_scores(&graph, 0.0);
+
+        assert_eq!(raw.len(), normalized.len());
+        for score in &raw {
+            let other = normalized.iter().find(|s| s.path == score.path).unwrap();
+            assert_eq!(other.partner_count, score.partner_count);
+            assert_eq!(other.hub_score, score.hub_score);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use crate::storage::{Edge, Issue};
+
+// Upper-case tokens that look like JIRA keys but are versions or standards (UTF-8, SHA-256)
+const NOT_PROJECT_KEYS: &[&str] = &["UTF", "SHA", "ISO", "RFC", "CVE", "MD", "X", "ECMA"];
+
+/// Issue-tracker references in a commit message, in first-seen order without duplicates.
+/// GitHub references (`#123`, `Fixes #45`, `GH-45`) become `#<number>` and JIRA keys
+/// (`PROJ-123`) are kept as written.
+pub fn extract_issue_refs(message: &str) -> Vec<String> {
+    let chars: Vec<char> = message.chars().collect();
+    let mut refs: Vec<String> = Vec::new();
+    let mut push = |key: String| {
+        if !refs.contains(&key) {
+            refs.push(key);
+        }
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        // References must start a word; `&#123;` is an HTML entity, not an issue
+        let at_word_start = i == 0 || !(chars[i - 1].is_alphanumeric() || chars[i - 1] == '&');
+
+        if at_word_start && chars[i] == '#' {
+            let digits = count_while(&chars[i + 1..], |c| c.is_ascii_digit());
+            if digits > 0 && ends_word(&chars, i + 1 + digits) {
+                push(format!("#{}", collect(&chars[i + 1..i + 1 + digits])));
+                i += 1 + digits;
+                continue;
+            }
+        }
+
+        if at_word_start && chars[i].is_ascii_uppercase() {
+            let project = count_while(&chars[i..], |c| {
+                c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'
+            });
+            let dash = i + project;
+            if dash < chars.len() && chars[dash] == '-' {
+                let digits = count_while(&chars[dash + 1..], |c| c.is_ascii_digit());
+                let project_key = collect(&chars[i..dash]);
+                if digits > 0 && ends_word(&chars, dash + 1 + digits) {
+                    let number = collect(&chars[dash + 1..dash + 1 + digits]);
+                    if project_key == "GH" {
+                        push(format!("#{}", number));
+                    } else if project > 1 && !NOT_PROJECT_KEYS.contains(&project_key.as_str()) {
+                        push(format!("{}-{}", project_key, number));
+                    }
+                    i = dash + 1 + digits;
+                    continue;
+                }
+            }
+            i += project.max(1);
+            continue;
+        }
+
+        i += 1;
+    }
+
+    refs
+}
+
+fn count_while(chars: &[char], predicate: impl Fn(char) -> bool) -> usize {
+    chars.iter().take_while(|c| predicate(**c)).count()
+}
+
+fn ends_word(chars: &[char], end: usize) -> bool {
+    end >= chars.len() || !(chars[end].is_alphanumeric() || chars[end] == '_')
+}
+
+fn collect(chars: &[char]) -> String {
+    chars.iter().collect()
+}
+
+/// Co-change with every issue's commits merged into one logical change set: two files are
+/// linked once per issue whose commits touched both. Edges are undirected with
+/// `source < target`.
+pub fn issue_cochanges(issues: &[Issue]) -> Vec<Edge> {
+    let mut weights: HashMap<(&str, &str), i64> = HashMap::new();
+    for issue in issues {
+        let mut paths: Vec<&str> = issue.files.iter().map(|f| f.path.as_str()).collect();
+        paths.sort();
+        paths.dedup();
+        for i in 0..paths.len() {
+            for j in (i + 1)..paths.len() {
+                *weights.entry((paths[i], paths[j])).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut edges: Vec<Edge> = weights
+        .into_iter()
+        .map(|((source, target), weight)| Edge {
+            source: source.to_string(),
+            target: target.to_string(),
+            weight,
+            decayed_weight: None,
+        })
+        .collect();
+    edges.sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::IssueFile;
+
+    #[test]
+    fn extracts_github_references() {
+        assert_eq!(
+            extract_issue_refs("Fixes #123, see GH-45 and #123 again"),
+            vec!["#123", "#45"]
+        );
+        assert_eq!(extract_issue_refs("(#7)"), vec!["#7"]);
+    }
+
+    #[test]
+    fn ignores_hashes_that_are_not_references() {
+        assert!(extract_issue_refs("escape &#123; and foo#12 and #12a and #").is_empty());
+        assert!(extract_issue_refs("See https://github.com/o/r/issues/123").is_empty());
+        assert!(extract_issue_refs("https://github.com/o/r/pull/9#issuecomment-456").is_empty());
+    }
+
+    #[test]
+    fn extracts_jira_keys_but_not_standards() {
+        assert_eq!(
+            extract_issue_refs("PROJ-12: convert to UTF-8, hash with SHA-256 (ACTS_2-3)"),
+            vec!["PROJ-12", "ACTS_2-3"]
+        );
+        assert!(extract_issue_refs("A-1 and XPROJ-12b and ISO-8601").is_empty());
+    }
+
+    #[test]
+    fn links_files_once_per_issue() {
+        let issue = |key: &str, paths: &[&str]| Issue {
+            key: key.to_string(),
+            commits: vec![],
+            files: paths
+                .iter()
+                .map(|path| IssueFile {
+                    path: path.to_string(),
+                    commits: 1,
+                })
+                .collect(),
+        };
+        let edges = issue_cochanges(&[
+            issue("#1", &["b.cpp", "a.cpp", "a.cpp"]),
+            issue("#2", &["a.cpp", "b.cpp", "c.cpp"]),
+        ]);
+
+        let pairs: Vec<(&str, &str, i64)> = edges
+            .iter()
+            .map(|e| (e.source.as_str(), e.target.as_str(), e.weight))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("a.cpp", "b.cpp", 2),
+                ("a.cpp", "c.cpp", 1),
+                ("b.cpp", "c.cpp", 1)
+            ]
+        );
+    }
+}
@@ -1,11 +1,27 @@
 pub mod entrypoint;
 pub mod file_graph;
 pub mod file_metrics_analyser;
+pub mod function_spans;
 pub mod git_analyzer;
+pub mod change_set;
+pub mod clone_cache;
 pub mod codescene_client;
+pub mod collaboration;
+pub mod commit_class;
+pub mod commit_filter;
+pub mod config;
+pub mod credentials;
+pub mod hub_score;
+pub mod issue_refs;
+pub mod manifest;
+pub mod ownership;
+pub mod path_filter;
 pub mod storage;
+pub mod szz;
+pub mod timeline;
 
 pub use file_graph::{ChangedFile, Edge, FileGraphBuilder, FileNode};
-pub use git_analyzer::GitAnalyzer;
+pub use git_analyzer::{AnalysisWindow, GitAnalyzer, IngestMode, MergeStrategy};
+pub use path_filter::PathFilter;
 pub use codescene_client::{label_from_code_health, CodeSceneClient};
-pub use storage::{GraphData, HubScoreData, Neo4jClient};
\ No newline at end of file
+pub use storage::{open_store, GraphData, GraphStore, HubScoreData, MemoryStore, Neo4jClient, SqliteStore};
\ No newline at end of file
@@ -0,0 +1,166 @@
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Pipeline stages in the order a repository passes through them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Stage {
+    Queued,
+    Cloned,
+    Analysed,
+    HubScored,
+    Exported,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Stage::Queued => "queued",
+            Stage::Cloned => "cloned",
+            Stage::Analysed => "analysed",
+            Stage::HubScored => "hub-scored",
+            Stage::Exported => "exported",
+        };
+        f.pad(name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoState {
+    pub url: String,
+    /// The last stage the repository completed
+    pub stage: Stage,
+    /// Why the stage after `stage` failed on the last attempt
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub failures: u32,
+    pub updated_at: String,
+}
+
+/// The state of every repository of a corpus run, kept in a JSON file that is rewritten
+/// after each change, so an interrupted run can resume from the last completed stage.
+pub struct Manifest {
+    path: PathBuf,
+    repos: Mutex<BTreeMap<String, RepoState>>,
+}
+
+impl Manifest {
+    /// Reads the manifest at `path`, or starts an empty one if it does not exist yet.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let repos = if Path::new(path).exists() {
+            let content = fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read manifest {}: {}", path, e))?;
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse manifest {}: {}", path, e))?
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(Self {
+            path: PathBuf::from(path),
+            repos: Mutex::new(repos),
+        })
+    }
+
+    /// Starts an empty manifest at `path`, replacing any existing one on the first write.
+    pub fn fresh(path: &str) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            repos: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn state(&self, repo: &str) -> Option<RepoState> {
+        self.repos.lock().unwrap().get(repo).cloned()
+    }
+
+    pub fn repos(&self) -> BTreeMap<String, RepoState> {
+        self.repos.lock().unwrap().clone()
+    }
+
+    /// Adds the repositories not in the manifest yet as queued.
+    pub fn register(&self, repos: &[(String, String)]) -> Result<(), String> {
+        let mut states = self.repos.lock().unwrap();
+        for (repo, url) in repos {
+            states.entry(repo.clone()).or_insert_with(|| RepoState {
+                url: url.clone(),
+                stage: Stage::Queued,
+                error: None,
+                failures: 0,
+                updated_at: now(),
+            });
+        }
+        self.save(&states)
+    }
+
+    /// Records that `repo` completed `stage`, clearing an earlier failure.
+    pub fn advance(&self, repo: &str, url: &str, stage: Stage) -> Result<(), String> {
+        let mut states = self.repos.lock().unwrap();
+        let state = states.entry(repo.to_string()).or_insert_with(|| RepoState {
+            url: url.to_string(),
+            stage,
+            error: None,
+            failures: 0,
+            updated_at: now(),
+        });
+        state.stage = stage;
+        state.error = None;
+        state.updated_at = now();
+        self.save(&states)
+    }
+
+    /// Records that the stage after the last completed one failed for `repo`.
+    pub fn fail(&self, repo: &str, url: &str, error: &str) -> Result<(), String> {
+        let mut states = self.repos.lock().unwrap();
+        let state = states.entry(repo.to_string()).or_insert_with(|| RepoState {
+            url: url.to_string(),
+            stage: Stage::Queued,
+            error: None,
+            failures: 0,
+            updated_at: now(),
+        });
+        state.error = Some(error.to_string());
+        state.failures += 1;
+        state.updated_at = now();
+        self.save(&states)
+    }
+
+    /// Marks the listed repositories that have hub scores as exported, returning how many
+    /// were marked.
+    pub fn mark_exported(&self, repos: &HashSet<String>) -> Result<usize, String> {
+        let mut states = self.repos.lock().unwrap();
+        let mut updated = 0;
+        for (repo, state) in states.iter_mut() {
+            if repos.contains(repo) && state.stage >= Stage::HubScored {
+                state.stage = Stage::Exported;
+                state.error = None;
+                state.updated_at = now();
+                updated += 1;
+            }
+        }
+        self.save(&states)?;
+        Ok(updated)
+    }
+
+    // Written to a temporary file first, so a run killed mid-write keeps the old manifest
+    fn save(&self, states: &BTreeMap<String, RepoState>) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(states)
+            .map_err(|e| format!("Failed to serialise manifest: {}", e))?;
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, json)
+            .map_err(|e| format!("Failed to write manifest {}: {}", tmp.display(), e))?;
+        fs::rename(&tmp, &self.path)
+            .map_err(|e| format!("Failed to write manifest {}: {}", self.path.display(), e))
+    }
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
@@ -0,0 +1,47 @@
+use crate::storage::GraphData;
+
+#[derive(Debug, Clone)]
+pub struct Ownership {
+    pub path: String,
+    pub author_count: i64,
+    pub main_author_share: f64,
+    pub bus_factor: i64,
+}
+
+/// Ownership metrics from the commits each author made to a file. `main_author_share` is
+/// the top author's fraction of those commits and `bus_factor` the smallest number of
+/// authors that together made more than half of them. Files without recorded authors are
+/// skipped.
+pub fn compute_ownership(graph: &GraphData) -> Vec<Ownership> {
+    graph
+        .files
+        .iter()
+        .filter(|file| !file.authors.is_empty())
+        .map(|file| {
+            let mut commits: Vec<i64> = file.authors.iter().map(|a| a.commits).collect();
+            commits.sort_unstable_by(|a, b| b.cmp(a));
+            let total: i64 = commits.iter().sum();
+
+            let mut covered = 0;
+            let mut bus_factor = 0;
+            for count in &commits {
+                covered += count;
+                bus_factor += 1;
+                if covered * 2 > total {
+                    break;
+                }
+            }
+
+            Ownership {
+                path: file.path.clone(),
+                author_count: commits.len() as i64,
+                main_author_share: if total > 0 {
+                    commits[0] as f64 / total as f64
+                } else {
+                    0.0
+                },
+                bus_factor,
+            }
+        })
+        .collect()
+}
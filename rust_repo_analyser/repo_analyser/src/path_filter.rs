@@ -0,0 +1,287 @@
+// Directory names that hold code copied from other projects
+const VENDORED_DIRS: &[&str] = &[
+    "vendor",
+    "vendored",
+    "third_party",
+    "third-party",
+    "thirdparty",
+    "3rdparty",
+    "3rd_party",
+    "3rd-party",
+    "external",
+    "externals",
+    "extern",
+];
+
+// Libraries that C++ projects commonly bundle as a source copy
+const BUNDLED_LIBRARIES: &[&str] = &[
+    "eigen",
+    "eigen3",
+    "boost",
+    "googletest",
+    "gtest",
+    "gmock",
+    "catch2",
+    "pybind11",
+    "rapidjson",
+    "nlohmann",
+];
+
+const GENERATED_DIRS: &[&str] = &["generated", "autogen"];
+
+// File name patterns of protobuf, gRPC, flatbuffers and Qt output
+const GENERATED_FILES: &[&str] = &[
+    "*.pb.h",
+    "*.pb.cc",
+    "*.pb.cpp",
+    "*_pb2.py",
+    "*_generated.h",
+    "*.generated.*",
+    "moc_*.cpp",
+    "ui_*.h",
+    "qrc_*.cpp",
+];
+
+/// A gitignore-style pattern. Patterns without a slash match a file or directory name at
+/// any depth, other patterns are matched from the repository root; `*` and `?` stay within
+/// one path component, `**` spans any number of them and a trailing `/` only matches
+/// directories. A pattern matching a directory matches everything below it.
+#[derive(Debug, Clone)]
+pub struct Glob {
+    pattern: String,
+    segments: Vec<String>,
+    anchored: bool,
+    dir_only: bool,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
+        let trimmed = pattern.trim();
+        let dir_only = trimmed.ends_with('/');
+        let body = trimmed.trim_end_matches('/');
+        let anchored = body.contains('/');
+        let segments = body
+            .trim_start_matches('/')
+            .split('/')
+            .map(|s| s.to_string())
+            .collect();
+
+        Self {
+            pattern: trimmed.to_string(),
+            segments,
+            anchored,
+            dir_only,
+        }
+    }
+
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        let parts: Vec<&str> = path.split('/').collect();
+        (1..=parts.len()).any(|end| {
+            if end == parts.len() && self.dir_only {
+                return false;
+            }
+            if self.anchored {
+                match_segments(&self.segments, &parts[..end])
+            } else {
+                wildcard_match(&self.segments[0], parts[end - 1])
+            }
+        })
+    }
+}
+
+fn match_segments(pattern: &[String], parts: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => parts.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=parts.len()).any(|skip| match_segments(rest, &parts[skip..]))
+        }
+        Some((first, rest)) => {
+            !parts.is_empty()
+                && wildcard_match(first, parts[0])
+                && match_segments(rest, &parts[1..])
+        }
+    }
+}
+
+// Wildcard match of a single path component or name, backtracking to the last `*`
+pub(crate) fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Decides which files with an analysed extension are left out, and why: files matching
+/// an exclude pattern, files outside the include patterns (when there are any) and, unless
+/// turned off, vendored, bundled or generated code recognised by its path.
+#[derive(Debug, Clone)]
+pub struct PathFilter {
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    detect_vendored: bool,
+}
+
+impl Default for PathFilter {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            detect_vendored: true,
+        }
+    }
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String], detect_vendored: bool) -> Self {
+        Self {
+            include: include.iter().map(|p| Glob::new(p)).collect(),
+            exclude: exclude.iter().map(|p| Glob::new(p)).collect(),
+            detect_vendored,
+        }
+    }
+
+    /// The reason `path` is excluded from the analysis, or `None` to analyse it.
+    pub fn exclusion(&self, path: &str) -> Option<String> {
+        if let Some(glob) = self.exclude.iter().find(|glob| glob.matches(path)) {
+            return Some(format!("exclude pattern {}", glob.pattern()));
+        }
+        if !self.include.is_empty() && !self.include.iter().any(|glob| glob.matches(path)) {
+            return Some("not matched by include patterns".to_string());
+        }
+        if self.detect_vendored {
+            return detect_vendored(path);
+        }
+        None
+    }
+}
+
+fn detect_vendored(path: &str) -> Option<String> {
+    let parts: Vec<&str> = path.split('/').collect();
+    let (file_name, dirs) = parts.split_last()?;
+
+    for (i, dir) in dirs.iter().enumerate() {
+        let lowered = dir.to_lowercase();
+        let prefix = parts[..=i].join("/");
+        if VENDORED_DIRS.contains(&lowered.as_str()) {
+            return Some(format!("vendored directory {}/", prefix));
+        }
+        if BUNDLED_LIBRARIES.contains(&lowered.as_str()) {
+            return Some(format!("bundled library {}/", prefix));
+        }
+        if GENERATED_DIRS.contains(&lowered.as_str()) {
+            return Some(format!("generated directory {}/", prefix));
+        }
+    }
+
+    GENERATED_FILES
+        .iter()
+        .find(|pattern| wildcard_match(pattern, file_name))
+        .map(|pattern| format!("generated file {}", pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str], detect_vendored: bool) -> PathFilter {
+        let strings =
+            |patterns: &[&str]| -> Vec<String> { patterns.iter().map(|p| p.to_string()).collect() };
+        PathFilter::new(&strings(include), &strings(exclude), detect_vendored)
+    }
+
+    #[test]
+    fn unanchored_globs_match_names_at_any_depth() {
+        let glob = Glob::new("*_test.cpp");
+        assert!(glob.matches("a_test.cpp"));
+        assert!(glob.matches("src/deep/a_test.cpp"));
+        assert!(!glob.matches("src/a_test.cpp.orig"));
+
+        let dir = Glob::new("tests/");
+        assert!(dir.matches("tests/a.cpp"));
+        assert!(dir.matches("src/tests/a.cpp"));
+        assert!(!dir.matches("tests"));
+    }
+
+    #[test]
+    fn anchored_globs_match_from_the_root() {
+        let glob = Glob::new("src/*.cpp");
+        assert!(glob.matches("src/a.cpp"));
+        assert!(!glob.matches("src/sub/a.cpp"));
+        assert!(!glob.matches("lib/src/a.cpp"));
+
+        let recursive = Glob::new("src/**/core");
+        assert!(recursive.matches("src/core/a.cpp"));
+        assert!(recursive.matches("src/a/b/core/a.cpp"));
+        assert!(!recursive.matches("lib/core/a.cpp"));
+    }
+
+    #[test]
+    fn exclude_wins_over_include() {
+        let filter = filter(&["src/**"], &["*_test.cpp"], true);
+        assert_eq!(filter.exclusion("src/a.cpp"), None);
+        assert_eq!(
+            filter.exclusion("src/a_test.cpp").as_deref(),
+            Some("exclude pattern *_test.cpp")
+        );
+        assert_eq!(
+            filter.exclusion("tools/a.cpp").as_deref(),
+            Some("not matched by include patterns")
+        );
+    }
+
+    #[test]
+    fn include_does_not_override_vendored_detection() {
+        let filter = filter(&["src/**"], &[], true);
+        assert_eq!(
+            filter.exclusion("src/third_party/x.cpp").as_deref(),
+            Some("vendored directory src/third_party/")
+        );
+    }
+
+    #[test]
+    fn detects_vendored_and_generated_code() {
+        let filter = filter(&[], &[], true);
+        assert_eq!(
+            filter.exclusion("External/Eigen/core.h").as_deref(),
+            Some("vendored directory External/")
+        );
+        assert_eq!(
+            filter.exclusion("lib/boost/any.hpp").as_deref(),
+            Some("bundled library lib/boost/")
+        );
+        assert_eq!(
+            filter.exclusion("proto/msg.pb.cc").as_deref(),
+            Some("generated file *.pb.cc")
+        );
+        assert_eq!(filter.exclusion("src/vendor.cpp"), None);
+    }
+
+    #[test]
+    fn keep_vendored_turns_detection_off() {
+        let filter = filter(&[], &[], false);
+        assert_eq!(filter.exclusion("third_party/x.cpp"), None);
+        assert_eq!(filter.exclusion("moc_window.cpp"), None);
+    }
+}
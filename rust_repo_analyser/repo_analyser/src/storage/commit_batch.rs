@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+
+use crate::commit_class::ClassCounts;
+use crate::file_graph::{CommitChanges, FileGraph};
+
+#[derive(Debug, Clone)]
+pub struct FileRow {
+    pub path: String,
+    pub additions: i64,
+    pub deletions: i64,
+    pub normalized_additions: i64,
+    pub normalized_deletions: i64,
+    pub commit_count: i64,
+    pub deleted_at_commit: Option<String>,
+    pub decayed_churn: Option<f64>,
+    pub decayed_commit_count: Option<f64>,
+    pub commit_classes: ClassCounts,
+}
+
+#[derive(Debug, Clone)]
+pub struct CochangeRow {
+    pub source: String,
+    pub target: String,
+    pub weight: i64,
+    pub decayed_weight: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthorRow {
+    pub email: String,
+    pub name: String,
+    pub commit_count: i64,
+    pub committed_count: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileAuthorRow {
+    pub path: String,
+    pub email: String,
+    pub commits: i64,
+    pub churn: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct IssueCommitRow {
+    pub key: String,
+    pub commit_hash: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct IssueFileRow {
+    pub key: String,
+    pub path: String,
+    pub commits: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionRow {
+    pub path: String,
+    pub name: String,
+    pub commit_count: i64,
+    pub churn: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionCochangeRow {
+    pub source_path: String,
+    pub source_name: String,
+    pub target_path: String,
+    pub target_name: String,
+    pub weight: i64,
+}
+
+/// A run of consecutive commits that can be written with one set-based query per row
+/// type. Renames change which node a path refers to, so every commit with renames starts
+/// a new segment; its renames run first, then the aggregated file and co-change rows.
+#[derive(Debug, Default)]
+pub struct BatchSegment {
+    pub renames: Vec<(String, String)>,
+    pub files: Vec<FileRow>,
+    pub cochanges: Vec<CochangeRow>,
+    pub authors: Vec<AuthorRow>,
+    pub file_authors: Vec<FileAuthorRow>,
+    pub issue_commits: Vec<IssueCommitRow>,
+    pub issue_files: Vec<IssueFileRow>,
+    pub functions: Vec<FunctionRow>,
+    pub function_cochanges: Vec<FunctionCochangeRow>,
+}
+
+type FunctionKey = (String, String);
+
+#[derive(Default)]
+struct SegmentBuilder {
+    renames: Vec<(String, String)>,
+    files: HashMap<String, FileRow>,
+    file_order: Vec<String>,
+    cochanges: HashMap<(String, String), (i64, Option<f64>)>,
+    authors: HashMap<String, AuthorRow>,
+    file_authors: HashMap<(String, String), (i64, i64)>,
+    issue_commits: Vec<IssueCommitRow>,
+    issue_files: HashMap<(String, String), i64>,
+    functions: HashMap<FunctionKey, (i64, i64)>,
+    function_cochanges: HashMap<(FunctionKey, FunctionKey), i64>,
+}
+
+impl SegmentBuilder {
+    fn is_empty(&self) -> bool {
+        self.renames.is_empty()
+            && self.files.is_empty()
+            && self.cochanges.is_empty()
+            && self.authors.is_empty()
+            && self.issue_commits.is_empty()
+    }
+
+    fn add(&mut self, commit: &CommitChanges) {
+        for (identity, authored, committed) in [(&commit.author, 1, 0), (&commit.committer, 0, 1)] {
+            let key = identity.key();
+            let row = self
+                .authors
+                .entry(key.clone())
+                .or_insert_with(|| AuthorRow {
+                    email: key,
+                    name: String::new(),
+                    commit_count: 0,
+                    committed_count: 0,
+                });
+            row.name = identity.name.clone();
+            row.commit_count += authored;
+            row.committed_count += committed;
+        }
+        let author = commit.author.key();
+
+        for key in &commit.issues {
+            self.issue_commits.push(IssueCommitRow {
+                key: key.clone(),
+                commit_hash: commit.commit_hash.clone(),
+            });
+            for path in commit.issue_files() {
+                *self
+                    .issue_files
+                    .entry((key.clone(), path.to_string()))
+                    .or_insert(0) += 1;
+            }
+        }
+
+        for file in &commit.files {
+            let deleted_at_commit = commit.deleted_at_commit(file);
+            let row = self.files.entry(file.path.clone()).or_insert_with(|| {
+                self.file_order.push(file.path.clone());
+                FileRow {
+                    path: file.path.clone(),
+                    additions: 0,
+                    deletions: 0,
+                    normalized_additions: 0,
+                    normalized_deletions: 0,
+                    commit_count: 0,
+                    deleted_at_commit: None,
+                    decayed_churn: None,
+                    decayed_commit_count: None,
+                    commit_classes: ClassCounts::default(),
+                }
+            });
+            row.additions += file.additions as i64;
+            row.deletions += file.deletions as i64;
+            row.normalized_additions += file.normalized_additions as i64;
+            row.normalized_deletions += file.normalized_deletions as i64;
+            row.commit_count += 1;
+            row.commit_classes.add(commit.class);
+            if row.deleted_at_commit.is_none() {
+                row.deleted_at_commit = deleted_at_commit.map(|s| s.to_string());
+            }
+            if let Some(decay) = commit.decay {
+                *row.decayed_churn.get_or_insert(0.0) +=
+                    decay * (file.additions + file.deletions) as f64;
+                *row.decayed_commit_count.get_or_insert(0.0) += decay;
+            }
+
+            let (commits, churn) = self
+                .file_authors
+                .entry((file.path.clone(), author.clone()))
+                .or_insert((0, 0));
+            *commits += 1;
+            *churn += (file.additions + file.deletions) as i64;
+        }
+
+        for function in &commit.functions {
+            let (commit_count, churn) = self
+                .functions
+                .entry((function.path.clone(), function.name.clone()))
+                .or_insert((0, 0));
+            *commit_count += 1;
+            *churn += function.churn as i64;
+        }
+        for (source, target) in commit.function_pairs() {
+            *self
+                .function_cochanges
+                .entry((
+                    (source.path.clone(), source.name.clone()),
+                    (target.path.clone(), target.name.clone()),
+                ))
+                .or_insert(0) += 1;
+        }
+
+        for (source, target) in commit.cochange_pairs() {
+            let (weight, decayed_weight) = self
+                .cochanges
+                .entry((source.to_string(), target.to_string()))
+                .or_insert((0, None));
+            *weight += 1;
+            if let Some(decay) = commit.decay {
+                *decayed_weight.get_or_insert(0.0) += decay;
+            }
+        }
+    }
+
+    fn build(mut self) -> BatchSegment {
+        let files = self
+            .file_order
+            .iter()
+            .filter_map(|path| self.files.remove(path))
+            .collect();
+        let cochanges = self
+            .cochanges
+            .into_iter()
+            .map(|((source, target), (weight, decayed_weight))| CochangeRow {
+                source,
+                target,
+                weight,
+                decayed_weight,
+            })
+            .collect();
+
+        let authors = self.authors.into_values().collect();
+        let file_authors = self
+            .file_authors
+            .into_iter()
+            .map(|((path, email), (commits, churn))| FileAuthorRow {
+                path,
+                email,
+                commits,
+                churn,
+            })
+            .collect();
+
+        let issue_files = self
+            .issue_files
+            .into_iter()
+            .map(|((key, path), commits)| IssueFileRow { key, path, commits })
+            .collect();
+
+        let functions = self
+            .functions
+            .into_iter()
+            .map(|((path, name), (commit_count, churn))| FunctionRow {
+                path,
+                name,
+                commit_count,
+                churn,
+            })
+            .collect();
+        let function_cochanges = self
+            .function_cochanges
+            .into_iter()
+            .map(|((source, target), weight)| FunctionCochangeRow {
+                source_path: source.0,
+                source_name: source.1,
+                target_path: target.0,
+                target_name: target.1,
+                weight,
+            })
+            .collect();
+
+        BatchSegment {
+            renames: self.renames,
+            files,
+            cochanges,
+            authors,
+            file_authors,
+            issue_commits: self.issue_commits,
+            issue_files,
+            functions,
+            function_cochanges,
+        }
+    }
+}
+
+pub fn segment_batch(batch: &[CommitChanges]) -> Vec<BatchSegment> {
+    let mut segments = Vec::new();
+    let mut current = SegmentBuilder::default();
+
+    for commit in batch {
+        if !commit.renames.is_empty() {
+            if !current.is_empty() {
+                segments.push(std::mem::take(&mut current).build());
+            }
+            current.renames = commit.renames.clone();
+        }
+        current.add(commit);
+    }
+
+    if !current.is_empty() {
+        segments.push(current.build());
+    }
+
+    segments
+}
+
+/// Flattens a finalized graph into one rename-free segment. Each undirected co-change edge
+/// appears once, with `source < target`.
+pub fn graph_segment(graph: &FileGraph) -> BatchSegment {
+    let mut segment = BatchSegment::default();
+
+    for node in &graph.node_map {
+        segment.files.push(FileRow {
+            path: node.path.clone(),
+            additions: node.additions as i64,
+            deletions: node.deletions as i64,
+            normalized_additions: node.normalized_additions as i64,
+            normalized_deletions: node.normalized_deletions as i64,
+            commit_count: node.commit_count as i64,
+            deleted_at_commit: node.deleted_at_commit.clone(),
+            decayed_churn: node.decayed_churn,
+            decayed_commit_count: node.decayed_commit_count,
+            commit_classes: node.commit_classes,
+        });
+
+        for edge in &node.edges {
+            if node.path < edge.target {
+                segment.cochanges.push(CochangeRow {
+                    source: node.path.clone(),
+                    target: edge.target.clone(),
+                    weight: edge.weight as i64,
+                    decayed_weight: edge.decayed_weight,
+                });
+            }
+        }
+
+        for author in &node.authors {
+            segment.file_authors.push(FileAuthorRow {
+                path: node.path.clone(),
+                email: author.email.clone(),
+                commits: author.commits as i64,
+                churn: author.churn as i64,
+            });
+        }
+    }
+
+    for issue in &graph.issues {
+        for commit_hash in &issue.commits {
+            segment.issue_commits.push(IssueCommitRow {
+                key: issue.key.clone(),
+                commit_hash: commit_hash.clone(),
+            });
+        }
+        for file in &issue.files {
+            segment.issue_files.push(IssueFileRow {
+                key: issue.key.clone(),
+                path: file.path.clone(),
+                commits: file.commits as i64,
+            });
+        }
+    }
+
+    for function in &graph.functions {
+        segment.functions.push(FunctionRow {
+            path: function.path.clone(),
+            name: function.name.clone(),
+            commit_count: function.commit_count as i64,
+            churn: function.churn as i64,
+        });
+    }
+    for edge in &graph.function_edges {
+        segment.function_cochanges.push(FunctionCochangeRow {
+            source_path: edge.source_path.clone(),
+            source_name: edge.source_name.clone(),
+            target_path: edge.target_path.clone(),
+            target_name: edge.target_name.clone(),
+            weight: edge.weight as i64,
+        });
+    }
+
+    for author in &graph.authors {
+        segment.authors.push(AuthorRow {
+            email: author.email.clone(),
+            name: author.name.clone(),
+            commit_count: author.commit_count as i64,
+            committed_count: author.committed_count as i64,
+        });
+    }
+
+    segment
+}
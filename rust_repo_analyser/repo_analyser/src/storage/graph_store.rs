@@ -0,0 +1,164 @@
+use async_trait::async_trait;
+use log::warn;
+
+use super::{
+    AnalysisRun, Collaboration, ExcludedFile, FunctionHubScoreData, GraphData, HubScoreData,
+    RepoWithFiles,
+};
+use crate::file_graph::{CommitChanges, FileGraph};
+
+/// Where the last completed analysis of a repository stopped, used to resume the
+/// history walk in incremental mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalysisCheckpoint {
+    pub last_commit: String,
+    pub total_commits: i64,
+}
+
+/// Storage backend for the co-change graph. Every analysis, scoring and export step only
+/// talks to this trait so the pipeline can run against Neo4j or entirely in memory.
+#[async_trait]
+pub trait GraphStore: Send + Sync {
+    async fn init_schema(&self) -> Result<(), String>;
+
+    /// Wipes every repository in the store. Prefer `delete_repository` when other
+    /// repositories share the store.
+    async fn delete_all_nodes(&self) -> Result<(), String>;
+
+    /// Removes one repository together with its files, authors, co-change edges and links.
+    async fn delete_repository(&self, repo: &str) -> Result<(), String>;
+
+    async fn save_repository(&self, name: &str, url: Option<&str>) -> Result<(), String>;
+
+    async fn update_commit_count(&self, repo: &str, total_commits: i64) -> Result<(), String>;
+
+    async fn get_checkpoint(&self, repo: &str) -> Result<Option<AnalysisCheckpoint>, String>;
+
+    async fn set_last_commit(&self, repo: &str, commit: &str) -> Result<(), String>;
+
+    /// Records which slice of history the repository's graph describes.
+    async fn set_analysis_window(&self, repo: &str, window: &str) -> Result<(), String>;
+
+    /// Per-row write used by the default `save_commit_batch`, which has no normalized churn;
+    /// the raw counts are recorded as normalized churn too.
+    async fn save_file_node(
+        &self,
+        repo: &str,
+        path: &str,
+        additions: i64,
+        deletions: i64,
+        deleted_at_commit: Option<&str>,
+    ) -> Result<(), String>;
+
+    async fn rename_file_node(
+        &self,
+        repo: &str,
+        old_path: &str,
+        new_path: &str,
+    ) -> Result<(), String>;
+
+    async fn save_cochange_relationship(
+        &self,
+        repo: &str,
+        source: &str,
+        target: &str,
+    ) -> Result<(), String>;
+
+    /// Writes a buffered run of commits. The default replays them through the per-row
+    /// methods; backends with set-based writes override it.
+    async fn save_commit_batch(&self, repo: &str, batch: &[CommitChanges]) -> Result<(), String> {
+        for commit in batch {
+            for (old_path, new_path) in &commit.renames {
+                if let Err(e) = self.rename_file_node(repo, old_path, new_path).await {
+                    warn!(
+                        "Failed to rename file node {} -> {}: {}",
+                        old_path, new_path, e
+                    );
+                }
+            }
+
+            for file in &commit.files {
+                self.save_file_node(
+                    repo,
+                    &file.path,
+                    file.additions as i64,
+                    file.deletions as i64,
+                    commit.deleted_at_commit(file),
+                )
+                .await?;
+            }
+
+            for (source, target) in commit.cochange_pairs() {
+                self.save_cochange_relationship(repo, source, target)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes a graph built entirely in memory in one pass. Files and edges are merged into
+    /// whatever the repository already holds, as if the commits had been streamed.
+    async fn bulk_load_graph(&self, graph: &FileGraph) -> Result<(), String>;
+
+    /// Multiplies the repository's decayed churn, commit counts and co-change weights by
+    /// `factor`, moving their decay reference time forward.
+    async fn rescale_decay(&self, repo: &str, factor: f64) -> Result<(), String>;
+
+    /// Adds author collaboration weights to the repository's network. Both authors must
+    /// already be stored.
+    async fn save_collaborations(
+        &self,
+        repo: &str,
+        collaborations: &[Collaboration],
+    ) -> Result<(), String>;
+
+    /// Records files left out of the analysis by the path filter and why. A later record
+    /// for the same path replaces the reason.
+    async fn save_excluded_files(&self, repo: &str, files: &[ExcludedFile]) -> Result<(), String>;
+
+    /// Records the configuration an analysis of the repository ran with. Runs accumulate
+    /// until the repository is deleted, so incremental runs keep the earlier ones.
+    async fn save_analysis_run(&self, repo: &str, run: &AnalysisRun) -> Result<(), String>;
+
+    async fn get_graph(&self, repo: &str) -> Result<GraphData, String>;
+
+    async fn link_file_to_repo(&self, repo: &str, file_path: &str) -> Result<(), String>;
+
+    async fn link_all_files_to_repo(&self, repo: &str) -> Result<(), String>;
+
+    /// Drops the repository's co-change edges with a weight below `threshold` and returns
+    /// how many were removed.
+    async fn remove_low_importance_connections(
+        &self,
+        repo: &str,
+        threshold: i64,
+    ) -> Result<u64, String>;
+
+    /// Removes the repository's files that have no co-change partner left, plus live files
+    /// without churn. Returns the number of files removed.
+    async fn remove_orphan_files(&self, repo: &str) -> Result<u64, String>;
+
+    /// Computes and stores hub scores, decayed hub scores for files recorded with time
+    /// decay, per-file ownership metrics and function-level hub scores.
+    async fn compute_hub_scores(&self, repo: &str, min_coupling: f64) -> Result<(), String>;
+
+    async fn get_all_repo_names(&self) -> Result<Vec<String>, String>;
+
+    async fn get_all_hub_scores(&self, extension: &str) -> Result<Vec<HubScoreData>, String>;
+
+    /// Hub scores of the functions in live files ending with `extension`, highest first.
+    async fn get_all_function_hub_scores(
+        &self,
+        extension: &str,
+    ) -> Result<Vec<FunctionHubScoreData>, String>;
+
+    /// Live files ending with `extension` grouped by repository, limited to `repo` if given.
+    async fn get_files_by_hub_threshold(
+        &self,
+        repo: Option<&str>,
+        hub_threshold: f64,
+        extension: &str,
+        ignore_repos: &[String],
+    ) -> Result<Vec<RepoWithFiles>, String>;
+}
@@ -0,0 +1,668 @@
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tokio::sync::Mutex;
+
+use super::{
+    AnalysisCheckpoint, AnalysisRun, Author, Collaboration, Edge, ExcludedFile, FileAuthor,
+    FileNode, Function, FunctionEdge, FunctionHubScoreData, GraphData, GraphStore, HubScoreData,
+    Issue, IssueFile, RepoFile, RepoWithFiles,
+};
+use crate::file_graph::{CommitChanges, FileGraph, FileGraphBuilder};
+use crate::hub_score::{self, FunctionHubScore, HubScore};
+use crate::issue_refs::issue_cochanges;
+use crate::ownership::{self, Ownership};
+
+struct RepoState {
+    url: Option<String>,
+    total_commits: i64,
+    last_commit: Option<String>,
+    window: Option<String>,
+    builder: FileGraphBuilder,
+    hub_scores: HashMap<String, HubScore>,
+    normalized_hub_scores: HashMap<String, HubScore>,
+    decayed_hub_scores: HashMap<String, HubScore>,
+    ownership: HashMap<String, Ownership>,
+    function_hub_scores: HashMap<(String, String), FunctionHubScore>,
+    collaborations: HashMap<(String, String), i64>,
+    excluded_files: BTreeMap<String, String>,
+    analysis_runs: Vec<AnalysisRun>,
+}
+
+impl RepoState {
+    fn new(name: &str) -> Self {
+        Self {
+            url: None,
+            total_commits: 0,
+            last_commit: None,
+            window: None,
+            builder: FileGraphBuilder::new(name.to_string()),
+            hub_scores: HashMap::new(),
+            normalized_hub_scores: HashMap::new(),
+            decayed_hub_scores: HashMap::new(),
+            ownership: HashMap::new(),
+            function_hub_scores: HashMap::new(),
+            collaborations: HashMap::new(),
+            excluded_files: BTreeMap::new(),
+            analysis_runs: Vec::new(),
+        }
+    }
+
+    fn rename_scores(&mut self, old_path: &str, new_path: &str) {
+        for scores in [
+            &mut self.hub_scores,
+            &mut self.normalized_hub_scores,
+            &mut self.decayed_hub_scores,
+        ] {
+            scores.remove(new_path);
+            if let Some(mut score) = scores.remove(old_path) {
+                score.path = new_path.to_string();
+                scores.insert(new_path.to_string(), score);
+            }
+        }
+
+        self.ownership.remove(new_path);
+        if let Some(mut ownership) = self.ownership.remove(old_path) {
+            ownership.path = new_path.to_string();
+            self.ownership.insert(new_path.to_string(), ownership);
+        }
+
+        self.function_hub_scores
+            .retain(|(path, _), _| path != new_path);
+        let moved: Vec<(String, String)> = self
+            .function_hub_scores
+            .keys()
+            .filter(|(path, _)| path == old_path)
+            .cloned()
+            .collect();
+        for key in moved {
+            if let Some(mut score) = self.function_hub_scores.remove(&key) {
+                score.path = new_path.to_string();
+                self.function_hub_scores
+                    .insert((new_path.to_string(), key.1), score);
+            }
+        }
+    }
+}
+
+/// Process-local graph store backed by `FileGraphBuilder`. Nothing is persisted, so it is
+/// meant for single-run pipelines such as `analyse-local` on a laptop or in CI.
+pub struct MemoryStore {
+    repos: Mutex<HashMap<String, RepoState>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self {
+            repos: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) fn graph_data_from(graph: &FileGraph, total_commits: i64) -> GraphData {
+    let files = graph
+        .node_map
+        .iter()
+        .map(|node| FileNode {
+            path: node.path.clone(),
+            additions: node.additions as i64,
+            deletions: node.deletions as i64,
+            normalized_additions: node.normalized_additions as i64,
+            normalized_deletions: node.normalized_deletions as i64,
+            commit_count: node.commit_count as i64,
+            deleted_at_commit: node.deleted_at_commit.clone(),
+            decayed_churn: node.decayed_churn,
+            decayed_commit_count: node.decayed_commit_count,
+            authors: node
+                .authors
+                .iter()
+                .map(|author| FileAuthor {
+                    email: author.email.clone(),
+                    commits: author.commits as i64,
+                    churn: author.churn as i64,
+                })
+                .collect(),
+            commit_classes: node.commit_classes,
+        })
+        .collect();
+
+    // The builder keeps both directions of every pair; Neo4j stores a single
+    // source -> target relationship, so only emit the lexicographically ordered one.
+    let edges = graph
+        .node_map
+        .iter()
+        .flat_map(|node| {
+            node.edges
+                .iter()
+                .filter(|edge| node.path < edge.target)
+                .map(|edge| Edge {
+                    source: node.path.clone(),
+                    target: edge.target.clone(),
+                    weight: edge.weight as i64,
+                    decayed_weight: edge.decayed_weight,
+                })
+        })
+        .collect();
+
+    GraphData {
+        repo: graph.repo.clone(),
+        total_commits_analyzed: total_commits,
+        files,
+        edges,
+        window: None,
+        authors: graph
+            .authors
+            .iter()
+            .map(|author| Author {
+                email: author.email.clone(),
+                name: author.name.clone(),
+                commit_count: author.commit_count as i64,
+                committed_count: author.committed_count as i64,
+            })
+            .collect(),
+        issues: graph
+            .issues
+            .iter()
+            .map(|issue| Issue {
+                key: issue.key.clone(),
+                commits: issue.commits.clone(),
+                files: issue
+                    .files
+                    .iter()
+                    .map(|file| IssueFile {
+                        path: file.path.clone(),
+                        commits: file.commits as i64,
+                    })
+                    .collect(),
+            })
+            .collect(),
+        issue_edges: Vec::new(),
+        collaborations: Vec::new(),
+        functions: graph
+            .functions
+            .iter()
+            .map(|function| Function {
+                path: function.path.clone(),
+                name: function.name.clone(),
+                commit_count: function.commit_count as i64,
+                churn: function.churn as i64,
+            })
+            .collect(),
+        function_edges: graph
+            .function_edges
+            .iter()
+            .map(|edge| FunctionEdge {
+                source_path: edge.source_path.clone(),
+                source_name: edge.source_name.clone(),
+                target_path: edge.target_path.clone(),
+                target_name: edge.target_name.clone(),
+                weight: edge.weight as i64,
+            })
+            .collect(),
+        excluded_files: Vec::new(),
+        analysis_runs: Vec::new(),
+    }
+}
+
+#[async_trait]
+impl GraphStore for MemoryStore {
+    async fn init_schema(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn delete_all_nodes(&self) -> Result<(), String> {
+        self.repos.lock().await.clear();
+        Ok(())
+    }
+
+    async fn delete_repository(&self, repo: &str) -> Result<(), String> {
+        self.repos.lock().await.remove(repo);
+        Ok(())
+    }
+
+    async fn save_repository(&self, name: &str, url: Option<&str>) -> Result<(), String> {
+        let mut repos = self.repos.lock().await;
+        let state = repos
+            .entry(name.to_string())
+            .or_insert_with(|| RepoState::new(name));
+        if let Some(url) = url {
+            state.url = Some(url.to_string());
+        }
+        Ok(())
+    }
+
+    async fn update_commit_count(&self, repo: &str, total_commits: i64) -> Result<(), String> {
+        let mut repos = self.repos.lock().await;
+        if let Some(state) = repos.get_mut(repo) {
+            state.total_commits = total_commits;
+            state.builder.set_total_commits(total_commits as u32);
+        }
+        Ok(())
+    }
+
+    async fn get_checkpoint(&self, repo: &str) -> Result<Option<AnalysisCheckpoint>, String> {
+        let repos = self.repos.lock().await;
+        Ok(repos.get(repo).and_then(|state| {
+            state
+                .last_commit
+                .as_ref()
+                .map(|last_commit| AnalysisCheckpoint {
+                    last_commit: last_commit.clone(),
+                    total_commits: state.total_commits,
+                })
+        }))
+    }
+
+    async fn set_last_commit(&self, repo: &str, commit: &str) -> Result<(), String> {
+        let mut repos = self.repos.lock().await;
+        if let Some(state) = repos.get_mut(repo) {
+            state.last_commit = Some(commit.to_string());
+        }
+        Ok(())
+    }
+
+    async fn set_analysis_window(&self, repo: &str, window: &str) -> Result<(), String> {
+        let mut repos = self.repos.lock().await;
+        if let Some(state) = repos.get_mut(repo) {
+            state.window = Some(window.to_string());
+        }
+        Ok(())
+    }
+
+    async fn save_file_node(
+        &self,
+        repo: &str,
+        path: &str,
+        additions: i64,
+        deletions: i64,
+        deleted_at_commit: Option<&str>,
+    ) -> Result<(), String> {
+        let mut repos = self.repos.lock().await;
+        let state = repos
+            .entry(repo.to_string())
+            .or_insert_with(|| RepoState::new(repo));
+        state.builder.record_file_change(
+            path,
+            additions as u32,
+            deletions as u32,
+            additions as u32,
+            deletions as u32,
+            deleted_at_commit,
+        );
+        Ok(())
+    }
+
+    async fn rename_file_node(
+        &self,
+        repo: &str,
+        old_path: &str,
+        new_path: &str,
+    ) -> Result<(), String> {
+        let mut repos = self.repos.lock().await;
+        if let Some(state) = repos.get_mut(repo) {
+            state.builder.rename_file(old_path, new_path);
+            state.rename_scores(old_path, new_path);
+        }
+        Ok(())
+    }
+
+    async fn save_cochange_relationship(
+        &self,
+        repo: &str,
+        source: &str,
+        target: &str,
+    ) -> Result<(), String> {
+        let mut repos = self.repos.lock().await;
+        if let Some(state) = repos.get_mut(repo) {
+            state.builder.record_cochange(source, target);
+        }
+        Ok(())
+    }
+
+    async fn bulk_load_graph(&self, graph: &FileGraph) -> Result<(), String> {
+        let mut repos = self.repos.lock().await;
+        let state = repos
+            .entry(graph.repo.clone())
+            .or_insert_with(|| RepoState::new(&graph.repo));
+        state.builder.merge_graph(graph);
+        Ok(())
+    }
+
+    async fn save_commit_batch(&self, repo: &str, batch: &[CommitChanges]) -> Result<(), String> {
+        let mut repos = self.repos.lock().await;
+        let state = repos
+            .entry(repo.to_string())
+            .or_insert_with(|| RepoState::new(repo));
+        let total_commits = state.builder.total_commits();
+        for commit in batch {
+            for (old_path, new_path) in &commit.renames {
+                state.rename_scores(old_path, new_path);
+            }
+            state.builder.apply_commit(commit);
+        }
+        // Commit totals are owned by update_commit_count, as with the other stores
+        state.builder.set_total_commits(total_commits);
+        Ok(())
+    }
+
+    async fn rescale_decay(&self, repo: &str, factor: f64) -> Result<(), String> {
+        let mut repos = self.repos.lock().await;
+        if let Some(state) = repos.get_mut(repo) {
+            state.builder.rescale_decay(factor);
+        }
+        Ok(())
+    }
+
+    async fn save_collaborations(
+        &self,
+        repo: &str,
+        collaborations: &[Collaboration],
+    ) -> Result<(), String> {
+        let mut repos = self.repos.lock().await;
+        if let Some(state) = repos.get_mut(repo) {
+            for collaboration in collaborations {
+                *state
+                    .collaborations
+                    .entry((collaboration.source.clone(), collaboration.target.clone()))
+                    .or_insert(0) += collaboration.weight;
+            }
+        }
+        Ok(())
+    }
+
+    async fn save_excluded_files(&self, repo: &str, files: &[ExcludedFile]) -> Result<(), String> {
+        let mut repos = self.repos.lock().await;
+        if let Some(state) = repos.get_mut(repo) {
+            for file in files {
+                state
+                    .excluded_files
+                    .insert(file.path.clone(), file.reason.clone());
+            }
+        }
+        Ok(())
+    }
+
+    async fn save_analysis_run(&self, repo: &str, run: &AnalysisRun) -> Result<(), String> {
+        let mut repos = self.repos.lock().await;
+        if let Some(state) = repos.get_mut(repo) {
+            state.analysis_runs.push(run.clone());
+        }
+        Ok(())
+    }
+
+    async fn get_graph(&self, repo: &str) -> Result<GraphData, String> {
+        let repos = self.repos.lock().await;
+        match repos.get(repo) {
+            Some(state) => {
+                let mut collaborations: Vec<Collaboration> = state
+                    .collaborations
+                    .iter()
+                    .map(|((source, target), weight)| Collaboration {
+                        source: source.clone(),
+                        target: target.clone(),
+                        weight: *weight,
+                    })
+                    .collect();
+                collaborations.sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+
+                // Issue co-change is derived on every read, so it never goes stale here
+                let graph = graph_data_from(&state.builder.snapshot(), state.total_commits);
+                Ok(GraphData {
+                    window: state.window.clone(),
+                    collaborations,
+                    excluded_files: state
+                        .excluded_files
+                        .iter()
+                        .map(|(path, reason)| ExcludedFile {
+                            path: path.clone(),
+                            reason: reason.clone(),
+                        })
+                        .collect(),
+                    issue_edges: issue_cochanges(&graph.issues),
+                    analysis_runs: state.analysis_runs.clone(),
+                    ..graph
+                })
+            }
+            None => Ok(GraphData {
+                repo: repo.to_string(),
+                total_commits_analyzed: 0,
+                files: Vec::new(),
+                edges: Vec::new(),
+                window: None,
+                authors: Vec::new(),
+                collaborations: Vec::new(),
+                issues: Vec::new(),
+                issue_edges: Vec::new(),
+                functions: Vec::new(),
+                function_edges: Vec::new(),
+                excluded_files: Vec::new(),
+                analysis_runs: Vec::new(),
+            }),
+        }
+    }
+
+    async fn link_file_to_repo(&self, _repo: &str, _file_path: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn link_all_files_to_repo(&self, _repo: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn remove_low_importance_connections(
+        &self,
+        repo: &str,
+        threshold: i64,
+    ) -> Result<u64, String> {
+        let mut repos = self.repos.lock().await;
+        Ok(repos.get_mut(repo).map_or(0, |state| {
+            state.builder.prune_edges(threshold.max(0) as u32)
+        }))
+    }
+
+    async fn remove_orphan_files(&self, repo: &str) -> Result<u64, String> {
+        let mut repos = self.repos.lock().await;
+        let Some(state) = repos.get_mut(repo) else {
+            return Ok(0);
+        };
+
+        let removed = state.builder.remove_orphans();
+        let builder = &state.builder;
+        state
+            .hub_scores
+            .retain(|path, _| builder.contains_file(path));
+        state
+            .normalized_hub_scores
+            .retain(|path, _| builder.contains_file(path));
+        state
+            .decayed_hub_scores
+            .retain(|path, _| builder.contains_file(path));
+        state
+            .ownership
+            .retain(|path, _| builder.contains_file(path));
+        state
+            .function_hub_scores
+            .retain(|(path, _), _| builder.contains_file(path));
+        Ok(removed)
+    }
+
+    async fn compute_hub_scores(&self, repo: &str, min_coupling: f64) -> Result<(), String> {
+        let mut repos = self.repos.lock().await;
+        let state = repos
+            .get_mut(repo)
+            .ok_or_else(|| format!("Repository {} not found", repo))?;
+
+        let graph = graph_data_from(&state.builder.snapshot(), state.total_commits);
+        for score in hub_score::compute_hub_scores(&graph, min_coupling) {
+            state.hub_scores.insert(score.path.clone(), score);
+        }
+        for score in hub_score::compute_normalized_hub_scores(&graph, min_coupling) {
+            state
+                .normalized_hub_scores
+                .insert(score.path.clone(), score);
+        }
+        for score in hub_score::compute_decayed_hub_scores(&graph, min_coupling) {
+            state.decayed_hub_scores.insert(score.path.clone(), score);
+        }
+        for ownership in ownership::compute_ownership(&graph) {
+            state.ownership.insert(ownership.path.clone(), ownership);
+        }
+        state.function_hub_scores.clear();
+        for score in hub_score::compute_function_hub_scores(&graph, min_coupling) {
+            state
+                .function_hub_scores
+                .insert((score.path.clone(), score.name.clone()), score);
+        }
+        Ok(())
+    }
+
+    async fn get_all_repo_names(&self) -> Result<Vec<String>, String> {
+        let repos = self.repos.lock().await;
+        let mut names: Vec<String> = repos
+            .iter()
+            .filter(|(_, state)| state.builder.file_count() > 0)
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    async fn get_all_hub_scores(&self, extension: &str) -> Result<Vec<HubScoreData>, String> {
+        let repos = self.repos.lock().await;
+        let mut files = Vec::new();
+        for (name, state) in repos.iter() {
+            for node in state.builder.snapshot().node_map {
+                if !node.path.ends_with(extension) || node.deleted_at_commit.is_some() {
+                    continue;
+                }
+                let score = state.hub_scores.get(&node.path);
+                let normalized = state.normalized_hub_scores.get(&node.path);
+                let decayed = state.decayed_hub_scores.get(&node.path);
+                let ownership = state.ownership.get(&node.path);
+                files.push(HubScoreData {
+                    repo: name.clone(),
+                    path: node.path.clone(),
+                    hub_score: score.map(|s| s.hub_score).unwrap_or(0.0),
+                    avg_coupling: score.map(|s| s.avg_coupling).unwrap_or(0.0),
+                    commit_count: node.commit_count as i64,
+                    partner_count: score.map(|s| s.partner_count).unwrap_or(0),
+                    churn: (node.additions + node.deletions) as i64,
+                    window: state.window.clone(),
+                    normalized_hub_score: normalized.map(|s| s.hub_score),
+                    decayed_hub_score: decayed.map(|s| s.hub_score),
+                    decayed_avg_coupling: decayed.map(|s| s.avg_coupling),
+                    decayed_partner_count: decayed.map(|s| s.partner_count),
+                    author_count: ownership.map(|o| o.author_count),
+                    main_author_share: ownership.map(|o| o.main_author_share),
+                    bus_factor: ownership.map(|o| o.bus_factor),
+                    commit_classes: node.commit_classes,
+                });
+            }
+        }
+        files.sort_by(|a, b| b.hub_score.total_cmp(&a.hub_score));
+        Ok(files)
+    }
+
+    async fn get_all_function_hub_scores(
+        &self,
+        extension: &str,
+    ) -> Result<Vec<FunctionHubScoreData>, String> {
+        let repos = self.repos.lock().await;
+        let mut functions = Vec::new();
+        for (name, state) in repos.iter() {
+            let graph = state.builder.snapshot();
+            let live_files: HashSet<&str> = graph
+                .node_map
+                .iter()
+                .filter(|node| node.deleted_at_commit.is_none())
+                .map(|node| node.path.as_str())
+                .collect();
+            for function in &graph.functions {
+                if !function.path.ends_with(extension)
+                    || !live_files.contains(function.path.as_str())
+                {
+                    continue;
+                }
+                let score = state
+                    .function_hub_scores
+                    .get(&(function.path.clone(), function.name.clone()));
+                functions.push(FunctionHubScoreData {
+                    repo: name.clone(),
+                    hub_score: score.map(|s| s.hub_score).unwrap_or(0.0),
+                    avg_coupling: score.map(|s| s.avg_coupling).unwrap_or(0.0),
+                    commit_count: function.commit_count as i64,
+                    partner_count: score.map(|s| s.partner_count).unwrap_or(0),
+                    churn: function.churn as i64,
+                    path: function.path.clone(),
+                    name: function.name.clone(),
+                });
+            }
+        }
+        functions.sort_by(|a, b| b.hub_score.total_cmp(&a.hub_score));
+        Ok(functions)
+    }
+
+    async fn get_files_by_hub_threshold(
+        &self,
+        repo: Option<&str>,
+        hub_threshold: f64,
+        extension: &str,
+        ignore_repos: &[String],
+    ) -> Result<Vec<RepoWithFiles>, String> {
+        let repos = self.repos.lock().await;
+        let mut names: Vec<&String> = repos
+            .keys()
+            .filter(|name| repo.is_none_or(|repo| repo == name.as_str()))
+            .filter(|name| {
+                !ignore_repos
+                    .iter()
+                    .any(|ignore| name.to_lowercase().contains(&ignore.to_lowercase()))
+            })
+            .collect();
+        names.sort();
+
+        let mut repos_with_files = Vec::new();
+        for name in names {
+            let state = &repos[name];
+            let mut files: Vec<RepoFile> = state
+                .builder
+                .snapshot()
+                .node_map
+                .into_iter()
+                .filter(|node| node.deleted_at_commit.is_none() && node.path.ends_with(extension))
+                .map(|node| RepoFile {
+                    hub_score: state
+                        .hub_scores
+                        .get(&node.path)
+                        .map(|s| s.hub_score)
+                        .unwrap_or(0.0),
+                    path: node.path,
+                })
+                .collect();
+
+            if files.is_empty() {
+                continue;
+            }
+            files.sort_by(|a, b| b.hub_score.total_cmp(&a.hub_score));
+
+            let is_high_risk = files
+                .first()
+                .map(|f| f.hub_score >= hub_threshold)
+                .unwrap_or(false);
+
+            repos_with_files.push(RepoWithFiles {
+                repo: name.clone(),
+                repo_url: state.url.clone().unwrap_or_default(),
+                files,
+                is_high_risk,
+            });
+        }
+
+        Ok(repos_with_files)
+    }
+}
@@ -1,3 +1,40 @@
+pub mod commit_batch;
+pub mod graph_store;
+pub mod memory_store;
 pub mod neo4j_client;
+pub mod sqlite_store;
 
-pub use neo4j_client::{GraphData, HubScoreData, Neo4jClient, RepoFile, RepoWithFiles};
+pub use commit_batch::{
+    graph_segment, segment_batch, AuthorRow, BatchSegment, CochangeRow, FileAuthorRow, FileRow,
+    FunctionCochangeRow, FunctionRow, IssueCommitRow, IssueFileRow,
+};
+pub use graph_store::{AnalysisCheckpoint, GraphStore};
+pub use memory_store::MemoryStore;
+pub use neo4j_client::{
+    AnalysisRun, Author, Collaboration, Edge, ExcludedFile, FileAuthor, FileNode, Function,
+    FunctionEdge, FunctionHubScoreData, GraphData, HubScoreData, Issue, IssueFile, Neo4jClient,
+    RepoFile, RepoWithFiles,
+};
+pub use sqlite_store::SqliteStore;
+
+/// Opens the store named by a `--store` value: `neo4j` (the default, using `neo4j_uri`),
+/// `memory`, or `sqlite:<path>` for a single-file database.
+pub async fn open_store(
+    spec: &str,
+    neo4j_uri: &str,
+    neo4j_database: &str,
+) -> Result<Box<dyn GraphStore>, String> {
+    match spec {
+        "neo4j" => Ok(Box::new(
+            Neo4jClient::new_with_database(neo4j_uri, neo4j_database).await?,
+        )),
+        "memory" => Ok(Box::new(MemoryStore::new())),
+        other => match other.strip_prefix("sqlite:") {
+            Some(path) if !path.is_empty() => Ok(Box::new(SqliteStore::open(path)?)),
+            _ => Err(format!(
+                "Unknown store '{}', expected 'neo4j', 'memory' or 'sqlite:<path>'",
+                other
+            )),
+        },
+    }
+}
@@ -1,14 +1,112 @@
-use neo4rs::{query, ConfigBuilder, Graph};
+use async_trait::async_trait;
+use neo4rs::{query, BoltType, ConfigBuilder, Graph, Txn};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use super::{
+    graph_segment, segment_batch, AnalysisCheckpoint, AuthorRow, CochangeRow, FileAuthorRow,
+    FileRow, FunctionCochangeRow, FunctionRow, GraphStore, IssueCommitRow, IssueFileRow,
+};
+use crate::commit_class::ClassCounts;
+use crate::file_graph::{CommitChanges, FileGraph};
+use crate::hub_score;
+use crate::issue_refs::issue_cochanges;
+use crate::ownership;
+
+const UNWIND_CHUNK_SIZE: usize = 10_000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileNode {
     pub path: String,
     pub additions: i64,
     pub deletions: i64,
+    #[serde(default)]
+    pub normalized_additions: i64,
+    #[serde(default)]
+    pub normalized_deletions: i64,
+    pub commit_count: i64,
+    #[serde(default)]
+    pub deleted_at_commit: Option<String>,
+    #[serde(default)]
+    pub decayed_churn: Option<f64>,
+    #[serde(default)]
+    pub decayed_commit_count: Option<f64>,
+    #[serde(default)]
+    pub authors: Vec<FileAuthor>,
+    #[serde(default)]
+    pub commit_classes: ClassCounts,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileAuthor {
+    pub email: String,
+    pub commits: i64,
+    pub churn: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Author {
+    pub email: String,
+    pub name: String,
+    pub commit_count: i64,
+    pub committed_count: i64,
+}
+
+/// Undirected author-to-author link, stored once with `source < target`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collaboration {
+    pub source: String,
+    pub target: String,
+    pub weight: i64,
+}
+
+/// The commits referencing one issue key and the files they touched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Issue {
+    pub key: String,
+    pub commits: Vec<String>,
+    pub files: Vec<IssueFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssueFile {
+    pub path: String,
+    pub commits: i64,
+}
+
+/// A file with an analysed extension that the path filter left out, e.g. vendored code.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcludedFile {
+    pub path: String,
+    pub reason: String,
+}
+
+/// The effective pipeline configuration, as TOML, that an analysis run used.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisRun {
+    pub started_at: String,
+    pub config: String,
+}
+
+/// A function or method; `churn` counts the changed lines attributed to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Function {
+    pub path: String,
+    pub name: String,
     pub commit_count: i64,
+    pub churn: i64,
+}
+
+/// Function co-change, stored once with the source ordered before the target.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionEdge {
+    pub source_path: String,
+    pub source_name: String,
+    pub target_path: String,
+    pub target_name: String,
+    pub weight: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +114,8 @@ pub struct Edge {
     pub source: String,
     pub target: String,
     pub weight: i64,
+    #[serde(default)]
+    pub decayed_weight: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +124,25 @@ pub struct GraphData {
     pub total_commits_analyzed: i64,
     pub files: Vec<FileNode>,
     pub edges: Vec<Edge>,
+    #[serde(default)]
+    pub window: Option<String>,
+    #[serde(default)]
+    pub authors: Vec<Author>,
+    #[serde(default)]
+    pub collaborations: Vec<Collaboration>,
+    #[serde(default)]
+    pub issues: Vec<Issue>,
+    /// Co-change with each issue's commits merged into one change set.
+    #[serde(default)]
+    pub issue_edges: Vec<Edge>,
+    #[serde(default)]
+    pub functions: Vec<Function>,
+    #[serde(default)]
+    pub function_edges: Vec<FunctionEdge>,
+    #[serde(default)]
+    pub excluded_files: Vec<ExcludedFile>,
+    #[serde(default)]
+    pub analysis_runs: Vec<AnalysisRun>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +154,36 @@ pub struct HubScoreData {
     pub commit_count: i64,
     pub partner_count: i64,
     pub churn: i64,
+    #[serde(default)]
+    pub window: Option<String>,
+    #[serde(default)]
+    pub normalized_hub_score: Option<f64>,
+    #[serde(default)]
+    pub decayed_hub_score: Option<f64>,
+    #[serde(default)]
+    pub decayed_avg_coupling: Option<f64>,
+    #[serde(default)]
+    pub decayed_partner_count: Option<i64>,
+    #[serde(default)]
+    pub author_count: Option<i64>,
+    #[serde(default)]
+    pub main_author_share: Option<f64>,
+    #[serde(default)]
+    pub bus_factor: Option<i64>,
+    #[serde(flatten)]
+    pub commit_classes: ClassCounts,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionHubScoreData {
+    pub repo: String,
+    pub path: String,
+    pub name: String,
+    pub hub_score: f64,
+    pub avg_coupling: f64,
+    pub commit_count: i64,
+    pub partner_count: i64,
+    pub churn: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,6 +200,16 @@ pub struct RepoWithFiles {
     pub is_high_risk: bool,
 }
 
+fn class_counts(row: &neo4rs::Row) -> ClassCounts {
+    ClassCounts {
+        bugfix_commits: row.get::<i64>("bugfix_commits").unwrap_or(0),
+        feature_commits: row.get::<i64>("feature_commits").unwrap_or(0),
+        refactor_commits: row.get::<i64>("refactor_commits").unwrap_or(0),
+        docs_commits: row.get::<i64>("docs_commits").unwrap_or(0),
+        merge_commits: row.get::<i64>("merge_commits").unwrap_or(0),
+    }
+}
+
 pub struct Neo4jClient {
     graph: Arc<Mutex<Graph>>,
 }
@@ -83,7 +242,497 @@ impl Neo4jClient {
         })
     }
 
-    pub async fn delete_all_nodes(&self) -> Result<(), String> {
+    async fn run_renames_in_txn(
+        txn: &mut Txn,
+        repo: &str,
+        renames: &[(String, String)],
+    ) -> Result<(), String> {
+        for (old_path, new_path) in renames {
+            for q in [
+                "MATCH (fn:Function {repo: $repo, path: $new_path}) DETACH DELETE fn",
+                "MATCH (f:File {repo: $repo, path: $new_path}) DETACH DELETE f",
+            ] {
+                txn.run(
+                    query(q)
+                        .param("repo", repo)
+                        .param("new_path", new_path.as_str()),
+                )
+                .await
+                .map_err(|e| format!("Failed to delete conflicting new_path node: {}", e))?;
+            }
+
+            for q in [
+                "MATCH (f:File {repo: $repo, path: $old_path}) SET f.path = $new_path",
+                "MATCH (fn:Function {repo: $repo, path: $old_path}) SET fn.path = $new_path",
+            ] {
+                txn.run(
+                    query(q)
+                        .param("repo", repo)
+                        .param("old_path", old_path.as_str())
+                        .param("new_path", new_path.as_str()),
+                )
+                .await
+                .map_err(|e| format!("Failed to rename file node: {}", e))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn unwind_file_rows(txn: &mut Txn, repo: &str, rows: &[FileRow]) -> Result<(), String> {
+        for chunk in rows.chunks(UNWIND_CHUNK_SIZE) {
+            let rows: Vec<HashMap<&str, BoltType>> = chunk
+                .iter()
+                .map(|row| {
+                    HashMap::from([
+                        ("path", row.path.as_str().into()),
+                        ("additions", row.additions.into()),
+                        ("deletions", row.deletions.into()),
+                        ("normalized_additions", row.normalized_additions.into()),
+                        ("normalized_deletions", row.normalized_deletions.into()),
+                        ("commit_count", row.commit_count.into()),
+                        ("deleted_at_commit", row.deleted_at_commit.clone().into()),
+                        ("decayed_churn", row.decayed_churn.into()),
+                        ("decayed_commit_count", row.decayed_commit_count.into()),
+                        ("bugfix_commits", row.commit_classes.bugfix_commits.into()),
+                        ("feature_commits", row.commit_classes.feature_commits.into()),
+                        (
+                            "refactor_commits",
+                            row.commit_classes.refactor_commits.into(),
+                        ),
+                        ("docs_commits", row.commit_classes.docs_commits.into()),
+                        ("merge_commits", row.commit_classes.merge_commits.into()),
+                    ])
+                })
+                .collect();
+
+            txn.run(
+                query(
+                    "UNWIND $rows AS row \
+                     MERGE (f:File {repo: $repo, path: row.path}) \
+                     SET f.additions = COALESCE(f.additions, 0) + row.additions, \
+                         f.deletions = COALESCE(f.deletions, 0) + row.deletions, \
+                         f.normalized_additions = COALESCE(f.normalized_additions, 0) + row.normalized_additions, \
+                         f.normalized_deletions = COALESCE(f.normalized_deletions, 0) + row.normalized_deletions, \
+                         f.commit_count = COALESCE(f.commit_count, 0) + row.commit_count, \
+                         f.deleted_at_commit = COALESCE(f.deleted_at_commit, row.deleted_at_commit), \
+                         f.decayed_churn = COALESCE(f.decayed_churn + row.decayed_churn, \
+                             f.decayed_churn, row.decayed_churn), \
+                         f.decayed_commit_count = COALESCE(f.decayed_commit_count + row.decayed_commit_count, \
+                             f.decayed_commit_count, row.decayed_commit_count), \
+                         f.bugfix_commits = COALESCE(f.bugfix_commits, 0) + row.bugfix_commits, \
+                         f.feature_commits = COALESCE(f.feature_commits, 0) + row.feature_commits, \
+                         f.refactor_commits = COALESCE(f.refactor_commits, 0) + row.refactor_commits, \
+                         f.docs_commits = COALESCE(f.docs_commits, 0) + row.docs_commits, \
+                         f.merge_commits = COALESCE(f.merge_commits, 0) + row.merge_commits",
+                )
+                .param("repo", repo)
+                .param("rows", rows),
+            )
+            .await
+            .map_err(|e| format!("Failed to save file nodes: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    async fn unwind_cochange_rows(
+        txn: &mut Txn,
+        repo: &str,
+        rows: &[CochangeRow],
+    ) -> Result<(), String> {
+        for chunk in rows.chunks(UNWIND_CHUNK_SIZE) {
+            let rows: Vec<HashMap<&str, BoltType>> = chunk
+                .iter()
+                .map(|row| {
+                    HashMap::from([
+                        ("source", row.source.as_str().into()),
+                        ("target", row.target.as_str().into()),
+                        ("weight", row.weight.into()),
+                        ("decayed_weight", row.decayed_weight.into()),
+                    ])
+                })
+                .collect();
+
+            txn.run(
+                query(
+                    "UNWIND $rows AS row \
+                     MATCH (f1:File {repo: $repo, path: row.source}) \
+                     MATCH (f2:File {repo: $repo, path: row.target}) \
+                     MERGE (f1)-[r:CO_CHANGED]->(f2) \
+                     SET r.weight = COALESCE(r.weight, 0) + row.weight, \
+                         r.decayed_weight = COALESCE(r.decayed_weight + row.decayed_weight, \
+                             r.decayed_weight, row.decayed_weight)",
+                )
+                .param("repo", repo)
+                .param("rows", rows),
+            )
+            .await
+            .map_err(|e| format!("Failed to save co-change relationships: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    async fn unwind_author_rows(
+        txn: &mut Txn,
+        repo: &str,
+        authors: &[AuthorRow],
+        file_authors: &[FileAuthorRow],
+    ) -> Result<(), String> {
+        for chunk in authors.chunks(UNWIND_CHUNK_SIZE) {
+            let rows: Vec<HashMap<&str, BoltType>> = chunk
+                .iter()
+                .map(|row| {
+                    HashMap::from([
+                        ("email", row.email.as_str().into()),
+                        ("name", row.name.as_str().into()),
+                        ("commit_count", row.commit_count.into()),
+                        ("committed_count", row.committed_count.into()),
+                    ])
+                })
+                .collect();
+
+            txn.run(
+                query(
+                    "UNWIND $rows AS row \
+                     MERGE (a:Author {repo: $repo, email: row.email}) \
+                     SET a.name = row.name, \
+                         a.commit_count = COALESCE(a.commit_count, 0) + row.commit_count, \
+                         a.committed_count = COALESCE(a.committed_count, 0) + row.committed_count",
+                )
+                .param("repo", repo)
+                .param("rows", rows),
+            )
+            .await
+            .map_err(|e| format!("Failed to save authors: {}", e))?;
+        }
+
+        for chunk in file_authors.chunks(UNWIND_CHUNK_SIZE) {
+            let rows: Vec<HashMap<&str, BoltType>> = chunk
+                .iter()
+                .map(|row| {
+                    HashMap::from([
+                        ("path", row.path.as_str().into()),
+                        ("email", row.email.as_str().into()),
+                        ("commits", row.commits.into()),
+                        ("churn", row.churn.into()),
+                    ])
+                })
+                .collect();
+
+            txn.run(
+                query(
+                    "UNWIND $rows AS row \
+                     MATCH (a:Author {repo: $repo, email: row.email}) \
+                     MATCH (f:File {repo: $repo, path: row.path}) \
+                     MERGE (a)-[r:AUTHORED]->(f) \
+                     SET r.commits = COALESCE(r.commits, 0) + row.commits, \
+                         r.churn = COALESCE(r.churn, 0) + row.churn",
+                )
+                .param("repo", repo)
+                .param("rows", rows),
+            )
+            .await
+            .map_err(|e| format!("Failed to save file authors: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    async fn unwind_issue_rows(
+        txn: &mut Txn,
+        repo: &str,
+        issue_commits: &[IssueCommitRow],
+        issue_files: &[IssueFileRow],
+    ) -> Result<(), String> {
+        for chunk in issue_commits.chunks(UNWIND_CHUNK_SIZE) {
+            let rows: Vec<HashMap<&str, BoltType>> = chunk
+                .iter()
+                .map(|row| {
+                    HashMap::from([
+                        ("key", row.key.as_str().into()),
+                        ("hash", row.commit_hash.as_str().into()),
+                    ])
+                })
+                .collect();
+
+            txn.run(
+                query(
+                    "UNWIND $rows AS row \
+                     MERGE (i:Issue {repo: $repo, key: row.key}) \
+                     MERGE (c:Commit {repo: $repo, hash: row.hash}) \
+                     MERGE (c)-[:REFERENCES]->(i)",
+                )
+                .param("repo", repo)
+                .param("rows", rows),
+            )
+            .await
+            .map_err(|e| format!("Failed to save issue references: {}", e))?;
+        }
+
+        for chunk in issue_files.chunks(UNWIND_CHUNK_SIZE) {
+            let rows: Vec<HashMap<&str, BoltType>> = chunk
+                .iter()
+                .map(|row| {
+                    HashMap::from([
+                        ("key", row.key.as_str().into()),
+                        ("path", row.path.as_str().into()),
+                        ("commits", row.commits.into()),
+                    ])
+                })
+                .collect();
+
+            txn.run(
+                query(
+                    "UNWIND $rows AS row \
+                     MATCH (i:Issue {repo: $repo, key: row.key}) \
+                     MATCH (f:File {repo: $repo, path: row.path}) \
+                     MERGE (i)-[r:TOUCHES]->(f) \
+                     SET r.commits = COALESCE(r.commits, 0) + row.commits",
+                )
+                .param("repo", repo)
+                .param("rows", rows),
+            )
+            .await
+            .map_err(|e| format!("Failed to save issue files: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    async fn unwind_function_rows(
+        txn: &mut Txn,
+        repo: &str,
+        functions: &[FunctionRow],
+        function_cochanges: &[FunctionCochangeRow],
+    ) -> Result<(), String> {
+        for chunk in functions.chunks(UNWIND_CHUNK_SIZE) {
+            let rows: Vec<HashMap<&str, BoltType>> = chunk
+                .iter()
+                .map(|row| {
+                    HashMap::from([
+                        ("path", row.path.as_str().into()),
+                        ("name", row.name.as_str().into()),
+                        ("commit_count", row.commit_count.into()),
+                        ("churn", row.churn.into()),
+                    ])
+                })
+                .collect();
+
+            txn.run(
+                query(
+                    "UNWIND $rows AS row \
+                     MATCH (f:File {repo: $repo, path: row.path}) \
+                     MERGE (fn:Function {repo: $repo, path: row.path, name: row.name}) \
+                     MERGE (f)-[:DECLARES]->(fn) \
+                     SET fn.commit_count = COALESCE(fn.commit_count, 0) + row.commit_count, \
+                         fn.churn = COALESCE(fn.churn, 0) + row.churn",
+                )
+                .param("repo", repo)
+                .param("rows", rows),
+            )
+            .await
+            .map_err(|e| format!("Failed to save functions: {}", e))?;
+        }
+
+        for chunk in function_cochanges.chunks(UNWIND_CHUNK_SIZE) {
+            let rows: Vec<HashMap<&str, BoltType>> = chunk
+                .iter()
+                .map(|row| {
+                    HashMap::from([
+                        ("source_path", row.source_path.as_str().into()),
+                        ("source_name", row.source_name.as_str().into()),
+                        ("target_path", row.target_path.as_str().into()),
+                        ("target_name", row.target_name.as_str().into()),
+                        ("weight", row.weight.into()),
+                    ])
+                })
+                .collect();
+
+            txn.run(
+                query(
+                    "UNWIND $rows AS row \
+                     MATCH (a:Function {repo: $repo, path: row.source_path, name: row.source_name}) \
+                     MATCH (b:Function {repo: $repo, path: row.target_path, name: row.target_name}) \
+                     MERGE (a)-[r:CO_CHANGED]->(b) \
+                     SET r.weight = COALESCE(r.weight, 0) + row.weight",
+                )
+                .param("repo", repo)
+                .param("rows", rows),
+            )
+            .await
+            .map_err(|e| format!("Failed to save function co-changes: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    // Decayed scores use fractional weights and ownership needs per-author counts, so these
+    // come from the Rust ports over the stored graph instead of Cypher, as do normalized
+    // hub scores, issue co-change and function hub scores.
+    async fn compute_graph_metrics(&self, repo: &str, min_coupling: f64) -> Result<(), String> {
+        let data = self.get_graph(repo).await?;
+
+        let normalized: Vec<HashMap<&str, BoltType>> =
+            hub_score::compute_normalized_hub_scores(&data, min_coupling)
+                .into_iter()
+                .map(|score| {
+                    HashMap::from([
+                        ("path", score.path.into()),
+                        ("hub_score", score.hub_score.into()),
+                    ])
+                })
+                .collect();
+
+        let decayed: Vec<HashMap<&str, BoltType>> =
+            hub_score::compute_decayed_hub_scores(&data, min_coupling)
+                .into_iter()
+                .map(|score| {
+                    HashMap::from([
+                        ("path", score.path.into()),
+                        ("partner_count", score.partner_count.into()),
+                        ("avg_coupling", score.avg_coupling.into()),
+                        ("hub_score", score.hub_score.into()),
+                    ])
+                })
+                .collect();
+
+        let ownership: Vec<HashMap<&str, BoltType>> = ownership::compute_ownership(&data)
+            .into_iter()
+            .map(|file| {
+                HashMap::from([
+                    ("path", file.path.into()),
+                    ("author_count", file.author_count.into()),
+                    ("main_author_share", file.main_author_share.into()),
+                    ("bus_factor", file.bus_factor.into()),
+                ])
+            })
+            .collect();
+
+        let issue_edges: Vec<HashMap<&str, BoltType>> = issue_cochanges(&data.issues)
+            .into_iter()
+            .map(|edge| {
+                HashMap::from([
+                    ("source", edge.source.into()),
+                    ("target", edge.target.into()),
+                    ("weight", edge.weight.into()),
+                ])
+            })
+            .collect();
+
+        let function_scores: Vec<HashMap<&str, BoltType>> =
+            hub_score::compute_function_hub_scores(&data, min_coupling)
+                .into_iter()
+                .map(|score| {
+                    HashMap::from([
+                        ("path", score.path.into()),
+                        ("name", score.name.into()),
+                        ("partner_count", score.partner_count.into()),
+                        ("avg_coupling", score.avg_coupling.into()),
+                        ("hub_score", score.hub_score.into()),
+                    ])
+                })
+                .collect();
+
+        let graph = self.graph.lock().await;
+        graph
+            .run(
+                query(
+                    "UNWIND $rows AS row \
+                     MATCH (f:File {repo: $repo, path: row.path}) \
+                     SET f.normalized_hub_score = row.hub_score",
+                )
+                .param("repo", repo)
+                .param("rows", normalized),
+            )
+            .await
+            .map_err(|e| format!("Failed to update normalized hub scores: {}", e))?;
+
+        graph
+            .run(
+                query(
+                    "UNWIND $rows AS row \
+                     MATCH (f:File {repo: $repo, path: row.path}) \
+                     SET f.decayed_partner_count = row.partner_count, \
+                         f.decayed_avg_coupling = row.avg_coupling, \
+                         f.decayed_hub_score = row.hub_score",
+                )
+                .param("repo", repo)
+                .param("rows", decayed),
+            )
+            .await
+            .map_err(|e| format!("Failed to update decayed hub scores: {}", e))?;
+
+        graph
+            .run(
+                query(
+                    "UNWIND $rows AS row \
+                     MATCH (f:File {repo: $repo, path: row.path}) \
+                     SET f.author_count = row.author_count, \
+                         f.main_author_share = row.main_author_share, \
+                         f.bus_factor = row.bus_factor",
+                )
+                .param("repo", repo)
+                .param("rows", ownership),
+            )
+            .await
+            .map_err(|e| format!("Failed to update ownership metrics: {}", e))?;
+
+        graph
+            .run(
+                query("MATCH (:File {repo: $repo})-[r:ISSUE_CO_CHANGED]->() DELETE r")
+                    .param("repo", repo),
+            )
+            .await
+            .map_err(|e| format!("Failed to update issue co-changes: {}", e))?;
+        graph
+            .run(
+                query(
+                    "UNWIND $rows AS row \
+                     MATCH (s:File {repo: $repo, path: row.source}) \
+                     MATCH (t:File {repo: $repo, path: row.target}) \
+                     MERGE (s)-[r:ISSUE_CO_CHANGED]->(t) \
+                     SET r.weight = row.weight",
+                )
+                .param("repo", repo)
+                .param("rows", issue_edges),
+            )
+            .await
+            .map_err(|e| format!("Failed to update issue co-changes: {}", e))?;
+
+        graph
+            .run(
+                query(
+                    "MATCH (fn:Function {repo: $repo}) \
+                     REMOVE fn.partner_count, fn.avg_coupling, fn.hub_score",
+                )
+                .param("repo", repo),
+            )
+            .await
+            .map_err(|e| format!("Failed to update function hub scores: {}", e))?;
+        graph
+            .run(
+                query(
+                    "UNWIND $rows AS row \
+                     MATCH (fn:Function {repo: $repo, path: row.path, name: row.name}) \
+                     SET fn.partner_count = row.partner_count, \
+                         fn.avg_coupling = row.avg_coupling, \
+                         fn.hub_score = row.hub_score",
+                )
+                .param("repo", repo)
+                .param("rows", function_scores),
+            )
+            .await
+            .map_err(|e| format!("Failed to update function hub scores: {}", e))?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GraphStore for Neo4jClient {
+    async fn delete_all_nodes(&self) -> Result<(), String> {
         let graph = self.graph.lock().await;
 
         graph
@@ -94,13 +743,57 @@ impl Neo4jClient {
         Ok(())
     }
 
-    pub async fn init_schema(&self) -> Result<(), String> {
+    async fn delete_repository(&self, repo: &str) -> Result<(), String> {
+        let graph = self.graph.lock().await;
+
+        let files_query = query("MATCH (f:File {repo: $repo}) DETACH DELETE f").param("repo", repo);
+        let authors_query =
+            query("MATCH (a:Author {repo: $repo}) DETACH DELETE a").param("repo", repo);
+        let issues_query =
+            query("MATCH (i:Issue {repo: $repo}) DETACH DELETE i").param("repo", repo);
+        let functions_query =
+            query("MATCH (fn:Function {repo: $repo}) DETACH DELETE fn").param("repo", repo);
+        let excluded_query =
+            query("MATCH (e:ExcludedFile {repo: $repo}) DETACH DELETE e").param("repo", repo);
+        let runs_query =
+            query("MATCH (a:AnalysisRun {repo: $repo}) DETACH DELETE a").param("repo", repo);
+        let commits_query =
+            query("MATCH (c:Commit {repo: $repo}) DETACH DELETE c").param("repo", repo);
+        let repo_query =
+            query("MATCH (r:Repository {name: $repo}) DETACH DELETE r").param("repo", repo);
+
+        for q in [
+            files_query,
+            authors_query,
+            issues_query,
+            functions_query,
+            excluded_query,
+            runs_query,
+            commits_query,
+            repo_query,
+        ] {
+            graph
+                .run(q)
+                .await
+                .map_err(|e| format!("Failed to delete repository {}: {}", repo, e))?;
+        }
+
+        Ok(())
+    }
+
+    async fn init_schema(&self) -> Result<(), String> {
         let graph = self.graph.lock().await;
 
         let queries = vec![
             "CREATE CONSTRAINT IF NOT EXISTS FOR (r:Repository) REQUIRE r.name IS UNIQUE",
             "CREATE CONSTRAINT IF NOT EXISTS FOR (f:File) REQUIRE (f.repo, f.path) IS UNIQUE",
             "CREATE INDEX IF NOT EXISTS FOR (f:File) ON (f.repo)",
+            "CREATE CONSTRAINT IF NOT EXISTS FOR (a:Author) REQUIRE (a.repo, a.email) IS UNIQUE",
+            "CREATE CONSTRAINT IF NOT EXISTS FOR (i:Issue) REQUIRE (i.repo, i.key) IS UNIQUE",
+            "CREATE CONSTRAINT IF NOT EXISTS FOR (c:Commit) REQUIRE (c.repo, c.hash) IS UNIQUE",
+            "CREATE CONSTRAINT IF NOT EXISTS FOR (fn:Function) REQUIRE (fn.repo, fn.path, fn.name) IS UNIQUE",
+            "CREATE CONSTRAINT IF NOT EXISTS FOR (e:ExcludedFile) REQUIRE (e.repo, e.path) IS UNIQUE",
+            "CREATE INDEX IF NOT EXISTS FOR (a:AnalysisRun) ON (a.repo)",
         ];
 
         for q in queries {
@@ -113,7 +806,7 @@ impl Neo4jClient {
         Ok(())
     }
 
-    pub async fn save_repository(&self, name: &str, url: Option<&str>) -> Result<(), String> {
+    async fn save_repository(&self, name: &str, url: Option<&str>) -> Result<(), String> {
         let graph = self.graph.lock().await;
 
         let q = if url.is_some() {
@@ -132,7 +825,7 @@ impl Neo4jClient {
         Ok(())
     }
 
-    pub async fn update_commit_count(&self, repo: &str, total_commits: i64) -> Result<(), String> {
+    async fn update_commit_count(&self, repo: &str, total_commits: i64) -> Result<(), String> {
         let graph = self.graph.lock().await;
 
         let q = query("MATCH (r:Repository {name: $repo}) SET r.total_commits = $total_commits")
@@ -147,7 +840,65 @@ impl Neo4jClient {
         Ok(())
     }
 
-    pub async fn save_file_node(
+    async fn get_checkpoint(&self, repo: &str) -> Result<Option<AnalysisCheckpoint>, String> {
+        let graph = self.graph.lock().await;
+
+        let q = query(
+            "MATCH (r:Repository {name: $repo}) WHERE r.last_commit IS NOT NULL \
+             RETURN r.last_commit as last_commit, COALESCE(r.total_commits, 0) as total_commits",
+        )
+        .param("repo", repo);
+
+        let mut result = graph
+            .execute(q)
+            .await
+            .map_err(|e| format!("Failed to query checkpoint: {}", e))?;
+
+        if let Ok(Some(row)) = result.next().await {
+            let last_commit: String = row
+                .get("last_commit")
+                .map_err(|e| format!("Failed to read last commit: {}", e))?;
+            let total_commits: i64 = row.get("total_commits").unwrap_or(0);
+            return Ok(Some(AnalysisCheckpoint {
+                last_commit,
+                total_commits,
+            }));
+        }
+
+        Ok(None)
+    }
+
+    async fn set_last_commit(&self, repo: &str, commit: &str) -> Result<(), String> {
+        let graph = self.graph.lock().await;
+
+        let q = query("MATCH (r:Repository {name: $repo}) SET r.last_commit = $commit")
+            .param("repo", repo)
+            .param("commit", commit);
+
+        graph
+            .run(q)
+            .await
+            .map_err(|e| format!("Failed to save last commit: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn set_analysis_window(&self, repo: &str, window: &str) -> Result<(), String> {
+        let graph = self.graph.lock().await;
+
+        let q = query("MATCH (r:Repository {name: $repo}) SET r.analysis_window = $window")
+            .param("repo", repo)
+            .param("window", window);
+
+        graph
+            .run(q)
+            .await
+            .map_err(|e| format!("Failed to save analysis window: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn save_file_node(
         &self,
         repo: &str,
         path: &str,
@@ -161,6 +912,8 @@ impl Neo4jClient {
             "MERGE (f:File {repo: $repo, path: $path}) \
              SET f.additions = COALESCE(f.additions, 0) + $additions, \
                  f.deletions = COALESCE(f.deletions, 0) + $deletions, \
+                 f.normalized_additions = COALESCE(f.normalized_additions, 0) + $additions, \
+                 f.normalized_deletions = COALESCE(f.normalized_deletions, 0) + $deletions, \
                  f.commit_count = COALESCE(f.commit_count, 0) + 1, \
                  f.deleted_at_commit = COALESCE(f.deleted_at_commit, $deleted_at_commit)",
         )
@@ -178,7 +931,7 @@ impl Neo4jClient {
         Ok(())
     }
 
-    pub async fn rename_file_node(
+    async fn rename_file_node(
         &self,
         repo: &str,
         old_path: &str,
@@ -199,6 +952,16 @@ impl Neo4jClient {
         let conflicting_node_exists = delete_result.next().await.is_ok();
 
         if conflicting_node_exists {
+            let functions_delete_q =
+                query("MATCH (fn:Function {repo: $repo, path: $new_path}) DETACH DELETE fn")
+                    .param("repo", repo)
+                    .param("new_path", new_path);
+
+            graph
+                .run(functions_delete_q)
+                .await
+                .map_err(|e| format!("Failed to delete conflicting new_path node: {}", e))?;
+
             let actual_delete_q =
                 query("MATCH (f:File {repo: $repo, path: $new_path}) DETACH DELETE f")
                     .param("repo", repo)
@@ -222,10 +985,21 @@ impl Neo4jClient {
             .await
             .map_err(|e| format!("Failed to rename file node: {}", e))?;
 
+        let functions_update_q =
+            query("MATCH (fn:Function {repo: $repo, path: $old_path}) SET fn.path = $new_path")
+                .param("repo", repo)
+                .param("old_path", old_path)
+                .param("new_path", new_path);
+
+        graph
+            .run(functions_update_q)
+            .await
+            .map_err(|e| format!("Failed to rename file node: {}", e))?;
+
         Ok(())
     }
 
-    pub async fn save_cochange_relationship(
+    async fn save_cochange_relationship(
         &self,
         repo: &str,
         source: &str,
@@ -248,11 +1022,197 @@ impl Neo4jClient {
         Ok(())
     }
 
-    pub async fn get_graph(&self, repo: &str) -> Result<GraphData, String> {
+    async fn save_commit_batch(&self, repo: &str, batch: &[CommitChanges]) -> Result<(), String> {
+        let graph = self.graph.lock().await;
+
+        let mut txn = graph
+            .start_txn()
+            .await
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        for segment in segment_batch(batch) {
+            Self::run_renames_in_txn(&mut txn, repo, &segment.renames).await?;
+            Self::unwind_file_rows(&mut txn, repo, &segment.files).await?;
+            Self::unwind_cochange_rows(&mut txn, repo, &segment.cochanges).await?;
+            Self::unwind_author_rows(&mut txn, repo, &segment.authors, &segment.file_authors)
+                .await?;
+            Self::unwind_issue_rows(&mut txn, repo, &segment.issue_commits, &segment.issue_files)
+                .await?;
+            Self::unwind_function_rows(
+                &mut txn,
+                repo,
+                &segment.functions,
+                &segment.function_cochanges,
+            )
+            .await?;
+        }
+
+        txn.commit()
+            .await
+            .map_err(|e| format!("Failed to commit batch: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn bulk_load_graph(&self, graph: &FileGraph) -> Result<(), String> {
+        let segment = graph_segment(graph);
+        let client = self.graph.lock().await;
+
+        let mut txn = client
+            .start_txn()
+            .await
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        Self::unwind_file_rows(&mut txn, &graph.repo, &segment.files).await?;
+        Self::unwind_cochange_rows(&mut txn, &graph.repo, &segment.cochanges).await?;
+        Self::unwind_author_rows(
+            &mut txn,
+            &graph.repo,
+            &segment.authors,
+            &segment.file_authors,
+        )
+        .await?;
+        Self::unwind_issue_rows(
+            &mut txn,
+            &graph.repo,
+            &segment.issue_commits,
+            &segment.issue_files,
+        )
+        .await?;
+        Self::unwind_function_rows(
+            &mut txn,
+            &graph.repo,
+            &segment.functions,
+            &segment.function_cochanges,
+        )
+        .await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| format!("Failed to commit bulk load: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn rescale_decay(&self, repo: &str, factor: f64) -> Result<(), String> {
+        let graph = self.graph.lock().await;
+
+        graph
+            .run(
+                query(
+                    "MATCH (f:File {repo: $repo}) \
+                     SET f.decayed_churn = f.decayed_churn * $factor, \
+                         f.decayed_commit_count = f.decayed_commit_count * $factor \
+                     WITH f \
+                     MATCH (f)-[r:CO_CHANGED]->() \
+                     SET r.decayed_weight = r.decayed_weight * $factor",
+                )
+                .param("repo", repo)
+                .param("factor", factor),
+            )
+            .await
+            .map_err(|e| format!("Failed to rescale decayed weights: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn save_collaborations(
+        &self,
+        repo: &str,
+        collaborations: &[Collaboration],
+    ) -> Result<(), String> {
+        let graph = self.graph.lock().await;
+
+        for chunk in collaborations.chunks(UNWIND_CHUNK_SIZE) {
+            let rows: Vec<HashMap<&str, BoltType>> = chunk
+                .iter()
+                .map(|row| {
+                    HashMap::from([
+                        ("source", row.source.as_str().into()),
+                        ("target", row.target.as_str().into()),
+                        ("weight", row.weight.into()),
+                    ])
+                })
+                .collect();
+
+            graph
+                .run(
+                    query(
+                        "UNWIND $rows AS row \
+                         MATCH (a1:Author {repo: $repo, email: row.source}) \
+                         MATCH (a2:Author {repo: $repo, email: row.target}) \
+                         MERGE (a1)-[r:COLLABORATED]->(a2) \
+                         SET r.weight = COALESCE(r.weight, 0) + row.weight",
+                    )
+                    .param("repo", repo)
+                    .param("rows", rows),
+                )
+                .await
+                .map_err(|e| format!("Failed to save collaborations: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    async fn save_excluded_files(&self, repo: &str, files: &[ExcludedFile]) -> Result<(), String> {
+        let graph = self.graph.lock().await;
+
+        for chunk in files.chunks(UNWIND_CHUNK_SIZE) {
+            let rows: Vec<HashMap<&str, BoltType>> = chunk
+                .iter()
+                .map(|row| {
+                    HashMap::from([
+                        ("path", row.path.as_str().into()),
+                        ("reason", row.reason.as_str().into()),
+                    ])
+                })
+                .collect();
+
+            graph
+                .run(
+                    query(
+                        "UNWIND $rows AS row \
+                         MERGE (e:ExcludedFile {repo: $repo, path: row.path}) \
+                         SET e.reason = row.reason \
+                         WITH e \
+                         MATCH (r:Repository {name: $repo}) \
+                         MERGE (r)-[:EXCLUDES]->(e)",
+                    )
+                    .param("repo", repo)
+                    .param("rows", rows),
+                )
+                .await
+                .map_err(|e| format!("Failed to save excluded files: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    async fn save_analysis_run(&self, repo: &str, run: &AnalysisRun) -> Result<(), String> {
+        let graph = self.graph.lock().await;
+
+        graph
+            .run(
+                query(
+                    "MATCH (r:Repository {name: $repo}) \
+                     CREATE (r)-[:ANALYSED_WITH]->(:AnalysisRun \
+                     {repo: $repo, started_at: $started_at, config: $config})",
+                )
+                .param("repo", repo)
+                .param("started_at", run.started_at.as_str())
+                .param("config", run.config.as_str()),
+            )
+            .await
+            .map_err(|e| format!("Failed to save analysis run: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn get_graph(&self, repo: &str) -> Result<GraphData, String> {
         let graph = self.graph.lock().await;
 
         let files_query = query(
-            "MATCH (f:File {repo: $repo}) RETURN f.path as path, f.additions as additions, f.deletions as deletions, f.commit_count as commit_count",
+            "MATCH (f:File {repo: $repo}) RETURN f.path as path, f.additions as additions, f.deletions as deletions, f.normalized_additions as normalized_additions, f.normalized_deletions as normalized_deletions, f.commit_count as commit_count, f.deleted_at_commit as deleted_at_commit, f.decayed_churn as decayed_churn, f.decayed_commit_count as decayed_commit_count, f.bugfix_commits as bugfix_commits, f.feature_commits as feature_commits, f.refactor_commits as refactor_commits, f.docs_commits as docs_commits, f.merge_commits as merge_commits",
         )
         .param("repo", repo);
 
@@ -266,18 +1226,94 @@ impl Neo4jClient {
             let path: String = row.get::<String>("path").unwrap_or_default();
             let additions: i64 = row.get::<i64>("additions").unwrap_or(0);
             let deletions: i64 = row.get::<i64>("deletions").unwrap_or(0);
+            let normalized_additions: i64 =
+                row.get::<i64>("normalized_additions").unwrap_or(additions);
+            let normalized_deletions: i64 =
+                row.get::<i64>("normalized_deletions").unwrap_or(deletions);
             let commit_count: i64 = row.get::<i64>("commit_count").unwrap_or(0);
+            let deleted_at_commit: Option<String> = row.get::<String>("deleted_at_commit").ok();
+            let decayed_churn: Option<f64> = row.get::<f64>("decayed_churn").ok();
+            let decayed_commit_count: Option<f64> = row.get::<f64>("decayed_commit_count").ok();
 
             files.push(FileNode {
                 path,
                 additions,
                 deletions,
+                normalized_additions,
+                normalized_deletions,
                 commit_count,
+                deleted_at_commit,
+                decayed_churn,
+                decayed_commit_count,
+                authors: Vec::new(),
+                commit_classes: class_counts(&row),
+            });
+        }
+
+        let file_authors_query = query(
+            "MATCH (a:Author {repo: $repo})-[r:AUTHORED]->(f:File {repo: $repo}) RETURN f.path as path, a.email as email, r.commits as commits, r.churn as churn",
+        )
+        .param("repo", repo);
+
+        let mut file_authors_result = graph
+            .execute(file_authors_query)
+            .await
+            .map_err(|e| format!("Failed to query file authors: {}", e))?;
+
+        let mut authors_by_file: HashMap<String, Vec<FileAuthor>> = HashMap::new();
+        while let Ok(Some(row)) = file_authors_result.next().await {
+            let path: String = row.get::<String>("path").unwrap_or_default();
+            authors_by_file.entry(path).or_default().push(FileAuthor {
+                email: row.get::<String>("email").unwrap_or_default(),
+                commits: row.get::<i64>("commits").unwrap_or(0),
+                churn: row.get::<i64>("churn").unwrap_or(0),
+            });
+        }
+        for file in &mut files {
+            file.authors = authors_by_file.remove(&file.path).unwrap_or_default();
+        }
+
+        let authors_query = query(
+            "MATCH (a:Author {repo: $repo}) RETURN a.email as email, a.name as name, a.commit_count as commit_count, a.committed_count as committed_count ORDER BY email",
+        )
+        .param("repo", repo);
+
+        let mut authors_result = graph
+            .execute(authors_query)
+            .await
+            .map_err(|e| format!("Failed to query authors: {}", e))?;
+
+        let mut authors = Vec::new();
+        while let Ok(Some(row)) = authors_result.next().await {
+            authors.push(Author {
+                email: row.get::<String>("email").unwrap_or_default(),
+                name: row.get::<String>("name").unwrap_or_default(),
+                commit_count: row.get::<i64>("commit_count").unwrap_or(0),
+                committed_count: row.get::<i64>("committed_count").unwrap_or(0),
+            });
+        }
+
+        let collaborations_query = query(
+            "MATCH (a1:Author {repo: $repo})-[r:COLLABORATED]->(a2:Author {repo: $repo}) RETURN a1.email as source, a2.email as target, r.weight as weight ORDER BY source, target",
+        )
+        .param("repo", repo);
+
+        let mut collaborations_result = graph
+            .execute(collaborations_query)
+            .await
+            .map_err(|e| format!("Failed to query collaborations: {}", e))?;
+
+        let mut collaborations = Vec::new();
+        while let Ok(Some(row)) = collaborations_result.next().await {
+            collaborations.push(Collaboration {
+                source: row.get::<String>("source").unwrap_or_default(),
+                target: row.get::<String>("target").unwrap_or_default(),
+                weight: row.get::<i64>("weight").unwrap_or(0),
             });
         }
 
         let edges_query = query(
-            "MATCH (f1:File {repo: $repo})-[r:CO_CHANGED]->(f2:File {repo: $repo}) RETURN f1.path as source, f2.path as target, r.weight as weight",
+            "MATCH (f1:File {repo: $repo})-[r:CO_CHANGED]->(f2:File {repo: $repo}) RETURN f1.path as source, f2.path as target, r.weight as weight, r.decayed_weight as decayed_weight",
         )
         .param("repo", repo);
 
@@ -291,16 +1327,150 @@ impl Neo4jClient {
             let source: String = row.get::<String>("source").unwrap_or_default();
             let target: String = row.get::<String>("target").unwrap_or_default();
             let weight: i64 = row.get::<i64>("weight").unwrap_or(0);
+            let decayed_weight: Option<f64> = row.get::<f64>("decayed_weight").ok();
 
             edges.push(Edge {
                 source,
                 target,
                 weight,
+                decayed_weight,
+            });
+        }
+
+        let issues_query = query(
+            "MATCH (i:Issue {repo: $repo}) \
+             RETURN i.key as key, \
+                    [(c:Commit)-[:REFERENCES]->(i) | c.hash] as commits, \
+                    [(i)-[r:TOUCHES]->(f:File) | [f.path, r.commits]] as files \
+             ORDER BY key",
+        )
+        .param("repo", repo);
+
+        let mut issues_result = graph
+            .execute(issues_query)
+            .await
+            .map_err(|e| format!("Failed to query issues: {}", e))?;
+
+        let mut issues = Vec::new();
+        while let Ok(Some(row)) = issues_result.next().await {
+            let files: Vec<(String, i64)> =
+                row.get::<Vec<(String, i64)>>("files").unwrap_or_default();
+            issues.push(Issue {
+                key: row.get::<String>("key").unwrap_or_default(),
+                commits: row.get::<Vec<String>>("commits").unwrap_or_default(),
+                files: files
+                    .into_iter()
+                    .map(|(path, commits)| IssueFile { path, commits })
+                    .collect(),
+            });
+        }
+
+        let issue_edges_query = query(
+            "MATCH (f1:File {repo: $repo})-[r:ISSUE_CO_CHANGED]->(f2:File {repo: $repo}) RETURN f1.path as source, f2.path as target, r.weight as weight",
+        )
+        .param("repo", repo);
+
+        let mut issue_edges_result = graph
+            .execute(issue_edges_query)
+            .await
+            .map_err(|e| format!("Failed to query issue co-changes: {}", e))?;
+
+        let mut issue_edges = Vec::new();
+        while let Ok(Some(row)) = issue_edges_result.next().await {
+            issue_edges.push(Edge {
+                source: row.get::<String>("source").unwrap_or_default(),
+                target: row.get::<String>("target").unwrap_or_default(),
+                weight: row.get::<i64>("weight").unwrap_or(0),
+                decayed_weight: None,
+            });
+        }
+
+        let functions_query = query(
+            "MATCH (fn:Function {repo: $repo}) \
+             RETURN fn.path as path, fn.name as name, fn.commit_count as commit_count, \
+                    fn.churn as churn \
+             ORDER BY path, name",
+        )
+        .param("repo", repo);
+
+        let mut functions_result = graph
+            .execute(functions_query)
+            .await
+            .map_err(|e| format!("Failed to query functions: {}", e))?;
+
+        let mut functions = Vec::new();
+        while let Ok(Some(row)) = functions_result.next().await {
+            functions.push(Function {
+                path: row.get::<String>("path").unwrap_or_default(),
+                name: row.get::<String>("name").unwrap_or_default(),
+                commit_count: row.get::<i64>("commit_count").unwrap_or(0),
+                churn: row.get::<i64>("churn").unwrap_or(0),
+            });
+        }
+
+        let function_edges_query = query(
+            "MATCH (a:Function {repo: $repo})-[r:CO_CHANGED]->(b:Function {repo: $repo}) \
+             RETURN a.path as source_path, a.name as source_name, \
+                    b.path as target_path, b.name as target_name, r.weight as weight",
+        )
+        .param("repo", repo);
+
+        let mut function_edges_result = graph
+            .execute(function_edges_query)
+            .await
+            .map_err(|e| format!("Failed to query function co-changes: {}", e))?;
+
+        let mut function_edges = Vec::new();
+        while let Ok(Some(row)) = function_edges_result.next().await {
+            function_edges.push(FunctionEdge {
+                source_path: row.get::<String>("source_path").unwrap_or_default(),
+                source_name: row.get::<String>("source_name").unwrap_or_default(),
+                target_path: row.get::<String>("target_path").unwrap_or_default(),
+                target_name: row.get::<String>("target_name").unwrap_or_default(),
+                weight: row.get::<i64>("weight").unwrap_or(0),
+            });
+        }
+
+        let excluded_query = query(
+            "MATCH (e:ExcludedFile {repo: $repo}) \
+             RETURN e.path as path, e.reason as reason ORDER BY path",
+        )
+        .param("repo", repo);
+
+        let mut excluded_result = graph
+            .execute(excluded_query)
+            .await
+            .map_err(|e| format!("Failed to query excluded files: {}", e))?;
+
+        let mut excluded_files = Vec::new();
+        while let Ok(Some(row)) = excluded_result.next().await {
+            excluded_files.push(ExcludedFile {
+                path: row.get::<String>("path").unwrap_or_default(),
+                reason: row.get::<String>("reason").unwrap_or_default(),
+            });
+        }
+
+        let runs_query = query(
+            "MATCH (a:AnalysisRun {repo: $repo}) \
+             RETURN a.started_at as started_at, a.config as config ORDER BY started_at",
+        )
+        .param("repo", repo);
+
+        let mut runs_result = graph
+            .execute(runs_query)
+            .await
+            .map_err(|e| format!("Failed to query analysis runs: {}", e))?;
+
+        let mut analysis_runs = Vec::new();
+        while let Ok(Some(row)) = runs_result.next().await {
+            analysis_runs.push(AnalysisRun {
+                started_at: row.get::<String>("started_at").unwrap_or_default(),
+                config: row.get::<String>("config").unwrap_or_default(),
             });
         }
 
         let count_query =
-            query("MATCH (r:Repository {name: $repo}) RETURN r.total_commits as total_commits")
+            query("MATCH (r:Repository {name: $repo}) RETURN r.total_commits as total_commits, r.analysis_window as window")
                 .param("repo", repo);
 
         let mut count_result = graph
@@ -308,10 +1478,13 @@ impl Neo4jClient {
             .await
             .map_err(|e| format!("Failed to query commit count: {}", e))?;
 
-        let total_commits = if let Ok(Some(row)) = count_result.next().await {
-            row.get::<i64>("total_commits").unwrap_or(0)
+        let (total_commits, window) = if let Ok(Some(row)) = count_result.next().await {
+            (
+                row.get::<i64>("total_commits").unwrap_or(0),
+                row.get::<String>("window").ok(),
+            )
         } else {
-            0
+            (0, None)
         };
 
         Ok(GraphData {
@@ -319,10 +1492,19 @@ impl Neo4jClient {
             total_commits_analyzed: total_commits,
             files,
             edges,
+            window,
+            authors,
+            collaborations,
+            issues,
+            issue_edges,
+            functions,
+            function_edges,
+            excluded_files,
+            analysis_runs,
         })
     }
 
-    pub async fn link_file_to_repo(&self, repo: &str, file_path: &str) -> Result<(), String> {
+    async fn link_file_to_repo(&self, repo: &str, file_path: &str) -> Result<(), String> {
         let graph = self.graph.lock().await;
 
         let q = query(
@@ -339,15 +1521,20 @@ impl Neo4jClient {
         Ok(())
     }
 
-    pub async fn remove_low_importance_connections(&self, threshold: i64) -> Result<u64, String> {
+    async fn remove_low_importance_connections(
+        &self,
+        repo: &str,
+        threshold: i64,
+    ) -> Result<u64, String> {
         let graph = self.graph.lock().await;
 
         let q = query(
-            "MATCH (f1:File)-[r:CO_CHANGED]->(f2:File)
+            "MATCH (f1:File {repo: $repo})-[r:CO_CHANGED]->(f2:File {repo: $repo})
      WHERE r.weight < $threshold
      DELETE r
      RETURN count(r) as deleted_count",
         )
+        .param("repo", repo)
         .param("threshold", threshold);
 
         let mut result = graph
@@ -361,34 +1548,58 @@ impl Neo4jClient {
             0
         };
 
-        let q2 = query(
-            "
-     MATCH (f:File)
-     WHERE NOT (f)-[:CO_CHANGED]->(:File) AND NOT (:File)-[:CO_CHANGED]->(f)
-     DETACH DELETE f
- ",
-        );
+        Ok(deleted_edges)
+    }
 
-        graph
-            .run(q2)
-            .await
-            .map_err(|e| format!("Failed to remove orphan files: {}", e))?;
+    async fn remove_orphan_files(&self, repo: &str) -> Result<u64, String> {
+        let graph = self.graph.lock().await;
+
+        let orphan_query = query(
+            "MATCH (f:File {repo: $repo}) \
+             WHERE NOT (f)-[:CO_CHANGED]->(:File) AND NOT (:File)-[:CO_CHANGED]->(f) \
+             DETACH DELETE f \
+             RETURN count(f) as deleted_count",
+        )
+        .param("repo", repo);
 
         let zero_churn_query = query(
-            "MATCH (f:File) \
+            "MATCH (f:File {repo: $repo}) \
              WHERE f.additions = 0 AND f.deletions = 0 AND f.deleted_at_commit IS NULL \
-             DETACH DELETE f",
-        );
+             DETACH DELETE f \
+             RETURN count(f) as deleted_count",
+        )
+        .param("repo", repo);
 
+        let mut removed = 0u64;
+        for (q, what) in [
+            (orphan_query, "orphan files"),
+            (zero_churn_query, "zero-churn files"),
+        ] {
+            let mut result = graph
+                .execute(q)
+                .await
+                .map_err(|e| format!("Failed to remove {}: {}", what, e))?;
+            if let Ok(Some(row)) = result.next().await {
+                removed += row.get::<i64>("deleted_count").unwrap_or(0) as u64;
+            }
+        }
+
+        // Functions go with their file but are not counted as removed files
         graph
-            .run(zero_churn_query)
+            .run(
+                query(
+                    "MATCH (fn:Function {repo: $repo}) WHERE NOT (:File)-[:DECLARES]->(fn) \
+                     DETACH DELETE fn",
+                )
+                .param("repo", repo),
+            )
             .await
-            .map_err(|e| format!("Failed to prune zero-churn files: {}", e))?;
+            .map_err(|e| format!("Failed to remove orphan functions: {}", e))?;
 
-        Ok(deleted_edges)
+        Ok(removed)
     }
 
-    pub async fn compute_hub_scores(&self, repo: &str, min_coupling: f64) -> Result<(), String> {
+    async fn compute_hub_scores(&self, repo: &str, min_coupling: f64) -> Result<(), String> {
         let graph = self.graph.lock().await;
 
         let totals_query = query(
@@ -462,10 +1673,12 @@ impl Neo4jClient {
                 .await
                 .map_err(|e| format!("Failed to update hub score: {}", e))?;
         }
-        Ok(())
+        drop(graph);
+
+        self.compute_graph_metrics(repo, min_coupling).await
     }
 
-    pub async fn get_all_repo_names(&self) -> Result<Vec<String>, String> {
+    async fn get_all_repo_names(&self) -> Result<Vec<String>, String> {
         let graph = self.graph.lock().await;
 
         let q = query("MATCH (f:File) RETURN distinct f.repo as repo ORDER BY repo");
@@ -486,7 +1699,7 @@ impl Neo4jClient {
         Ok(repos)
     }
 
-    pub async fn link_all_files_to_repo(&self, repo: &str) -> Result<(), String> {
+    async fn link_all_files_to_repo(&self, repo: &str) -> Result<(), String> {
         let graph = self.graph.lock().await;
 
         let q = query(
@@ -502,7 +1715,7 @@ impl Neo4jClient {
         Ok(())
     }
 
-    pub async fn get_all_hub_scores(&self, extension: &str) -> Result<Vec<HubScoreData>, String> {
+    async fn get_all_hub_scores(&self, extension: &str) -> Result<Vec<HubScoreData>, String> {
         let graph = self.graph.lock().await;
 
         let pattern = format!("{}", extension);
@@ -510,10 +1723,19 @@ impl Neo4jClient {
         let q = query(
             "MATCH (f:File) \
              WHERE f.path ENDS WITH $ext AND f.deleted_at_commit IS NULL \
+             OPTIONAL MATCH (r:Repository {name: f.repo}) \
              RETURN f.repo as repo, f.path as path, f.hub_score as hub_score, \
                     f.avg_coupling as avg_coupling, f.commit_count as commit_count, \
                     f.partner_count as partner_count, f.additions as additions, \
-                    f.deletions as deletions \
+                    f.deletions as deletions, r.analysis_window as window, \
+                    f.normalized_hub_score as normalized_hub_score, \
+                    f.decayed_hub_score as decayed_hub_score, \
+                    f.decayed_avg_coupling as decayed_avg_coupling, \
+                    f.decayed_partner_count as decayed_partner_count, \
+                    f.author_count as author_count, f.main_author_share as main_author_share, \
+                    f.bus_factor as bus_factor, f.bugfix_commits as bugfix_commits, \
+                    f.feature_commits as feature_commits, f.refactor_commits as refactor_commits, \
+                    f.docs_commits as docs_commits, f.merge_commits as merge_commits \
              ORDER BY f.hub_score DESC",
         )
         .param("ext", pattern);
@@ -533,6 +1755,14 @@ impl Neo4jClient {
             let partner_count: i64 = row.get::<i64>("partner_count").unwrap_or(0);
             let additions: i64 = row.get::<i64>("additions").unwrap_or(0);
             let deletions: i64 = row.get::<i64>("deletions").unwrap_or(0);
+            let window: Option<String> = row.get::<String>("window").ok();
+            let normalized_hub_score: Option<f64> = row.get::<f64>("normalized_hub_score").ok();
+            let decayed_hub_score: Option<f64> = row.get::<f64>("decayed_hub_score").ok();
+            let decayed_avg_coupling: Option<f64> = row.get::<f64>("decayed_avg_coupling").ok();
+            let decayed_partner_count: Option<i64> = row.get::<i64>("decayed_partner_count").ok();
+            let author_count: Option<i64> = row.get::<i64>("author_count").ok();
+            let main_author_share: Option<f64> = row.get::<f64>("main_author_share").ok();
+            let bus_factor: Option<i64> = row.get::<i64>("bus_factor").ok();
 
             files.push(HubScoreData {
                 repo,
@@ -542,14 +1772,63 @@ impl Neo4jClient {
                 commit_count,
                 partner_count,
                 churn: additions + deletions,
+                window,
+                normalized_hub_score,
+                decayed_hub_score,
+                decayed_avg_coupling,
+                decayed_partner_count,
+                author_count,
+                main_author_share,
+                bus_factor,
+                commit_classes: class_counts(&row),
             });
         }
 
         Ok(files)
     }
 
-    pub async fn get_files_by_hub_threshold(
+    async fn get_all_function_hub_scores(
         &self,
+        extension: &str,
+    ) -> Result<Vec<FunctionHubScoreData>, String> {
+        let graph = self.graph.lock().await;
+
+        let q = query(
+            "MATCH (f:File)-[:DECLARES]->(fn:Function) \
+             WHERE f.path ENDS WITH $ext AND f.deleted_at_commit IS NULL \
+             RETURN fn.repo as repo, fn.path as path, fn.name as name, \
+                    fn.hub_score as hub_score, fn.avg_coupling as avg_coupling, \
+                    fn.commit_count as commit_count, fn.partner_count as partner_count, \
+                    fn.churn as churn \
+             ORDER BY fn.hub_score DESC",
+        )
+        .param("ext", extension);
+
+        let mut result = graph
+            .execute(q)
+            .await
+            .map_err(|e| format!("Failed to get function hub scores: {}", e))?;
+
+        let mut functions = Vec::new();
+        while let Ok(Some(row)) = result.next().await {
+            functions.push(FunctionHubScoreData {
+                repo: row.get::<String>("repo").unwrap_or_default(),
+                path: row.get::<String>("path").unwrap_or_default(),
+                name: row.get::<String>("name").unwrap_or_default(),
+                hub_score: row.get::<f64>("hub_score").unwrap_or(0.0),
+                avg_coupling: row.get::<f64>("avg_coupling").unwrap_or(0.0),
+                commit_count: row.get::<i64>("commit_count").unwrap_or(0),
+                partner_count: row.get::<i64>("partner_count").unwrap_or(0),
+                churn: row.get::<i64>("churn").unwrap_or(0),
+            });
+        }
+
+        Ok(functions)
+    }
+
+    async fn get_files_by_hub_threshold(
+        &self,
+        repo: Option<&str>,
         hub_threshold: f64,
         extension: &str,
         ignore_repos: &[String],
@@ -562,6 +1841,9 @@ impl Neo4jClient {
             "f.deleted_at_commit IS NULL".to_string(),
             "f.path ENDS WITH $ext".to_string(),
         ];
+        if repo.is_some() {
+            where_clauses.push("f.repo = $repo".to_string());
+        }
         for (i, _) in ignore_repos.iter().enumerate() {
             where_clauses.push(format!(
                 "NOT toLower(f.repo) CONTAINS toLower($ignore_{})",
@@ -582,6 +1864,9 @@ impl Neo4jClient {
         .param("ext", pattern);
 
         let mut q = q;
+        if let Some(repo) = repo {
+            q = q.param("repo", repo);
+        }
         for (i, ignore) in ignore_repos.iter().enumerate() {
             q = q.param(&format!("ignore_{}", i), ignore.as_str());
         }
@@ -0,0 +1,1517 @@
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::Mutex;
+
+use super::{
+    graph_segment, segment_batch, AnalysisCheckpoint, AnalysisRun, Author, BatchSegment,
+    Collaboration, Edge, ExcludedFile, FileAuthor, FileNode, Function, FunctionEdge,
+    FunctionHubScoreData, GraphData, GraphStore, HubScoreData, Issue, IssueFile, RepoFile,
+    RepoWithFiles,
+};
+use crate::commit_class::ClassCounts;
+use crate::file_graph::{CommitChanges, FileGraph};
+use crate::hub_score;
+use crate::issue_refs::issue_cochanges;
+use crate::ownership;
+
+/// Embedded graph store that keeps a whole corpus in a single SQLite file. The tables
+/// mirror the Neo4j schema: `repositories` (Repository), `files` (File), `co_changed`
+/// (CO_CHANGED with weight), `contains` (CONTAINS), `authors` (Author), `file_authors`
+/// (AUTHORED), `collaborations` (COLLABORATED), `issue_commits` (Issue REFERENCES from
+/// Commit), `issue_files` (TOUCHES), `issue_co_changed` (ISSUE_CO_CHANGED), `functions`
+/// (Function, DECLARES from its file) and `function_co_changed` (CO_CHANGED between
+/// functions), `excluded_files` (ExcludedFile, EXCLUDES from the repository) and
+/// `analysis_runs` (AnalysisRun, ANALYSED_WITH from the repository).
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let conn = Connection::open(path)
+            .map_err(|e| format!("Failed to open SQLite database {}: {}", path, e))?;
+
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")
+            .map_err(|e| format!("Failed to configure SQLite database: {}", e))?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+// Same conflict resolution as Neo4j: the node already living at new_path is detached and
+// deleted before the old node takes over its path.
+fn rename_in(
+    conn: &Connection,
+    repo: &str,
+    old_path: &str,
+    new_path: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM co_changed WHERE repo = ?1 AND (source = ?2 OR target = ?2)",
+        params![repo, new_path],
+    )?;
+    conn.execute(
+        "DELETE FROM contains WHERE repo = ?1 AND path = ?2",
+        params![repo, new_path],
+    )?;
+    conn.execute(
+        "DELETE FROM file_authors WHERE repo = ?1 AND path = ?2",
+        params![repo, new_path],
+    )?;
+    conn.execute(
+        "DELETE FROM issue_files WHERE repo = ?1 AND path = ?2",
+        params![repo, new_path],
+    )?;
+    conn.execute(
+        "DELETE FROM issue_co_changed WHERE repo = ?1 AND (source = ?2 OR target = ?2)",
+        params![repo, new_path],
+    )?;
+    conn.execute(
+        "DELETE FROM function_co_changed WHERE repo = ?1 \
+         AND (source_path = ?2 OR target_path = ?2)",
+        params![repo, new_path],
+    )?;
+    conn.execute(
+        "DELETE FROM functions WHERE repo = ?1 AND path = ?2",
+        params![repo, new_path],
+    )?;
+    conn.execute(
+        "DELETE FROM files WHERE repo = ?1 AND path = ?2",
+        params![repo, new_path],
+    )?;
+
+    conn.execute(
+        "UPDATE files SET path = ?3 WHERE repo = ?1 AND path = ?2",
+        params![repo, old_path, new_path],
+    )?;
+    conn.execute(
+        "UPDATE co_changed SET source = ?3 WHERE repo = ?1 AND source = ?2",
+        params![repo, old_path, new_path],
+    )?;
+    conn.execute(
+        "UPDATE co_changed SET target = ?3 WHERE repo = ?1 AND target = ?2",
+        params![repo, old_path, new_path],
+    )?;
+    conn.execute(
+        "UPDATE contains SET path = ?3 WHERE repo = ?1 AND path = ?2",
+        params![repo, old_path, new_path],
+    )?;
+    conn.execute(
+        "UPDATE file_authors SET path = ?3 WHERE repo = ?1 AND path = ?2",
+        params![repo, old_path, new_path],
+    )?;
+    conn.execute(
+        "UPDATE issue_files SET path = ?3 WHERE repo = ?1 AND path = ?2",
+        params![repo, old_path, new_path],
+    )?;
+    conn.execute(
+        "UPDATE issue_co_changed SET source = ?3 WHERE repo = ?1 AND source = ?2",
+        params![repo, old_path, new_path],
+    )?;
+    conn.execute(
+        "UPDATE issue_co_changed SET target = ?3 WHERE repo = ?1 AND target = ?2",
+        params![repo, old_path, new_path],
+    )?;
+    conn.execute(
+        "UPDATE functions SET path = ?3 WHERE repo = ?1 AND path = ?2",
+        params![repo, old_path, new_path],
+    )?;
+    conn.execute(
+        "UPDATE function_co_changed SET source_path = ?3 WHERE repo = ?1 AND source_path = ?2",
+        params![repo, old_path, new_path],
+    )?;
+    conn.execute(
+        "UPDATE function_co_changed SET target_path = ?3 WHERE repo = ?1 AND target_path = ?2",
+        params![repo, old_path, new_path],
+    )?;
+
+    Ok(())
+}
+
+fn write_segment(conn: &Connection, repo: &str, segment: &BatchSegment) -> Result<(), String> {
+    for (old_path, new_path) in &segment.renames {
+        rename_in(conn, repo, old_path, new_path)
+            .map_err(|e| format!("Failed to rename file node: {}", e))?;
+    }
+
+    let mut file_stmt = conn
+        .prepare_cached(UPSERT_FILE)
+        .map_err(|e| format!("Failed to save file nodes: {}", e))?;
+    for row in &segment.files {
+        file_stmt
+            .execute(params![
+                repo,
+                row.path,
+                row.additions,
+                row.deletions,
+                row.commit_count,
+                row.deleted_at_commit,
+                row.decayed_churn,
+                row.decayed_commit_count,
+                row.commit_classes.bugfix_commits,
+                row.commit_classes.feature_commits,
+                row.commit_classes.refactor_commits,
+                row.commit_classes.docs_commits,
+                row.commit_classes.merge_commits,
+                row.normalized_additions,
+                row.normalized_deletions
+            ])
+            .map_err(|e| format!("Failed to save file nodes: {}", e))?;
+    }
+
+    let mut cochange_stmt = conn
+        .prepare_cached(UPSERT_COCHANGE)
+        .map_err(|e| format!("Failed to save co-change relationships: {}", e))?;
+    for row in &segment.cochanges {
+        cochange_stmt
+            .execute(params![
+                repo,
+                row.source,
+                row.target,
+                row.weight,
+                row.decayed_weight
+            ])
+            .map_err(|e| format!("Failed to save co-change relationships: {}", e))?;
+    }
+
+    let mut author_stmt = conn
+        .prepare_cached(UPSERT_AUTHOR)
+        .map_err(|e| format!("Failed to save authors: {}", e))?;
+    for row in &segment.authors {
+        author_stmt
+            .execute(params![
+                repo,
+                row.email,
+                row.name,
+                row.commit_count,
+                row.committed_count
+            ])
+            .map_err(|e| format!("Failed to save authors: {}", e))?;
+    }
+
+    let mut file_author_stmt = conn
+        .prepare_cached(UPSERT_FILE_AUTHOR)
+        .map_err(|e| format!("Failed to save file authors: {}", e))?;
+    for row in &segment.file_authors {
+        file_author_stmt
+            .execute(params![repo, row.path, row.email, row.commits, row.churn])
+            .map_err(|e| format!("Failed to save file authors: {}", e))?;
+    }
+
+    let mut issue_commit_stmt = conn
+        .prepare_cached(
+            "INSERT OR IGNORE INTO issue_commits (repo, key, commit_hash) VALUES (?1, ?2, ?3)",
+        )
+        .map_err(|e| format!("Failed to save issue references: {}", e))?;
+    for row in &segment.issue_commits {
+        issue_commit_stmt
+            .execute(params![repo, row.key, row.commit_hash])
+            .map_err(|e| format!("Failed to save issue references: {}", e))?;
+    }
+
+    let mut issue_file_stmt = conn
+        .prepare_cached(UPSERT_ISSUE_FILE)
+        .map_err(|e| format!("Failed to save issue files: {}", e))?;
+    for row in &segment.issue_files {
+        issue_file_stmt
+            .execute(params![repo, row.key, row.path, row.commits])
+            .map_err(|e| format!("Failed to save issue files: {}", e))?;
+    }
+
+    let mut function_stmt = conn
+        .prepare_cached(UPSERT_FUNCTION)
+        .map_err(|e| format!("Failed to save functions: {}", e))?;
+    for row in &segment.functions {
+        function_stmt
+            .execute(params![
+                repo,
+                row.path,
+                row.name,
+                row.commit_count,
+                row.churn
+            ])
+            .map_err(|e| format!("Failed to save functions: {}", e))?;
+    }
+
+    let mut function_cochange_stmt = conn
+        .prepare_cached(UPSERT_FUNCTION_COCHANGE)
+        .map_err(|e| format!("Failed to save function co-changes: {}", e))?;
+    for row in &segment.function_cochanges {
+        function_cochange_stmt
+            .execute(params![
+                repo,
+                row.source_path,
+                row.source_name,
+                row.target_path,
+                row.target_name,
+                row.weight
+            ])
+            .map_err(|e| format!("Failed to save function co-changes: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// Decayed values are NULL when decay is off; COALESCE keeps whichever side has one
+const UPSERT_FILE: &str = "INSERT INTO files (repo, path, additions, deletions, commit_count, \
+         deleted_at_commit, decayed_churn, decayed_commit_count, bugfix_commits, \
+         feature_commits, refactor_commits, docs_commits, merge_commits, normalized_additions, \
+         normalized_deletions) \
+     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15) \
+     ON CONFLICT(repo, path) DO UPDATE SET \
+         additions = additions + excluded.additions, \
+         deletions = deletions + excluded.deletions, \
+         normalized_additions = normalized_additions + excluded.normalized_additions, \
+         normalized_deletions = normalized_deletions + excluded.normalized_deletions, \
+         commit_count = commit_count + excluded.commit_count, \
+         deleted_at_commit = COALESCE(deleted_at_commit, excluded.deleted_at_commit), \
+         decayed_churn = COALESCE(decayed_churn + excluded.decayed_churn, \
+             decayed_churn, excluded.decayed_churn), \
+         decayed_commit_count = COALESCE(decayed_commit_count + excluded.decayed_commit_count, \
+             decayed_commit_count, excluded.decayed_commit_count), \
+         bugfix_commits = bugfix_commits + excluded.bugfix_commits, \
+         feature_commits = feature_commits + excluded.feature_commits, \
+         refactor_commits = refactor_commits + excluded.refactor_commits, \
+         docs_commits = docs_commits + excluded.docs_commits, \
+         merge_commits = merge_commits + excluded.merge_commits";
+
+const UPSERT_COCHANGE: &str =
+    "INSERT INTO co_changed (repo, source, target, weight, decayed_weight) \
+     SELECT ?1, ?2, ?3, ?4, ?5 \
+     WHERE EXISTS (SELECT 1 FROM files WHERE repo = ?1 AND path = ?2) \
+       AND EXISTS (SELECT 1 FROM files WHERE repo = ?1 AND path = ?3) \
+     ON CONFLICT(repo, source, target) DO UPDATE SET weight = weight + excluded.weight, \
+         decayed_weight = COALESCE(decayed_weight + excluded.decayed_weight, \
+             decayed_weight, excluded.decayed_weight)";
+
+const UPSERT_AUTHOR: &str =
+    "INSERT INTO authors (repo, email, name, commit_count, committed_count) \
+     VALUES (?1, ?2, ?3, ?4, ?5) \
+     ON CONFLICT(repo, email) DO UPDATE SET name = excluded.name, \
+         commit_count = commit_count + excluded.commit_count, \
+         committed_count = committed_count + excluded.committed_count";
+
+const UPSERT_FILE_AUTHOR: &str = "INSERT INTO file_authors (repo, path, email, commits, churn) \
+     SELECT ?1, ?2, ?3, ?4, ?5 \
+     WHERE EXISTS (SELECT 1 FROM files WHERE repo = ?1 AND path = ?2) \
+     ON CONFLICT(repo, path, email) DO UPDATE SET commits = commits + excluded.commits, \
+         churn = churn + excluded.churn";
+
+const UPSERT_ISSUE_FILE: &str = "INSERT INTO issue_files (repo, key, path, commits) \
+     SELECT ?1, ?2, ?3, ?4 \
+     WHERE EXISTS (SELECT 1 FROM files WHERE repo = ?1 AND path = ?3) \
+     ON CONFLICT(repo, key, path) DO UPDATE SET commits = commits + excluded.commits";
+
+const UPSERT_FUNCTION: &str = "INSERT INTO functions (repo, path, name, commit_count, churn) \
+     SELECT ?1, ?2, ?3, ?4, ?5 \
+     WHERE EXISTS (SELECT 1 FROM files WHERE repo = ?1 AND path = ?2) \
+     ON CONFLICT(repo, path, name) DO UPDATE SET \
+         commit_count = commit_count + excluded.commit_count, \
+         churn = churn + excluded.churn";
+
+const UPSERT_FUNCTION_COCHANGE: &str = "INSERT INTO function_co_changed \
+         (repo, source_path, source_name, target_path, target_name, weight) \
+     SELECT ?1, ?2, ?3, ?4, ?5, ?6 \
+     WHERE EXISTS (SELECT 1 FROM functions WHERE repo = ?1 AND path = ?2 AND name = ?3) \
+       AND EXISTS (SELECT 1 FROM functions WHERE repo = ?1 AND path = ?4 AND name = ?5) \
+     ON CONFLICT(repo, source_path, source_name, target_path, target_name) \
+         DO UPDATE SET weight = weight + excluded.weight";
+
+#[async_trait]
+impl GraphStore for SqliteStore {
+    async fn init_schema(&self) -> Result<(), String> {
+        let conn = self.conn.lock().await;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS repositories (
+                 name TEXT PRIMARY KEY,
+                 url TEXT,
+                 total_commits INTEGER,
+                 last_commit TEXT,
+                 analysis_window TEXT
+             );
+             CREATE TABLE IF NOT EXISTS files (
+                 repo TEXT NOT NULL,
+                 path TEXT NOT NULL,
+                 additions INTEGER NOT NULL DEFAULT 0,
+                 deletions INTEGER NOT NULL DEFAULT 0,
+                 normalized_additions INTEGER NOT NULL DEFAULT 0,
+                 normalized_deletions INTEGER NOT NULL DEFAULT 0,
+                 commit_count INTEGER NOT NULL DEFAULT 0,
+                 deleted_at_commit TEXT,
+                 partner_count INTEGER,
+                 avg_coupling REAL,
+                 hub_score REAL,
+                 normalized_hub_score REAL,
+                 decayed_churn REAL,
+                 decayed_commit_count REAL,
+                 decayed_partner_count INTEGER,
+                 decayed_avg_coupling REAL,
+                 decayed_hub_score REAL,
+                 author_count INTEGER,
+                 main_author_share REAL,
+                 bus_factor INTEGER,
+                 bugfix_commits INTEGER NOT NULL DEFAULT 0,
+                 feature_commits INTEGER NOT NULL DEFAULT 0,
+                 refactor_commits INTEGER NOT NULL DEFAULT 0,
+                 docs_commits INTEGER NOT NULL DEFAULT 0,
+                 merge_commits INTEGER NOT NULL DEFAULT 0,
+                 PRIMARY KEY (repo, path)
+             );
+             CREATE TABLE IF NOT EXISTS co_changed (
+                 repo TEXT NOT NULL,
+                 source TEXT NOT NULL,
+                 target TEXT NOT NULL,
+                 weight INTEGER NOT NULL DEFAULT 0,
+                 decayed_weight REAL,
+                 PRIMARY KEY (repo, source, target)
+             );
+             CREATE INDEX IF NOT EXISTS co_changed_target ON co_changed (repo, target);
+             CREATE TABLE IF NOT EXISTS contains (
+                 repo TEXT NOT NULL,
+                 path TEXT NOT NULL,
+                 PRIMARY KEY (repo, path)
+             );
+             CREATE TABLE IF NOT EXISTS authors (
+                 repo TEXT NOT NULL,
+                 email TEXT NOT NULL,
+                 name TEXT NOT NULL,
+                 commit_count INTEGER NOT NULL DEFAULT 0,
+                 committed_count INTEGER NOT NULL DEFAULT 0,
+                 PRIMARY KEY (repo, email)
+             );
+             CREATE TABLE IF NOT EXISTS file_authors (
+                 repo TEXT NOT NULL,
+                 path TEXT NOT NULL,
+                 email TEXT NOT NULL,
+                 commits INTEGER NOT NULL DEFAULT 0,
+                 churn INTEGER NOT NULL DEFAULT 0,
+                 PRIMARY KEY (repo, path, email)
+             );
+             CREATE TABLE IF NOT EXISTS collaborations (
+                 repo TEXT NOT NULL,
+                 source TEXT NOT NULL,
+                 target TEXT NOT NULL,
+                 weight INTEGER NOT NULL DEFAULT 0,
+                 PRIMARY KEY (repo, source, target)
+             );
+             CREATE TABLE IF NOT EXISTS issue_commits (
+                 repo TEXT NOT NULL,
+                 key TEXT NOT NULL,
+                 commit_hash TEXT NOT NULL,
+                 PRIMARY KEY (repo, key, commit_hash)
+             );
+             CREATE TABLE IF NOT EXISTS issue_files (
+                 repo TEXT NOT NULL,
+                 key TEXT NOT NULL,
+                 path TEXT NOT NULL,
+                 commits INTEGER NOT NULL DEFAULT 0,
+                 PRIMARY KEY (repo, key, path)
+             );
+             CREATE TABLE IF NOT EXISTS issue_co_changed (
+                 repo TEXT NOT NULL,
+                 source TEXT NOT NULL,
+                 target TEXT NOT NULL,
+                 weight INTEGER NOT NULL DEFAULT 0,
+                 PRIMARY KEY (repo, source, target)
+             );
+             CREATE TABLE IF NOT EXISTS functions (
+                 repo TEXT NOT NULL,
+                 path TEXT NOT NULL,
+                 name TEXT NOT NULL,
+                 commit_count INTEGER NOT NULL DEFAULT 0,
+                 churn INTEGER NOT NULL DEFAULT 0,
+                 partner_count INTEGER,
+                 avg_coupling REAL,
+                 hub_score REAL,
+                 PRIMARY KEY (repo, path, name)
+             );
+             CREATE TABLE IF NOT EXISTS function_co_changed (
+                 repo TEXT NOT NULL,
+                 source_path TEXT NOT NULL,
+                 source_name TEXT NOT NULL,
+                 target_path TEXT NOT NULL,
+                 target_name TEXT NOT NULL,
+                 weight INTEGER NOT NULL DEFAULT 0,
+                 PRIMARY KEY (repo, source_path, source_name, target_path, target_name)
+             );
+             CREATE TABLE IF NOT EXISTS excluded_files (
+                 repo TEXT NOT NULL,
+                 path TEXT NOT NULL,
+                 reason TEXT NOT NULL,
+                 PRIMARY KEY (repo, path)
+             );
+             CREATE TABLE IF NOT EXISTS analysis_runs (
+                 repo TEXT NOT NULL,
+                 started_at TEXT NOT NULL,
+                 config TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS idx_analysis_runs_repo ON analysis_runs(repo);",
+        )
+        .map_err(|e| format!("Failed to execute schema query: {}", e))?;
+
+        // Databases created by older versions lack the columns added since
+        for (table, column, definition) in [
+            ("repositories", "last_commit", "TEXT"),
+            ("repositories", "analysis_window", "TEXT"),
+            ("files", "decayed_churn", "REAL"),
+            ("files", "decayed_commit_count", "REAL"),
+            ("files", "decayed_partner_count", "INTEGER"),
+            ("files", "decayed_avg_coupling", "REAL"),
+            ("files", "decayed_hub_score", "REAL"),
+            ("co_changed", "decayed_weight", "REAL"),
+            ("files", "author_count", "INTEGER"),
+            ("files", "main_author_share", "REAL"),
+            ("files", "bus_factor", "INTEGER"),
+            ("files", "bugfix_commits", "INTEGER NOT NULL DEFAULT 0"),
+            ("files", "feature_commits", "INTEGER NOT NULL DEFAULT 0"),
+            ("files", "refactor_commits", "INTEGER NOT NULL DEFAULT 0"),
+            ("files", "docs_commits", "INTEGER NOT NULL DEFAULT 0"),
+            ("files", "merge_commits", "INTEGER NOT NULL DEFAULT 0"),
+            (
+                "files",
+                "normalized_additions",
+                "INTEGER NOT NULL DEFAULT 0",
+            ),
+            (
+                "files",
+                "normalized_deletions",
+                "INTEGER NOT NULL DEFAULT 0",
+            ),
+            ("files", "normalized_hub_score", "REAL"),
+        ] {
+            let exists: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+                    params![table, column],
+                    |row| row.get(0),
+                )
+                .map_err(|e| format!("Failed to execute schema query: {}", e))?;
+            if !exists {
+                conn.execute(
+                    &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                    [],
+                )
+                .map_err(|e| format!("Failed to execute schema query: {}", e))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn delete_all_nodes(&self) -> Result<(), String> {
+        let conn = self.conn.lock().await;
+
+        conn.execute_batch(
+            "DELETE FROM contains; DELETE FROM co_changed; DELETE FROM file_authors; \
+             DELETE FROM collaborations; DELETE FROM issue_commits; DELETE FROM issue_files; \
+             DELETE FROM issue_co_changed; DELETE FROM function_co_changed; \
+             DELETE FROM functions; DELETE FROM excluded_files; DELETE FROM analysis_runs; \
+             DELETE FROM authors; DELETE FROM files; \
+             DELETE FROM repositories;",
+        )
+        .map_err(|e| format!("Failed to delete all nodes: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn delete_repository(&self, repo: &str) -> Result<(), String> {
+        let mut conn = self.conn.lock().await;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        for table in [
+            "contains",
+            "co_changed",
+            "file_authors",
+            "collaborations",
+            "issue_commits",
+            "issue_files",
+            "issue_co_changed",
+            "function_co_changed",
+            "functions",
+            "excluded_files",
+            "analysis_runs",
+            "authors",
+            "files",
+        ] {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE repo = ?1", table),
+                params![repo],
+            )
+            .map_err(|e| format!("Failed to delete repository {}: {}", repo, e))?;
+        }
+        tx.execute("DELETE FROM repositories WHERE name = ?1", params![repo])
+            .map_err(|e| format!("Failed to delete repository {}: {}", repo, e))?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit repository delete: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn save_repository(&self, name: &str, url: Option<&str>) -> Result<(), String> {
+        let conn = self.conn.lock().await;
+
+        conn.execute(
+            "INSERT INTO repositories (name, url) VALUES (?1, ?2) \
+             ON CONFLICT(name) DO UPDATE SET url = COALESCE(excluded.url, url)",
+            params![name, url],
+        )
+        .map_err(|e| format!("Failed to save repository: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn update_commit_count(&self, repo: &str, total_commits: i64) -> Result<(), String> {
+        let conn = self.conn.lock().await;
+
+        conn.execute(
+            "UPDATE repositories SET total_commits = ?2 WHERE name = ?1",
+            params![repo, total_commits],
+        )
+        .map_err(|e| format!("Failed to update commit count: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn get_checkpoint(&self, repo: &str) -> Result<Option<AnalysisCheckpoint>, String> {
+        let conn = self.conn.lock().await;
+
+        conn.query_row(
+            "SELECT last_commit, COALESCE(total_commits, 0) FROM repositories \
+             WHERE name = ?1 AND last_commit IS NOT NULL",
+            params![repo],
+            |row| {
+                Ok(AnalysisCheckpoint {
+                    last_commit: row.get(0)?,
+                    total_commits: row.get(1)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| format!("Failed to query checkpoint: {}", e))
+    }
+
+    async fn set_last_commit(&self, repo: &str, commit: &str) -> Result<(), String> {
+        let conn = self.conn.lock().await;
+
+        conn.execute(
+            "UPDATE repositories SET last_commit = ?2 WHERE name = ?1",
+            params![repo, commit],
+        )
+        .map_err(|e| format!("Failed to save last commit: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn set_analysis_window(&self, repo: &str, window: &str) -> Result<(), String> {
+        let conn = self.conn.lock().await;
+
+        conn.execute(
+            "UPDATE repositories SET analysis_window = ?2 WHERE name = ?1",
+            params![repo, window],
+        )
+        .map_err(|e| format!("Failed to save analysis window: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn save_file_node(
+        &self,
+        repo: &str,
+        path: &str,
+        additions: i64,
+        deletions: i64,
+        deleted_at_commit: Option<&str>,
+    ) -> Result<(), String> {
+        let conn = self.conn.lock().await;
+
+        conn.execute(
+            UPSERT_FILE,
+            params![
+                repo,
+                path,
+                additions,
+                deletions,
+                1,
+                deleted_at_commit,
+                None::<f64>,
+                None::<f64>,
+                0,
+                0,
+                0,
+                0,
+                0,
+                additions,
+                deletions
+            ],
+        )
+        .map_err(|e| format!("Failed to save file node: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn rename_file_node(
+        &self,
+        repo: &str,
+        old_path: &str,
+        new_path: &str,
+    ) -> Result<(), String> {
+        let mut conn = self.conn.lock().await;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        rename_in(&tx, repo, old_path, new_path)
+            .map_err(|e| format!("Failed to rename file node: {}", e))?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit rename: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn save_cochange_relationship(
+        &self,
+        repo: &str,
+        source: &str,
+        target: &str,
+    ) -> Result<(), String> {
+        let conn = self.conn.lock().await;
+
+        conn.execute(
+            UPSERT_COCHANGE,
+            params![repo, source, target, 1, None::<f64>],
+        )
+        .map_err(|e| format!("Failed to save co-change relationship: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn save_commit_batch(&self, repo: &str, batch: &[CommitChanges]) -> Result<(), String> {
+        let mut conn = self.conn.lock().await;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        for segment in segment_batch(batch) {
+            write_segment(&tx, repo, &segment)?;
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit batch: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn bulk_load_graph(&self, graph: &FileGraph) -> Result<(), String> {
+        let mut conn = self.conn.lock().await;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        write_segment(&tx, &graph.repo, &graph_segment(graph))?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit bulk load: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn rescale_decay(&self, repo: &str, factor: f64) -> Result<(), String> {
+        let mut conn = self.conn.lock().await;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        tx.execute(
+            "UPDATE files SET decayed_churn = decayed_churn * ?2, \
+                 decayed_commit_count = decayed_commit_count * ?2 \
+             WHERE repo = ?1",
+            params![repo, factor],
+        )
+        .map_err(|e| format!("Failed to rescale decayed weights: {}", e))?;
+        tx.execute(
+            "UPDATE co_changed SET decayed_weight = decayed_weight * ?2 WHERE repo = ?1",
+            params![repo, factor],
+        )
+        .map_err(|e| format!("Failed to rescale decayed weights: {}", e))?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit rescale: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn save_collaborations(
+        &self,
+        repo: &str,
+        collaborations: &[Collaboration],
+    ) -> Result<(), String> {
+        let mut conn = self.conn.lock().await;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO collaborations (repo, source, target, weight) \
+                     SELECT ?1, ?2, ?3, ?4 \
+                     WHERE EXISTS (SELECT 1 FROM authors WHERE repo = ?1 AND email = ?2) \
+                       AND EXISTS (SELECT 1 FROM authors WHERE repo = ?1 AND email = ?3) \
+                     ON CONFLICT(repo, source, target) DO UPDATE SET \
+                         weight = weight + excluded.weight",
+                )
+                .map_err(|e| format!("Failed to save collaborations: {}", e))?;
+
+            for collaboration in collaborations {
+                stmt.execute(params![
+                    repo,
+                    collaboration.source,
+                    collaboration.target,
+                    collaboration.weight
+                ])
+                .map_err(|e| format!("Failed to save collaborations: {}", e))?;
+            }
+        }
+        tx.commit()
+            .map_err(|e| format!("Failed to commit collaborations: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn save_excluded_files(&self, repo: &str, files: &[ExcludedFile]) -> Result<(), String> {
+        let mut conn = self.conn.lock().await;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO excluded_files (repo, path, reason) VALUES (?1, ?2, ?3) \
+                     ON CONFLICT(repo, path) DO UPDATE SET reason = excluded.reason",
+                )
+                .map_err(|e| format!("Failed to save excluded files: {}", e))?;
+
+            for file in files {
+                stmt.execute(params![repo, file.path, file.reason])
+                    .map_err(|e| format!("Failed to save excluded files: {}", e))?;
+            }
+        }
+        tx.commit()
+            .map_err(|e| format!("Failed to commit excluded files: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn save_analysis_run(&self, repo: &str, run: &AnalysisRun) -> Result<(), String> {
+        let conn = self.conn.lock().await;
+
+        conn.execute(
+            "INSERT INTO analysis_runs (repo, started_at, config) VALUES (?1, ?2, ?3)",
+            params![repo, run.started_at, run.config],
+        )
+        .map_err(|e| format!("Failed to save analysis run: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn get_graph(&self, repo: &str) -> Result<GraphData, String> {
+        let conn = self.conn.lock().await;
+
+        let mut files_stmt = conn
+            .prepare(
+                "SELECT path, additions, deletions, commit_count, deleted_at_commit, \
+                        decayed_churn, decayed_commit_count, bugfix_commits, feature_commits, \
+                        refactor_commits, docs_commits, merge_commits, normalized_additions, \
+                        normalized_deletions \
+                 FROM files WHERE repo = ?1",
+            )
+            .map_err(|e| format!("Failed to query files: {}", e))?;
+
+        let mut authors_by_file: HashMap<String, Vec<FileAuthor>> = HashMap::new();
+        {
+            let mut stmt = conn
+                .prepare("SELECT path, email, commits, churn FROM file_authors WHERE repo = ?1")
+                .map_err(|e| format!("Failed to query file authors: {}", e))?;
+            let rows = stmt
+                .query_map(params![repo], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        FileAuthor {
+                            email: row.get(1)?,
+                            commits: row.get(2)?,
+                            churn: row.get(3)?,
+                        },
+                    ))
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("Failed to query file authors: {}", e))?;
+            for (path, author) in rows {
+                authors_by_file.entry(path).or_default().push(author);
+            }
+        }
+
+        let files = files_stmt
+            .query_map(params![repo], |row| {
+                let path: String = row.get(0)?;
+                Ok(FileNode {
+                    authors: authors_by_file.remove(&path).unwrap_or_default(),
+                    path,
+                    additions: row.get(1)?,
+                    deletions: row.get(2)?,
+                    normalized_additions: row.get(12)?,
+                    normalized_deletions: row.get(13)?,
+                    commit_count: row.get(3)?,
+                    deleted_at_commit: row.get(4)?,
+                    decayed_churn: row.get(5)?,
+                    decayed_commit_count: row.get(6)?,
+                    commit_classes: ClassCounts {
+                        bugfix_commits: row.get(7)?,
+                        feature_commits: row.get(8)?,
+                        refactor_commits: row.get(9)?,
+                        docs_commits: row.get(10)?,
+                        merge_commits: row.get(11)?,
+                    },
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to query files: {}", e))?;
+
+        let mut edges_stmt = conn
+            .prepare(
+                "SELECT source, target, weight, decayed_weight FROM co_changed WHERE repo = ?1",
+            )
+            .map_err(|e| format!("Failed to query edges: {}", e))?;
+
+        let edges = edges_stmt
+            .query_map(params![repo], |row| {
+                Ok(Edge {
+                    source: row.get(0)?,
+                    target: row.get(1)?,
+                    weight: row.get(2)?,
+                    decayed_weight: row.get(3)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to query edges: {}", e))?;
+
+        let (total_commits, window): (Option<i64>, Option<String>) = conn
+            .query_row(
+                "SELECT total_commits, analysis_window FROM repositories WHERE name = ?1",
+                params![repo],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to query commit count: {}", e))?
+            .unwrap_or_default();
+
+        let mut authors_stmt = conn
+            .prepare(
+                "SELECT email, name, commit_count, committed_count FROM authors \
+                 WHERE repo = ?1 ORDER BY email",
+            )
+            .map_err(|e| format!("Failed to query authors: {}", e))?;
+
+        let authors = authors_stmt
+            .query_map(params![repo], |row| {
+                Ok(Author {
+                    email: row.get(0)?,
+                    name: row.get(1)?,
+                    commit_count: row.get(2)?,
+                    committed_count: row.get(3)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to query authors: {}", e))?;
+
+        let mut collaborations_stmt = conn
+            .prepare(
+                "SELECT source, target, weight FROM collaborations \
+                 WHERE repo = ?1 ORDER BY source, target",
+            )
+            .map_err(|e| format!("Failed to query collaborations: {}", e))?;
+
+        let collaborations = collaborations_stmt
+            .query_map(params![repo], |row| {
+                Ok(Collaboration {
+                    source: row.get(0)?,
+                    target: row.get(1)?,
+                    weight: row.get(2)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to query collaborations: {}", e))?;
+
+        let mut issues: BTreeMap<String, Issue> = BTreeMap::new();
+        {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT key, commit_hash FROM issue_commits WHERE repo = ?1 \
+                     ORDER BY key, rowid",
+                )
+                .map_err(|e| format!("Failed to query issues: {}", e))?;
+            let rows = stmt
+                .query_map(params![repo], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("Failed to query issues: {}", e))?;
+            for (key, commit_hash) in rows {
+                issues
+                    .entry(key.clone())
+                    .or_insert_with(|| Issue {
+                        key,
+                        commits: Vec::new(),
+                        files: Vec::new(),
+                    })
+                    .commits
+                    .push(commit_hash);
+            }
+
+            let mut stmt = conn
+                .prepare(
+                    "SELECT key, path, commits FROM issue_files WHERE repo = ?1 \
+                     ORDER BY key, path",
+                )
+                .map_err(|e| format!("Failed to query issue files: {}", e))?;
+            let rows = stmt
+                .query_map(params![repo], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        IssueFile {
+                            path: row.get(1)?,
+                            commits: row.get(2)?,
+                        },
+                    ))
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("Failed to query issue files: {}", e))?;
+            for (key, file) in rows {
+                if let Some(issue) = issues.get_mut(&key) {
+                    issue.files.push(file);
+                }
+            }
+        }
+
+        let mut issue_edges_stmt = conn
+            .prepare(
+                "SELECT source, target, weight FROM issue_co_changed \
+                 WHERE repo = ?1 ORDER BY source, target",
+            )
+            .map_err(|e| format!("Failed to query issue co-changes: {}", e))?;
+
+        let issue_edges = issue_edges_stmt
+            .query_map(params![repo], |row| {
+                Ok(Edge {
+                    source: row.get(0)?,
+                    target: row.get(1)?,
+                    weight: row.get(2)?,
+                    decayed_weight: None,
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to query issue co-changes: {}", e))?;
+
+        let mut functions_stmt = conn
+            .prepare(
+                "SELECT path, name, commit_count, churn FROM functions \
+                 WHERE repo = ?1 ORDER BY path, name",
+            )
+            .map_err(|e| format!("Failed to query functions: {}", e))?;
+
+        let functions = functions_stmt
+            .query_map(params![repo], |row| {
+                Ok(Function {
+                    path: row.get(0)?,
+                    name: row.get(1)?,
+                    commit_count: row.get(2)?,
+                    churn: row.get(3)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to query functions: {}", e))?;
+
+        let mut function_edges_stmt = conn
+            .prepare(
+                "SELECT source_path, source_name, target_path, target_name, weight \
+                 FROM function_co_changed WHERE repo = ?1 \
+                 ORDER BY source_path, source_name, target_path, target_name",
+            )
+            .map_err(|e| format!("Failed to query function co-changes: {}", e))?;
+
+        let function_edges = function_edges_stmt
+            .query_map(params![repo], |row| {
+                Ok(FunctionEdge {
+                    source_path: row.get(0)?,
+                    source_name: row.get(1)?,
+                    target_path: row.get(2)?,
+                    target_name: row.get(3)?,
+                    weight: row.get(4)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to query function co-changes: {}", e))?;
+
+        let mut excluded_stmt = conn
+            .prepare("SELECT path, reason FROM excluded_files WHERE repo = ?1 ORDER BY path")
+            .map_err(|e| format!("Failed to query excluded files: {}", e))?;
+
+        let excluded_files = excluded_stmt
+            .query_map(params![repo], |row| {
+                Ok(ExcludedFile {
+                    path: row.get(0)?,
+                    reason: row.get(1)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to query excluded files: {}", e))?;
+
+        let mut runs_stmt = conn
+            .prepare(
+                "SELECT started_at, config FROM analysis_runs WHERE repo = ?1 \
+                 ORDER BY started_at, rowid",
+            )
+            .map_err(|e| format!("Failed to query analysis runs: {}", e))?;
+
+        let analysis_runs = runs_stmt
+            .query_map(params![repo], |row| {
+                Ok(AnalysisRun {
+                    started_at: row.get(0)?,
+                    config: row.get(1)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to query analysis runs: {}", e))?;
+
+        Ok(GraphData {
+            repo: repo.to_string(),
+            total_commits_analyzed: total_commits.unwrap_or(0),
+            files,
+            edges,
+            window,
+            authors,
+            collaborations,
+            issues: issues.into_values().collect(),
+            issue_edges,
+            functions,
+            function_edges,
+            excluded_files,
+            analysis_runs,
+        })
+    }
+
+    async fn link_file_to_repo(&self, repo: &str, file_path: &str) -> Result<(), String> {
+        let conn = self.conn.lock().await;
+
+        conn.execute(
+            "INSERT OR IGNORE INTO contains (repo, path) \
+             SELECT ?1, ?2 \
+             WHERE EXISTS (SELECT 1 FROM repositories WHERE name = ?1) \
+               AND EXISTS (SELECT 1 FROM files WHERE repo = ?1 AND path = ?2)",
+            params![repo, file_path],
+        )
+        .map_err(|e| format!("Failed to link file to repo: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn link_all_files_to_repo(&self, repo: &str) -> Result<(), String> {
+        let conn = self.conn.lock().await;
+
+        conn.execute(
+            "INSERT OR IGNORE INTO contains (repo, path) \
+             SELECT f.repo, f.path FROM files f \
+             JOIN repositories r ON r.name = f.repo \
+             WHERE f.repo = ?1",
+            params![repo],
+        )
+        .map_err(|e| format!("Failed to link all files: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn remove_low_importance_connections(
+        &self,
+        repo: &str,
+        threshold: i64,
+    ) -> Result<u64, String> {
+        let conn = self.conn.lock().await;
+
+        let deleted_edges = conn
+            .execute(
+                "DELETE FROM co_changed WHERE repo = ?1 AND weight < ?2",
+                params![repo, threshold],
+            )
+            .map_err(|e| format!("Failed to prune edges: {}", e))?;
+
+        Ok(deleted_edges as u64)
+    }
+
+    async fn remove_orphan_files(&self, repo: &str) -> Result<u64, String> {
+        let mut conn = self.conn.lock().await;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        let orphans = tx
+            .execute(
+                "DELETE FROM files WHERE repo = ?1 AND NOT EXISTS ( \
+                     SELECT 1 FROM co_changed c WHERE c.repo = files.repo \
+                     AND (c.source = files.path OR c.target = files.path))",
+                params![repo],
+            )
+            .map_err(|e| format!("Failed to remove orphan files: {}", e))?;
+
+        tx.execute(
+            "DELETE FROM co_changed WHERE repo = ?1 AND EXISTS ( \
+                 SELECT 1 FROM files f WHERE f.repo = co_changed.repo \
+                 AND (f.path = co_changed.source OR f.path = co_changed.target) \
+                 AND f.additions = 0 AND f.deletions = 0 AND f.deleted_at_commit IS NULL)",
+            params![repo],
+        )
+        .map_err(|e| format!("Failed to prune zero-churn files: {}", e))?;
+        let zero_churn = tx
+            .execute(
+                "DELETE FROM files WHERE repo = ?1 \
+                 AND additions = 0 AND deletions = 0 AND deleted_at_commit IS NULL",
+                params![repo],
+            )
+            .map_err(|e| format!("Failed to prune zero-churn files: {}", e))?;
+
+        for table in ["contains", "file_authors", "issue_files", "functions"] {
+            tx.execute(
+                &format!(
+                    "DELETE FROM {table} WHERE repo = ?1 AND NOT EXISTS ( \
+                         SELECT 1 FROM files f WHERE f.repo = {table}.repo \
+                         AND f.path = {table}.path)"
+                ),
+                params![repo],
+            )
+            .map_err(|e| format!("Failed to remove dangling {} links: {}", table, e))?;
+        }
+        tx.execute(
+            "DELETE FROM issue_co_changed WHERE repo = ?1 \
+                 AND (source NOT IN (SELECT path FROM files WHERE repo = ?1) \
+                 OR target NOT IN (SELECT path FROM files WHERE repo = ?1))",
+            params![repo],
+        )
+        .map_err(|e| format!("Failed to remove dangling issue co-changes: {}", e))?;
+        tx.execute(
+            "DELETE FROM function_co_changed WHERE repo = ?1 \
+                 AND (source_path NOT IN (SELECT path FROM files WHERE repo = ?1) \
+                 OR target_path NOT IN (SELECT path FROM files WHERE repo = ?1))",
+            params![repo],
+        )
+        .map_err(|e| format!("Failed to remove dangling function co-changes: {}", e))?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit prune: {}", e))?;
+
+        Ok((orphans + zero_churn) as u64)
+    }
+
+    async fn compute_hub_scores(&self, repo: &str, min_coupling: f64) -> Result<(), String> {
+        let graph = self.get_graph(repo).await?;
+        let scores = hub_score::compute_hub_scores(&graph, min_coupling);
+        let normalized_scores = hub_score::compute_normalized_hub_scores(&graph, min_coupling);
+        let decayed_scores = hub_score::compute_decayed_hub_scores(&graph, min_coupling);
+        let ownership = ownership::compute_ownership(&graph);
+        let issue_edges = issue_cochanges(&graph.issues);
+        let function_scores = hub_score::compute_function_hub_scores(&graph, min_coupling);
+
+        let mut conn = self.conn.lock().await;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "UPDATE files SET partner_count = ?3, avg_coupling = ?4, hub_score = ?5 \
+                     WHERE repo = ?1 AND path = ?2",
+                )
+                .map_err(|e| format!("Failed to update hub score: {}", e))?;
+
+            for score in scores {
+                stmt.execute(params![
+                    repo,
+                    score.path,
+                    score.partner_count,
+                    score.avg_coupling,
+                    score.hub_score
+                ])
+                .map_err(|e| format!("Failed to update hub score: {}", e))?;
+            }
+
+            let mut normalized_stmt = tx
+                .prepare("UPDATE files SET normalized_hub_score = ?3 WHERE repo = ?1 AND path = ?2")
+                .map_err(|e| format!("Failed to update normalized hub score: {}", e))?;
+
+            for score in normalized_scores {
+                normalized_stmt
+                    .execute(params![repo, score.path, score.hub_score])
+                    .map_err(|e| format!("Failed to update normalized hub score: {}", e))?;
+            }
+
+            let mut decayed_stmt = tx
+                .prepare(
+                    "UPDATE files SET decayed_partner_count = ?3, decayed_avg_coupling = ?4, \
+                         decayed_hub_score = ?5 \
+                     WHERE repo = ?1 AND path = ?2",
+                )
+                .map_err(|e| format!("Failed to update decayed hub score: {}", e))?;
+
+            for score in decayed_scores {
+                decayed_stmt
+                    .execute(params![
+                        repo,
+                        score.path,
+                        score.partner_count,
+                        score.avg_coupling,
+                        score.hub_score
+                    ])
+                    .map_err(|e| format!("Failed to update decayed hub score: {}", e))?;
+            }
+
+            let mut ownership_stmt = tx
+                .prepare(
+                    "UPDATE files SET author_count = ?3, main_author_share = ?4, \
+                         bus_factor = ?5 \
+                     WHERE repo = ?1 AND path = ?2",
+                )
+                .map_err(|e| format!("Failed to update ownership metrics: {}", e))?;
+
+            for file in ownership {
+                ownership_stmt
+                    .execute(params![
+                        repo,
+                        file.path,
+                        file.author_count,
+                        file.main_author_share,
+                        file.bus_factor
+                    ])
+                    .map_err(|e| format!("Failed to update ownership metrics: {}", e))?;
+            }
+
+            tx.execute(
+                "DELETE FROM issue_co_changed WHERE repo = ?1",
+                params![repo],
+            )
+            .map_err(|e| format!("Failed to update issue co-changes: {}", e))?;
+            let mut issue_stmt = tx
+                .prepare(
+                    "INSERT INTO issue_co_changed (repo, source, target, weight) \
+                     VALUES (?1, ?2, ?3, ?4)",
+                )
+                .map_err(|e| format!("Failed to update issue co-changes: {}", e))?;
+
+            for edge in issue_edges {
+                issue_stmt
+                    .execute(params![repo, edge.source, edge.target, edge.weight])
+                    .map_err(|e| format!("Failed to update issue co-changes: {}", e))?;
+            }
+
+            tx.execute(
+                "UPDATE functions SET partner_count = NULL, avg_coupling = NULL, \
+                     hub_score = NULL \
+                 WHERE repo = ?1",
+                params![repo],
+            )
+            .map_err(|e| format!("Failed to update function hub score: {}", e))?;
+            let mut function_stmt = tx
+                .prepare(
+                    "UPDATE functions SET partner_count = ?4, avg_coupling = ?5, hub_score = ?6 \
+                     WHERE repo = ?1 AND path = ?2 AND name = ?3",
+                )
+                .map_err(|e| format!("Failed to update function hub score: {}", e))?;
+
+            for score in function_scores {
+                function_stmt
+                    .execute(params![
+                        repo,
+                        score.path,
+                        score.name,
+                        score.partner_count,
+                        score.avg_coupling,
+                        score.hub_score
+                    ])
+                    .map_err(|e| format!("Failed to update function hub score: {}", e))?;
+            }
+        }
+        tx.commit()
+            .map_err(|e| format!("Failed to commit hub scores: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn get_all_repo_names(&self) -> Result<Vec<String>, String> {
+        let conn = self.conn.lock().await;
+
+        let mut stmt = conn
+            .prepare("SELECT DISTINCT repo FROM files ORDER BY repo")
+            .map_err(|e| format!("Failed to get repo names: {}", e))?;
+
+        let repos = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to get repo names: {}", e))?;
+
+        Ok(repos.into_iter().filter(|r| !r.is_empty()).collect())
+    }
+
+    async fn get_all_hub_scores(&self, extension: &str) -> Result<Vec<HubScoreData>, String> {
+        let conn = self.conn.lock().await;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT f.repo, f.path, f.hub_score, f.avg_coupling, f.commit_count, \
+                        f.partner_count, f.additions, f.deletions, r.analysis_window, \
+                        f.decayed_hub_score, f.decayed_avg_coupling, f.decayed_partner_count, \
+                        f.author_count, f.main_author_share, f.bus_factor, f.bugfix_commits, \
+                        f.feature_commits, f.refactor_commits, f.docs_commits, f.merge_commits, \
+                        f.normalized_hub_score \
+                 FROM files f LEFT JOIN repositories r ON r.name = f.repo \
+                 WHERE substr(f.path, -length(?1)) = ?1 AND f.deleted_at_commit IS NULL \
+                 ORDER BY f.hub_score DESC",
+            )
+            .map_err(|e| format!("Failed to get hub scores: {}", e))?;
+
+        let files = stmt
+            .query_map(params![extension], |row| {
+                let additions: i64 = row.get(6)?;
+                let deletions: i64 = row.get(7)?;
+                Ok(HubScoreData {
+                    repo: row.get(0)?,
+                    path: row.get(1)?,
+                    hub_score: row.get::<_, Option<f64>>(2)?.unwrap_or(0.0),
+                    avg_coupling: row.get::<_, Option<f64>>(3)?.unwrap_or(0.0),
+                    commit_count: row.get(4)?,
+                    partner_count: row.get::<_, Option<i64>>(5)?.unwrap_or(0),
+                    churn: additions + deletions,
+                    window: row.get(8)?,
+                    normalized_hub_score: row.get(20)?,
+                    decayed_hub_score: row.get(9)?,
+                    decayed_avg_coupling: row.get(10)?,
+                    decayed_partner_count: row.get(11)?,
+                    author_count: row.get(12)?,
+                    main_author_share: row.get(13)?,
+                    bus_factor: row.get(14)?,
+                    commit_classes: ClassCounts {
+                        bugfix_commits: row.get(15)?,
+                        feature_commits: row.get(16)?,
+                        refactor_commits: row.get(17)?,
+                        docs_commits: row.get(18)?,
+                        merge_commits: row.get(19)?,
+                    },
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to get hub scores: {}", e))?;
+
+        Ok(files)
+    }
+
+    async fn get_all_function_hub_scores(
+        &self,
+        extension: &str,
+    ) -> Result<Vec<FunctionHubScoreData>, String> {
+        let conn = self.conn.lock().await;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT fn.repo, fn.path, fn.name, fn.hub_score, fn.avg_coupling, \
+                        fn.commit_count, fn.partner_count, fn.churn \
+                 FROM functions fn JOIN files f ON f.repo = fn.repo AND f.path = fn.path \
+                 WHERE substr(f.path, -length(?1)) = ?1 AND f.deleted_at_commit IS NULL \
+                 ORDER BY fn.hub_score DESC",
+            )
+            .map_err(|e| format!("Failed to get function hub scores: {}", e))?;
+
+        let functions = stmt
+            .query_map(params![extension], |row| {
+                Ok(FunctionHubScoreData {
+                    repo: row.get(0)?,
+                    path: row.get(1)?,
+                    name: row.get(2)?,
+                    hub_score: row.get::<_, Option<f64>>(3)?.unwrap_or(0.0),
+                    avg_coupling: row.get::<_, Option<f64>>(4)?.unwrap_or(0.0),
+                    commit_count: row.get(5)?,
+                    partner_count: row.get::<_, Option<i64>>(6)?.unwrap_or(0),
+                    churn: row.get(7)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to get function hub scores: {}", e))?;
+
+        Ok(functions)
+    }
+
+    async fn get_files_by_hub_threshold(
+        &self,
+        repo: Option<&str>,
+        hub_threshold: f64,
+        extension: &str,
+        ignore_repos: &[String],
+    ) -> Result<Vec<RepoWithFiles>, String> {
+        let conn = self.conn.lock().await;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT f.repo, r.url, f.path, f.hub_score \
+                 FROM files f JOIN repositories r ON r.name = f.repo \
+                 WHERE f.deleted_at_commit IS NULL AND substr(f.path, -length(?1)) = ?1 \
+                     AND (?2 IS NULL OR f.repo = ?2) \
+                 ORDER BY f.repo, f.hub_score DESC",
+            )
+            .map_err(|e| format!("Failed to get files: {}", e))?;
+
+        let rows = stmt
+            .query_map(params![extension, repo], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    RepoFile {
+                        path: row.get(2)?,
+                        hub_score: row.get::<_, Option<f64>>(3)?.unwrap_or(0.0),
+                    },
+                ))
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to get files: {}", e))?;
+
+        let ignore_repos: Vec<String> = ignore_repos.iter().map(|i| i.to_lowercase()).collect();
+        let mut grouped: BTreeMap<String, (Option<String>, Vec<RepoFile>)> = BTreeMap::new();
+        for (repo, url, file) in rows {
+            let lowered = repo.to_lowercase();
+            if ignore_repos.iter().any(|ignore| lowered.contains(ignore)) {
+                continue;
+            }
+            grouped
+                .entry(repo)
+                .or_insert_with(|| (url, Vec::new()))
+                .1
+                .push(file);
+        }
+
+        Ok(grouped
+            .into_iter()
+            .map(|(repo, (repo_url, files))| {
+                let is_high_risk = files
+                    .first()
+                    .map(|f| f.hub_score >= hub_threshold)
+                    .unwrap_or(false);
+                RepoWithFiles {
+                    repo,
+                    repo_url: repo_url.unwrap_or_default(),
+                    files,
+                    is_high_risk,
+                }
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,196 @@
+use git2::{BlameOptions, DiffFindOptions, DiffOptions, Oid, Repository};
+use log::info;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::commit_class::CommitClass;
+use crate::file_graph::CommitChanges;
+
+/// SZZ labels for one file. `bug_density` is the number of bug-introducing commits per
+/// analysed commit touching the file.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileBugs {
+    pub path: String,
+    pub commit_count: i64,
+    pub bugfix_commits: i64,
+    pub bug_introducing_commits: i64,
+    pub bug_density: f64,
+}
+
+#[derive(Default)]
+struct FileState {
+    commit_count: i64,
+    bugfix_commits: i64,
+    introducing: HashSet<Oid>,
+}
+
+/// Walks `history` oldest first. The lines every bug-fix commit deletes or modifies are
+/// blamed in its parent revision, and the commits that last touched them become the file's
+/// bug-introducing commits. Renames carry a file's labels to its newest path and deleted
+/// files are dropped, like in the file graph.
+pub fn find_bug_introducing(
+    repo: &Repository,
+    history: &[CommitChanges],
+) -> Result<Vec<FileBugs>, String> {
+    let mut files: HashMap<String, FileState> = HashMap::new();
+    let mut fixes = 0;
+
+    for commit in history {
+        for (old_path, new_path) in &commit.renames {
+            files.remove(new_path);
+            if let Some(state) = files.remove(old_path) {
+                files.insert(new_path.clone(), state);
+            }
+        }
+
+        // Large commits only carry renames and deletions, so there is nothing to blame
+        if commit.class == CommitClass::Bugfix && commit.record_cochanges {
+            fixes += 1;
+            let mut introducing = blame_fix(repo, commit)?;
+            for file in commit.files.iter().filter(|f| !f.is_deleted) {
+                let state = files.entry(file.path.clone()).or_default();
+                state.bugfix_commits += 1;
+                if let Some(commits) = introducing.remove(&file.path) {
+                    state.introducing.extend(commits);
+                }
+            }
+        }
+
+        for file in &commit.files {
+            if file.is_deleted {
+                files.remove(&file.path);
+            } else {
+                files.entry(file.path.clone()).or_default().commit_count += 1;
+            }
+        }
+    }
+
+    info!(
+        "Blamed {} bug-fix commits across {} files",
+        fixes,
+        files.len()
+    );
+
+    let mut result: Vec<FileBugs> = files
+        .into_iter()
+        .map(|(path, state)| {
+            let bug_introducing_commits = state.introducing.len() as i64;
+            FileBugs {
+                path,
+                commit_count: state.commit_count,
+                bugfix_commits: state.bugfix_commits,
+                bug_introducing_commits,
+                bug_density: if state.commit_count > 0 {
+                    bug_introducing_commits as f64 / state.commit_count as f64
+                } else {
+                    0.0
+                },
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| {
+        b.bug_introducing_commits
+            .cmp(&a.bug_introducing_commits)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    Ok(result)
+}
+
+/// Commits that introduced the lines a fix removed or changed, keyed by the file's path
+/// after the fix.
+fn blame_fix(
+    repo: &Repository,
+    commit: &CommitChanges,
+) -> Result<HashMap<String, HashSet<Oid>>, String> {
+    let oid = Oid::from_str(&commit.commit_hash)
+        .map_err(|e| format!("Failed to parse commit id: {}", e))?;
+    let fix = repo
+        .find_commit(oid)
+        .map_err(|e| format!("Failed to find commit: {}", e))?;
+    if fix.parent_count() == 0 {
+        return Ok(HashMap::new());
+    }
+    let parent = fix
+        .parent(0)
+        .map_err(|e| format!("Failed to get parent commit: {}", e))?;
+
+    let parent_tree = parent
+        .tree()
+        .map_err(|e| format!("Failed to get parent tree: {}", e))?;
+    let tree = fix
+        .tree()
+        .map_err(|e| format!("Failed to get commit tree: {}", e))?;
+    let mut diff = repo
+        .diff_tree_to_tree(
+            Some(&parent_tree),
+            Some(&tree),
+            Some(DiffOptions::new().context_lines(0)),
+        )
+        .map_err(|e| format!("Failed to get diff: {}", e))?;
+    diff.find_similar(Some(&mut DiffFindOptions::new().renames(true)))
+        .map_err(|e| format!("Failed to find similar: {}", e))?;
+
+    let tracked: HashSet<&str> = commit
+        .files
+        .iter()
+        .filter(|f| !f.is_deleted)
+        .map(|f| f.path.as_str())
+        .collect();
+
+    // new path -> (path in the parent, removed line ranges as (first line, count))
+    let mut removed: HashMap<String, (String, Vec<(usize, usize)>)> = HashMap::new();
+    diff.foreach(
+        &mut |_, _| true,
+        None,
+        Some(&mut |delta, hunk| {
+            let (Some(old_path), Some(new_path)) =
+                (delta.old_file().path(), delta.new_file().path())
+            else {
+                return true;
+            };
+            let new_path = new_path.to_string_lossy().to_string();
+            if hunk.old_lines() > 0 && tracked.contains(new_path.as_str()) {
+                removed
+                    .entry(new_path)
+                    .or_insert_with(|| (old_path.to_string_lossy().to_string(), Vec::new()))
+                    .1
+                    .push((hunk.old_start() as usize, hunk.old_lines() as usize));
+            }
+            true
+        }),
+        None,
+    )
+    .map_err(|e| format!("Failed to read diff hunks: {}", e))?;
+
+    let mut introducing = HashMap::new();
+    for (path, (old_path, ranges)) in removed {
+        let first = ranges.iter().map(|(start, _)| *start).min().unwrap_or(1);
+        let last = ranges
+            .iter()
+            .map(|(start, count)| start + count - 1)
+            .max()
+            .unwrap_or(first);
+
+        let mut options = BlameOptions::new();
+        options
+            .newest_commit(parent.id())
+            .min_line(first)
+            .max_line(last);
+        let blame = repo
+            .blame_file(Path::new(&old_path), Some(&mut options))
+            .map_err(|e| format!("Failed to blame {} at {}: {}", old_path, parent.id(), e))?;
+
+        let commits: &mut HashSet<Oid> = introducing.entry(path).or_default();
+        for (start, count) in ranges {
+            for line in start..start + count {
+                if let Some(hunk) = blame.get_line(line) {
+                    commits.insert(hunk.final_commit_id());
+                }
+            }
+        }
+    }
+
+    Ok(introducing)
+}
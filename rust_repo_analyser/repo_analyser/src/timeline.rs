@@ -0,0 +1,216 @@
+use chrono::{DateTime, Datelike, Months, SecondsFormat, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::file_graph::{CommitChanges, FileGraphBuilder};
+use crate::hub_score;
+use crate::storage::memory_store::graph_data_from;
+
+/// Size of one timeline window, either a number of commits or a number of calendar months.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineWindow {
+    Commits(usize),
+    Months(u32),
+}
+
+/// Hub metrics of one file within one window. Files changed in the window without any
+/// co-change partner get zero scores so every series stays continuous.
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelinePoint {
+    pub window: usize,
+    pub start: String,
+    pub end: String,
+    pub path: String,
+    pub hub_score: f64,
+    pub normalized_hub_score: f64,
+    pub partner_count: i64,
+    pub avg_coupling: f64,
+    pub churn: i64,
+    pub commit_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileSeries<'a> {
+    pub path: &'a str,
+    pub points: Vec<&'a TimelinePoint>,
+}
+
+/// Recomputes the co-change graph and hub scores for every window of `history`, moving
+/// the window forward by `step` (in the window's unit). `history` must be in commit-time
+/// order, as returned by `GitAnalyzer::read_history`. Paths are normalised to their latest
+/// name so a renamed file keeps a single series.
+pub fn compute_timeline(
+    repo: &str,
+    history: &[CommitChanges],
+    window: TimelineWindow,
+    step: usize,
+    min_coupling: f64,
+) -> Vec<TimelinePoint> {
+    let history = canonicalize_paths(history);
+    let step = step.max(1);
+
+    let bounds = match window {
+        TimelineWindow::Commits(size) => commit_windows(&history, size.max(1), step),
+        TimelineWindow::Months(months) => month_windows(&history, months.max(1), step as u32),
+    };
+
+    let mut points = Vec::new();
+    for (index, (start, end, commits)) in bounds.into_iter().enumerate() {
+        if commits.is_empty() {
+            continue;
+        }
+
+        let mut builder = FileGraphBuilder::new(repo.to_string());
+        for commit in commits {
+            builder.apply_commit(commit);
+        }
+        let graph = graph_data_from(&builder.finalize(), commits.len() as i64);
+
+        let scores: HashMap<String, hub_score::HubScore> =
+            hub_score::compute_hub_scores(&graph, min_coupling)
+                .into_iter()
+                .map(|score| (score.path.clone(), score))
+                .collect();
+        let normalized_scores: HashMap<String, f64> =
+            hub_score::compute_normalized_hub_scores(&graph, min_coupling)
+                .into_iter()
+                .map(|score| (score.path, score.hub_score))
+                .collect();
+
+        let mut files: Vec<_> = graph
+            .files
+            .iter()
+            .filter(|f| f.deleted_at_commit.is_none())
+            .collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        for file in files {
+            let score = scores.get(&file.path);
+            points.push(TimelinePoint {
+                window: index,
+                start: start.clone(),
+                end: end.clone(),
+                path: file.path.clone(),
+                hub_score: score.map(|s| s.hub_score).unwrap_or(0.0),
+                normalized_hub_score: normalized_scores.get(&file.path).copied().unwrap_or(0.0),
+                partner_count: score.map(|s| s.partner_count).unwrap_or(0),
+                avg_coupling: score.map(|s| s.avg_coupling).unwrap_or(0.0),
+                churn: file.additions + file.deletions,
+                commit_count: file.commit_count,
+            });
+        }
+    }
+
+    points
+}
+
+/// Groups timeline points into one series per file, ordered by path and window.
+pub fn series_by_file(points: &[TimelinePoint]) -> Vec<FileSeries<'_>> {
+    let mut by_path: BTreeMap<&str, Vec<&TimelinePoint>> = BTreeMap::new();
+    for point in points {
+        by_path.entry(point.path.as_str()).or_default().push(point);
+    }
+
+    by_path
+        .into_iter()
+        .map(|(path, points)| FileSeries { path, points })
+        .collect()
+}
+
+// Walks the history backwards, mapping every path to the name it ends up with. A path
+// that is reused after a rename only maps forward for commits before that rename.
+fn canonicalize_paths(history: &[CommitChanges]) -> Vec<CommitChanges> {
+    let mut final_name: HashMap<String, String> = HashMap::new();
+    let mut canonical = Vec::with_capacity(history.len());
+
+    let resolve = |final_name: &HashMap<String, String>, path: &str| {
+        final_name
+            .get(path)
+            .cloned()
+            .unwrap_or_else(|| path.to_string())
+    };
+
+    for commit in history.iter().rev() {
+        let mut commit = commit.clone();
+        for file in &mut commit.files {
+            file.path = resolve(&final_name, &file.path);
+        }
+
+        let resolved: Vec<(String, String)> = std::mem::take(&mut commit.renames)
+            .into_iter()
+            .map(|(old_path, new_path)| (old_path, resolve(&final_name, &new_path)))
+            .collect();
+        final_name.extend(resolved);
+
+        canonical.push(commit);
+    }
+
+    canonical.reverse();
+    canonical
+}
+
+type Window<'a> = (String, String, &'a [CommitChanges]);
+
+fn commit_windows(history: &[CommitChanges], size: usize, step: usize) -> Vec<Window<'_>> {
+    let mut windows = Vec::new();
+    let mut start = 0;
+
+    while start < history.len() {
+        let end = (start + size).min(history.len());
+        let commits = &history[start..end];
+        windows.push((
+            commits[0].commit_hash.clone(),
+            commits[commits.len() - 1].commit_hash.clone(),
+            commits,
+        ));
+        if end == history.len() {
+            break;
+        }
+        start += step;
+    }
+
+    windows
+}
+
+fn month_windows(history: &[CommitChanges], months: u32, step: u32) -> Vec<Window<'_>> {
+    let (Some(first), Some(last)) = (history.first(), history.last()) else {
+        return Vec::new();
+    };
+
+    let Some(mut start) = month_start(first.commit_time) else {
+        return Vec::new();
+    };
+
+    let mut windows = Vec::new();
+    while start.timestamp() <= last.commit_time {
+        let Some(end) = start.checked_add_months(Months::new(months)) else {
+            break;
+        };
+
+        let from = history.partition_point(|c| c.commit_time < start.timestamp());
+        let to = history.partition_point(|c| c.commit_time < end.timestamp());
+        windows.push((format_date(&start), format_date(&end), &history[from..to]));
+
+        if end.timestamp() > last.commit_time {
+            break;
+        }
+        match start.checked_add_months(Months::new(step)) {
+            Some(next) => start = next,
+            None => break,
+        }
+    }
+
+    windows
+}
+
+fn month_start(seconds: i64) -> Option<DateTime<Utc>> {
+    let time = DateTime::<Utc>::from_timestamp(seconds, 0)?;
+    time.date_naive()
+        .with_day(1)?
+        .and_hms_opt(0, 0, 0)
+        .map(|t| t.and_utc())
+}
+
+fn format_date(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}